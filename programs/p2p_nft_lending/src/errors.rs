@@ -44,6 +44,88 @@ pub enum ErrorCode {
     FeeAlreadyCollected,
     #[msg("Unable to perform action at this time")]
     InvalidLoanState,
+    #[msg("Invalid lending pool configuration")]
+    InvalidPoolConfig,
+    #[msg("Not enough liquidity in the lending pool")]
+    InsufficientLiquidity,
+    #[msg("Nft is not part of the pool's verified collection")]
+    IneligibleCollateral,
+    #[msg("Syndicate is no longer accepting funds")]
+    SyndicateClosed,
+    #[msg("Syndicate has not been fully funded")]
+    SyndicateNotFunded,
+    #[msg("Syndicate is still open for funding")]
+    SyndicateStillOpen,
+    #[msg("Listing price has changed")]
+    ListingPriceChanged,
+    #[msg("Floating rate loans need the benchmark rate account")]
+    MissingBenchmarkRate,
+    #[msg("No pricing tier covers the requested ltv and duration")]
+    NoPricingTier,
+    #[msg("Invalid pricing tiers")]
+    InvalidPricingTier,
+    #[msg("Invalid collection configuration")]
+    InvalidCollectionConfig,
+    #[msg("Collection is not accepted as collateral")]
+    CollectionNotAllowed,
+    #[msg("Loan exceeds the collection's risk limits")]
+    CollectionLimitExceeded,
+    #[msg("Collection config account is missing")]
+    MissingCollectionConfig,
+    #[msg("Invalid currency configuration")]
+    InvalidCurrencyConfig,
+    #[msg("Currency is not allowlisted")]
+    CurrencyNotAllowed,
+    #[msg("Loan exceeds the currency's limits")]
+    CurrencyLimitExceeded,
+    #[msg("Invalid fee model")]
+    InvalidFeeModel,
+    #[msg("Fee shares must match the owners and add up to 10000 bps")]
+    InvalidFeeShares,
+    #[msg("Nothing to claim from the insurance fund")]
+    NoInsuredShortfall,
+    #[msg("Referral fee above the platform's cap")]
+    ReferralFeeTooHigh,
+    #[msg("A transaction needs at least one instruction")]
+    EmptyTransaction,
+    #[msg("The transaction's execution delay hasn't elapsed")]
+    TimelockNotElapsed,
+    #[msg("The transaction can only be vetoed while its execution delay runs")]
+    VetoWindowClosed,
+    #[msg("Too many owners rejected the transaction to reach the threshold")]
+    TransactionFailed,
+    #[msg("Only the proposer can cancel a transaction that wasn't voted down")]
+    UnableToCancelTransaction,
+    #[msg("Proposal lifetime above the multisig's maximum")]
+    InvalidExpiry,
+    #[msg("The transaction has expired")]
+    TransactionExpired,
+    #[msg("The transaction hasn't expired")]
+    TransactionNotExpired,
+    #[msg("Roles must be unique, held by owners and have a threshold within their members")]
+    InvalidRoles,
+    #[msg("The proposer doesn't hold the role the transaction requires")]
+    MissingRole,
+    #[msg("New loans are paused")]
+    Paused,
+    #[msg("Vote weights must be positive, one per owner")]
+    InvalidVoteWeights,
+    #[msg("No valid owner signature over the approval message")]
+    InvalidSignatures,
+    #[msg("The signed approval has expired")]
+    ApprovalExpired,
+    #[msg("Too many spending limits for this mint")]
+    SpendingLimitsFull,
+    #[msg("The withdrawal exceeds what's left of the spender's limits")]
+    SpendingLimitExceeded,
+    #[msg("Pricing table account is missing")]
+    MissingPricingTable,
+    #[msg("Spread above the borrower's or the platform's maximum")]
+    SpreadTooHigh,
+    #[msg("The pool has defaulted collateral waiting to be settled")]
+    PoolSettlementPending,
+    #[msg("Open-term loans need a notice period of at least one slot")]
+    InvalidNoticePeriod,
 }
 impl From<ErrorCode> for ProgramError {
     fn from(e: ErrorCode) -> Self {
@@ -1,21 +1,26 @@
 use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Burn, CloseAccount, Mint, MintTo, SetAuthority, Token, TokenAccount, Transfer},
+    metadata::Metadata,
+    token_interface::{
+        spl_token_2022::instruction::AuthorityType, Burn, CloseAccount, Mint, MintTo,
+        SetAuthority, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 use solana_program::native_token::LAMPORTS_PER_SOL;
-use spl_token::instruction::AuthorityType;
 
 use crate::{
     errors::ErrorCode,
-    math::{Decimal, TrySub},
+    instructions::third_party::charge_late_payment,
     state::{
         loan::{Loan, LoanRequest, LoanStatus},
-        PlatformFees,
+        CurrencyConfig, PlatformFees, Referral,
     },
     utils::{
-        calculate_fees, complete_loan, compound_interest, max_amount_allowed,
-        uncompounded_interest, LOAN_REQUEST_STR, NFT_ESCROW_STR, PLATFORM_FEES_SEED_STR,
+        accrued_interest, calculate_fees, complete_loan, gross_transfer_amount,
+        load_collection_config, load_pricing_tier, loan_to_value, max_amount_allowed,
+        mint_decimals, uncompounded_interest, verified_collection, CURRENCY_CONFIG_STR,
+        LOAN_REQUEST_STR, NFT_ESCROW_STR, PLATFORM_FEES_SEED_STR, PRICING_TABLE_STR,
     },
 };
 
@@ -25,15 +30,71 @@ pub fn request_for_loan(
     requested_amount: u64,
     slot_duration: u64,
 ) -> Result<()> {
+    require!(!ctx.accounts.platform_fees.paused, ErrorCode::Paused);
+    // open-term requests are priced in the longest duration band
+    let priced_duration = if ctx.accounts.loan_request.notice_slots > 0 {
+        u64::MAX
+    } else {
+        slot_duration
+    };
+    let collection = verified_collection(&ctx.accounts.nft_metadata)?;
+    let pricing_tier = load_pricing_tier(
+        &ctx.accounts.pricing_table,
+        ctx.remaining_accounts,
+        ctx.program_id,
+        collection,
+        loan_to_value(requested_amount, nft_worth)?,
+        priced_duration,
+    )?;
+    ctx.accounts.currency_config.check_loan(requested_amount)?;
+    let (mut ltv, mut interest_rate, mut fee_percentage) = match pricing_tier {
+        Some(tier) => (tier.max_ltv, tier.interest_rate, tier.fee_percentage),
+        None => (
+            ctx.accounts.platform_fees.ltv,
+            ctx.accounts.platform_fees.interest_rate,
+            ctx.accounts.platform_fees.fee_percentage,
+        ),
+    };
+
+    // a registered collection's risk parameters replace the global ltv
+    let collection_config = match collection {
+        Some(collection) => {
+            load_collection_config(ctx.remaining_accounts, ctx.program_id, &collection)?
+        }
+        None => None,
+    };
+    if let Some(collection_config) = &collection_config {
+        require!(collection_config.allowed, ErrorCode::CollectionNotAllowed);
+        require!(
+            priced_duration <= collection_config.max_slot_duration
+                && requested_amount
+                    <= collection_config
+                        .exposure_cap
+                        .saturating_sub(collection_config.exposure),
+            ErrorCode::CollectionLimitExceeded
+        );
+        ltv = match pricing_tier {
+            Some(_) => std::cmp::min(ltv, collection_config.max_ltv),
+            None => collection_config.max_ltv,
+        };
+        interest_rate = interest_rate.clamp(
+            collection_config.min_interest_rate,
+            collection_config.max_interest_rate,
+        );
+    }
+
+    if let Some(fee_override) = ctx.accounts.currency_config.fee_override {
+        fee_percentage = fee_override;
+    }
+
     // couldn't use the "compound_interest" fn as it would in this case
     //exceeded maximum number of instructions allowed
     // so we use compound_interest instead
     // any difference between compounded_interest and uncompounded_interest
     // will be caught by the update loan bot
-    let compounded_interest =
-        uncompounded_interest(requested_amount, ctx.accounts.platform_fees.interest_rate)?;
+    let compounded_interest = uncompounded_interest(requested_amount, interest_rate)?;
 
-    let max_borrow = max_amount_allowed(nft_worth, ctx.accounts.platform_fees.ltv)?;
+    let max_borrow = max_amount_allowed(nft_worth, ltv)?;
 
     require!(
         compounded_interest <= max_borrow,
@@ -49,6 +110,12 @@ pub fn request_for_loan(
         slot_duration,
         ctx.accounts.borrow_nft_mint.key(),
     )?;
+    ctx.accounts.loan_request.ltv = ltv;
+    ctx.accounts.loan_request.interest_rate = interest_rate;
+    ctx.accounts.loan_request.fee_percentage = fee_percentage;
+    ctx.accounts.loan_request.collection = collection_config
+        .map(|collection_config| collection_config.collection)
+        .unwrap_or_default();
 
     let nft_amount = ctx.accounts.nft_token_account.amount;
     require!(nft_amount > 0u64, ErrorCode::InsufficientFunds);
@@ -73,17 +140,18 @@ pub fn request_for_loan(
     let outer = vec![inner.as_slice()];
 
     //move nft into escrow
-    anchor_spl::token::transfer(
+    anchor_spl::token_interface::transfer_checked(
         ctx.accounts
             .transfer_into_escrow_context()
             .with_signer(outer.as_slice()),
         1u64,
+        ctx.accounts.nft_mint.decimals,
     )
     .expect("transfer failed");
 
     //change mint authority to platform_fees
     assert_eq!(ctx.accounts.borrow_nft_mint.supply, 0);
-    anchor_spl::token::set_authority(
+    anchor_spl::token_interface::set_authority(
         ctx.accounts.set_mint_authority_context(),
         AuthorityType::MintTokens,
         Some(authority),
@@ -91,7 +159,7 @@ pub fn request_for_loan(
     .expect("set_authority failed");
 
     //mint borrow nft (an nft to represent borrower's collateral and borrowed amount)
-    anchor_spl::token::mint_to(ctx.accounts.mint_context().with_signer(outer.as_slice()), 1)?;
+    anchor_spl::token_interface::mint_to(ctx.accounts.mint_context().with_signer(outer.as_slice()), 1)?;
     emit!(LoanRequestMade {
         loan_request: ctx.accounts.loan_request.key(),
         nft_worth,
@@ -104,6 +172,54 @@ pub fn request_for_loan(
     Ok(())
 }
 
+///requests an open-term loan: no fixed duration, the lender can call it with `notice_slots` notice
+pub fn request_open_term_loan(
+    ctx: Context<LoanRequestContext>,
+    nft_worth: u64,
+    requested_amount: u64,
+    notice_slots: u64,
+) -> Result<()> {
+    require!(notice_slots > 0, ErrorCode::InvalidNoticePeriod);
+    // LoanRequest::init leaves notice_slots untouched
+    ctx.accounts.loan_request.notice_slots = notice_slots;
+    request_for_loan(ctx, nft_worth, requested_amount, 0)
+}
+
+///requests a loan a lender can also grant at a floating rate, the benchmark rate plus a spread
+/// of at most `max_spread`
+pub fn request_floating_loan(
+    ctx: Context<LoanRequestContext>,
+    nft_worth: u64,
+    requested_amount: u64,
+    slot_duration: u64,
+    max_spread: u32,
+) -> Result<()> {
+    require!(
+        max_spread <= ctx.accounts.platform_fees.max_spread,
+        ErrorCode::SpreadTooHigh
+    );
+    // LoanRequest::init leaves max_spread untouched
+    ctx.accounts.loan_request.max_spread = Some(max_spread);
+    request_for_loan(ctx, nft_worth, requested_amount, slot_duration)
+}
+
+///requests a loan through a frontend, which earns `referral.bps` of the platform fees
+pub fn request_for_loan_with_referral(
+    ctx: Context<LoanRequestContext>,
+    nft_worth: u64,
+    requested_amount: u64,
+    slot_duration: u64,
+    referral: Referral,
+) -> Result<()> {
+    require!(
+        referral.bps <= ctx.accounts.platform_fees.max_referral_bps,
+        ErrorCode::ReferralFeeTooHigh
+    );
+    // LoanRequest::init leaves referral untouched
+    ctx.accounts.loan_request.referral = Some(referral);
+    request_for_loan(ctx, nft_worth, requested_amount, slot_duration)
+}
+
 pub fn cancel_loan_request(ctx: Context<CancelRequestContext>) -> Result<()> {
     let (_authority, bump) =
         Pubkey::find_program_address(&[PLATFORM_FEES_SEED_STR.as_bytes()], ctx.program_id);
@@ -123,17 +239,18 @@ pub fn cancel_loan_request(ctx: Context<CancelRequestContext>) -> Result<()> {
             require!(nft_amount > 0u64, ErrorCode::InsufficientFunds);
 
             //burn borrow nft
-            anchor_spl::token::burn(ctx.accounts.burn_borrow_token_context(), 1)?;
+            anchor_spl::token_interface::burn(ctx.accounts.burn_borrow_token_context(), 1)?;
             //transfer back collateral
-            anchor_spl::token::transfer(
+            anchor_spl::token_interface::transfer_checked(
                 ctx.accounts
                     .transfer_from_escrow_context()
                     .with_signer(outer.as_slice()),
                 1,
+                ctx.accounts.nft_mint.decimals,
             )?;
 
             //close accounts
-            anchor_spl::token::close_account(
+            anchor_spl::token_interface::close_account(
                 ctx.accounts
                     .close_escrow_account_context()
                     .with_signer(outer.as_slice()),
@@ -174,20 +291,23 @@ pub fn borrower_withdraw_tokens(ctx: Context<BorrowerWithdrawTokenContext>) -> R
 
     let fee = calculate_fees(
         ctx.accounts.loan_request.requested_amount,
-        ctx.accounts.platform_fees.fee_percentage,
+        ctx.accounts.loan.fee_percentage,
     )
     .unwrap()
     .try_round_u64()
     .unwrap();
 
-    let withdrawal_amount ;
-    match LoanStatus::from(ctx.accounts.loan.status)? {
-        LoanStatus::Started =>  withdrawal_amount = ctx.accounts.loan_request.requested_amount - fee,
-        LoanStatus::Seize =>  withdrawal_amount = ctx.accounts.loan.paid_amount,
-        LoanStatus::Sold => withdrawal_amount = ctx.accounts.loan.paid_amount,
-        LoanStatus::Completed => withdrawal_amount = ctx.accounts.loan.paid_amount,
+    // what the borrower paid before defaulting is refunded once the lender (or pool) has been
+    // paid out, the loan is closed for good so it can't default again
+    let (withdrawal_amount, status) = match LoanStatus::from(ctx.accounts.loan.status)? {
+        LoanStatus::Started => (
+            ctx.accounts.loan_request.requested_amount - fee,
+            LoanStatus::TokensWithdrawn,
+        ),
+        LoanStatus::Seize => (ctx.accounts.loan.paid_amount, LoanStatus::Settled),
+        LoanStatus::Completed => (ctx.accounts.loan.paid_amount, LoanStatus::Settled),
         _ => return Err(ErrorCode::InvalidLoanState.into()),
-    }
+    };
    
 
     if ctx.accounts.loan_request.requested_token_mint == Pubkey::default() {
@@ -200,16 +320,23 @@ pub fn borrower_withdraw_tokens(ctx: Context<BorrowerWithdrawTokenContext>) -> R
         )
         .expect("transfer failed");
     } else {
-        // spl_token
-        anchor_spl::token::transfer(
+        // spl_token / token-2022
+        // escrow is owned by platform_fees, any transfer fee is borne by the borrower
+        let platform_fees_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
+        let bump = &[*platform_fees_bump.unwrap()][..];
+        let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
+        let outer = vec![inner.as_slice()];
+
+        anchor_spl::token_interface::transfer_checked(
             ctx.accounts
                 .transfer_spl_tokens_from_escrow_context()
                 .with_signer(outer.as_slice()),
             withdrawal_amount,
+            mint_decimals(&ctx.accounts.requested_token_mint)?,
         )
         .expect("transfer failed");
     }
-    ctx.accounts.loan.status = LoanStatus::TokensWithdrawn.to_code();
+    ctx.accounts.loan.status = status.to_code();
     Ok(())
 }
 
@@ -223,24 +350,19 @@ pub fn repay_loan(ctx: Context<RepayLoansContext>, amount: u64) -> Result<()> {
     let clock = Clock::get().unwrap();
     let current_slot = clock.slot;
 
-    let expected_loan_end_slot =
-        ctx.accounts.loan.start_slot + ctx.accounts.loan_request.slot_duration;
+    // repayable until the grace period after maturity runs out
+    let expected_loan_default_slot = ctx.accounts.loan.default_slot();
 
-    let slots_elapsed = current_slot - ctx.accounts.loan.last_updated_slot;
-
-    require!(current_slot < expected_loan_end_slot, ErrorCode::LoanEnded);
-
-    let compounded_so_far = compound_interest(
-        ctx.accounts.loan.requested_amount,
-        ctx.accounts.loan.interest_rate,
-        slots_elapsed,
-    )?;
-
-    let new_interest_accrued =
-        compounded_so_far.try_sub(Decimal::from(ctx.accounts.loan.requested_amount))?;
+    require!(current_slot < expected_loan_default_slot, ErrorCode::LoanEnded);
 
     //increase outstanding_debt to capture new interest accrued
-    ctx.accounts.loan.outstanding_debt += new_interest_accrued.try_round_u64()?;
+    ctx.accounts.loan.outstanding_debt += accrued_interest(
+        &ctx.accounts.loan,
+        current_slot,
+        ctx.remaining_accounts,
+        ctx.program_id,
+    )?;
+    charge_late_payment(&mut ctx.accounts.loan, current_slot)?;
 
     let amount_to_pay = std::cmp::min(ctx.accounts.loan.outstanding_debt, amount);
 
@@ -253,9 +375,11 @@ pub fn repay_loan(ctx: Context<RepayLoansContext>, amount: u64) -> Result<()> {
         )?;
     } else {
         //SPL - tokens
-        anchor_spl::token::transfer(
+        // borrower covers any token-2022 transfer fee so the escrow receives amount_to_pay
+        anchor_spl::token_interface::transfer_checked(
             ctx.accounts.transfer_spl_tokens_to_escrow_context(),
-            amount_to_pay,
+            gross_transfer_amount(&ctx.accounts.requested_token_mint, amount_to_pay)?,
+            mint_decimals(&ctx.accounts.requested_token_mint)?,
         )
         .expect("transfer failed");
     }
@@ -271,6 +395,11 @@ pub fn repay_loan(ctx: Context<RepayLoansContext>, amount: u64) -> Result<()> {
 }
 #[derive(Accounts)]
 pub struct RepayLoansContext<'info> {
+    /// CHECK: requested_token_mint is the mint for the requested token (Pubkey::default() for SOL)
+    #[account(
+        constraint=requested_token_mint.key() == loan_request.requested_token_mint.key()
+    )]
+    pub requested_token_mint: UncheckedAccount<'info>,
     /// CHECK: requested_token_account is the mint for the requested token Data is never read or written to
     #[account(mut)]
     pub requested_token_account: UncheckedAccount<'info>,
@@ -296,34 +425,46 @@ pub struct RepayLoansContext<'info> {
     #[account(
         mut,
         constraint=borrow_nft_mint.mint_authority == platform_fees.key().into(),
+        mint::token_program=position_token_program,
     )]
-    pub borrow_nft_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub nft_mint: Account<'info, Mint>,
+    pub borrow_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        mint::token_program=nft_token_program,
+    )]
+    pub nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        associated_token::mint=nft_mint,
-        associated_token::authority=borrower
+        token::mint=nft_mint,
+        token::authority=borrower,
+        token::token_program=nft_token_program,
     )]
-    pub nft_token_account: Account<'info, TokenAccount>,
+    pub nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        associated_token::mint=borrow_nft_mint,
-        associated_token::authority=borrower
+        token::mint=borrow_nft_mint,
+        token::authority=borrower,
+        token::token_program=position_token_program,
     )]
-    pub borrow_nft_token_account: Account<'info, TokenAccount>,
+    pub borrow_nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         seeds=[NFT_ESCROW_STR.as_bytes(), loan_request.key().as_ref()],
         bump,
         token::mint=nft_mint,
         token::authority=platform_fees,
+        token::token_program=nft_token_program,
     )]
-    pub nft_escrow: Account<'info, TokenAccount>,
+    pub nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
     pub borrower: Signer<'info>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    /// program of the requested token
+    pub token_program: Interface<'info, TokenInterface>,
+    /// program of the collateral nft (spl-token or token-2022)
+    pub nft_token_program: Interface<'info, TokenInterface>,
+    /// program of the borrow nft
+    pub position_token_program: Interface<'info, TokenInterface>,
 }
 impl<'info> RepayLoansContext<'info> {
     pub fn burn_borrow_token_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
@@ -332,31 +473,33 @@ impl<'info> RepayLoansContext<'info> {
             authority: self.borrower.to_account_info().clone(),
             mint: self.borrow_nft_mint.to_account_info().clone(),
         };
-        CpiContext::new(self.token_program.to_account_info().clone(), burn_accounts)
+        CpiContext::new(self.position_token_program.to_account_info().clone(), burn_accounts)
     }
     pub fn transfer_nft_from_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_accounts = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
             from: self.nft_escrow.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
             to: self.nft_token_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
         CpiContext::new(
-            self.token_program.to_account_info().clone(),
+            self.nft_token_program.to_account_info().clone(),
             transfer_accounts,
         )
     }
 
     pub fn transfer_spl_tokens_to_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             to: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
             from: self.requested_token_account.to_account_info().clone(),
             authority: self.borrower.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
     pub fn transfer_lamports_to_escrow_context(
         &self,
@@ -370,6 +513,11 @@ impl<'info> RepayLoansContext<'info> {
 }
 #[derive(Accounts)]
 pub struct BorrowerWithdrawTokenContext<'info> {
+    /// CHECK: requested_token_mint is the mint for the requested token (Pubkey::default() for SOL)
+    #[account(
+        constraint=requested_token_mint.key() == loan_request.requested_token_mint.key()
+    )]
+    requested_token_mint: UncheckedAccount<'info>,
     /// CHECK: requested_token_account is the mint for the requested token Data is never read or written to
     #[account(mut)]
     requested_token_account: UncheckedAccount<'info>,
@@ -395,19 +543,20 @@ pub struct BorrowerWithdrawTokenContext<'info> {
     #[account(mut)]
     borrower: Signer<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+    token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
 }
 impl<'info> BorrowerWithdrawTokenContext<'info> {
     pub fn transfer_spl_tokens_from_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             from: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
             to: self.requested_token_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
     pub fn transfer_lamports_from_escrow_context(
         &self,
@@ -422,16 +571,35 @@ impl<'info> BorrowerWithdrawTokenContext<'info> {
 
 #[derive(Accounts)]
 pub struct LoanRequestContext<'info> {
-    #[account(mut)]
-    nft_mint: Account<'info, Mint>,
     #[account(
         mut,
-        associated_token::mint=nft_mint,
-        associated_token::authority=borrower
+        mint::token_program=nft_token_program,
     )]
-    nft_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    borrow_nft_mint: Account<'info, Mint>,
+    nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint=nft_mint,
+        token::authority=borrower,
+        token::token_program=nft_token_program,
+    )]
+    nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: the nft's metaplex metadata, read whenever it exists. The collection's risk
+    /// parameters apply whenever it has a verified collection
+    #[account(
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            nft_mint.key().as_ref(),
+        ],
+        seeds::program = token_metadata_program.key(),
+        bump,
+    )]
+    nft_metadata: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        mint::token_program=token_program,
+    )]
+    borrow_nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         init,
         seeds = [LOAN_REQUEST_STR.as_bytes(), borrow_nft_mint.key().as_ref()],
@@ -444,12 +612,18 @@ pub struct LoanRequestContext<'info> {
         // init,
         //transfer ownership to loan_request when its time to burn
         mut,
-        associated_token::mint=borrow_nft_mint,
-        associated_token::authority=borrower
+        token::mint=borrow_nft_mint,
+        token::authority=borrower,
+        token::token_program=token_program,
     )]
-    borrow_nft_token_account: Account<'info, TokenAccount>,
+    borrow_nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
     /// CHECK: requested_token_mint is the mint for the requested token Data is never read or written to
     requested_token_mint: UncheckedAccount<'info>,
+    #[account(
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), requested_token_mint.key().as_ref()],
+        bump = currency_config.bump,
+    )]
+    currency_config: Box<Account<'info, CurrencyConfig>>,
 
     #[account(
         mut,
@@ -459,6 +633,12 @@ pub struct LoanRequestContext<'info> {
         bump,
     )]
     platform_fees: Box<Account<'info, PlatformFees>>,
+    /// CHECK: the global pricing table, read whenever it has been created
+    #[account(
+        seeds = [PRICING_TABLE_STR.as_bytes(), Pubkey::default().as_ref()],
+        bump,
+    )]
+    pricing_table: UncheckedAccount<'info>,
     #[account(
         // mut,
         init,
@@ -467,25 +647,33 @@ pub struct LoanRequestContext<'info> {
         bump,
         token::mint=nft_mint,
         token::authority=platform_fees,
+        token::token_program=nft_token_program,
     )]
-    nft_escrow: Account<'info, TokenAccount>,
+    nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
     borrower: Signer<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+    /// program of the borrow nft
+    token_program: Interface<'info, TokenInterface>,
+    /// program of the collateral nft (spl-token or token-2022)
+    nft_token_program: Interface<'info, TokenInterface>,
+    token_metadata_program: Program<'info, Metadata>,
     associated_token_program: Program<'info, AssociatedToken>,
     rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> LoanRequestContext<'info> {
-    pub fn transfer_into_escrow_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_accounts = Transfer {
+    pub fn transfer_into_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
             from: self.nft_token_account.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
             to: self.nft_escrow.to_account_info().clone(),
             authority: self.borrower.to_account_info().clone(),
         };
         CpiContext::new(
-            self.token_program.to_account_info().clone(),
+            self.nft_token_program.to_account_info().clone(),
             transfer_accounts,
         )
     }
@@ -510,19 +698,24 @@ impl<'info> LoanRequestContext<'info> {
 
 #[derive(Accounts)]
 pub struct CancelRequestContext<'info> {
-    #[account(mut)]
-    nft_mint: Account<'info, Mint>,
     #[account(
         mut,
-        associated_token::mint=nft_mint,
-        associated_token::authority=borrower
+        mint::token_program=nft_token_program,
     )]
-    nft_token_account: Account<'info, TokenAccount>,
+    nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint=nft_mint,
+        token::authority=borrower,
+        token::token_program=nft_token_program,
+    )]
+    nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         constraint=borrow_nft_mint.mint_authority == platform_fees.key().into(),
+        mint::token_program=token_program,
     )]
-    borrow_nft_mint: Account<'info, Mint>,
+    borrow_nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
         seeds = [LOAN_REQUEST_STR.as_bytes(), borrow_nft_mint.key().as_ref()],
@@ -532,10 +725,11 @@ pub struct CancelRequestContext<'info> {
     loan_request: Box<Account<'info, LoanRequest>>,
     #[account(
         mut,
-        associated_token::mint=borrow_nft_mint,
-        associated_token::authority=borrower
+        token::mint=borrow_nft_mint,
+        token::authority=borrower,
+        token::token_program=token_program,
     )]
-    borrow_nft_token_account: Account<'info, TokenAccount>,
+    borrow_nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
     /// CHECK: requested_token_mint is the mint for the requested token Data is never read or written to
     requested_token_mint: UncheckedAccount<'info>,
 
@@ -553,12 +747,16 @@ pub struct CancelRequestContext<'info> {
           bump,
           token::mint=nft_mint,
           token::authority=platform_fees,
+          token::token_program=nft_token_program,
       )]
-    nft_escrow: Account<'info, TokenAccount>,
+    nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
     borrower: Signer<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+    /// program of the borrow nft
+    token_program: Interface<'info, TokenInterface>,
+    /// program of the collateral nft (spl-token or token-2022)
+    nft_token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     rent: Sysvar<'info, Rent>,
 }
@@ -572,14 +770,17 @@ impl<'info> CancelRequestContext<'info> {
         };
         CpiContext::new(self.token_program.to_account_info().clone(), burn_accounts)
     }
-    pub fn transfer_from_escrow_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_accounts = Transfer {
+    pub fn transfer_from_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
             from: self.nft_escrow.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
             to: self.nft_token_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
         CpiContext::new(
-            self.token_program.to_account_info().clone(),
+            self.nft_token_program.to_account_info().clone(),
             transfer_accounts,
         )
     }
@@ -591,7 +792,7 @@ impl<'info> CancelRequestContext<'info> {
             destination: self.borrower.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
-        CpiContext::new(self.token_program.to_account_info().clone(), close_accounts)
+        CpiContext::new(self.nft_token_program.to_account_info().clone(), close_accounts)
     }
 }
 
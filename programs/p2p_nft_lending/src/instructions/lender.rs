@@ -1,24 +1,62 @@
 use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{
     associated_token::{AssociatedToken, Create},
-    token::{Burn, Mint, MintTo, SetAuthority, Token, TokenAccount, Transfer},
+    token_interface::{
+        spl_token_2022::instruction::AuthorityType, Burn, Mint, MintTo, SetAuthority,
+        TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 use solana_program::native_token::LAMPORTS_PER_SOL;
-use spl_token::instruction::AuthorityType;
 
 use crate::{
     errors::ErrorCode,
     state::{
         loan::{GrantLoan, Loan, LoanRequest},
-        LoanFee, LoanStatus, Multisig, PlatformFees,
+        CurrencyConfig, LoanFee, LoanStatus, Multisig, PlatformFees, Referral,
     },
     utils::{
-        calculate_fees, GRANT_LOAN_STR, LOAN_FEE_STR, LOAN_STR, MULTISIG_SEED_STR, NFT_ESCROW_STR,
+        calculate_fees, create_interest_fee, fee_escrow_address, gross_transfer_amount, load_benchmark_rate,
+        mint_decimals, record_collection_exposure, referral_shares, CURRENCY_CONFIG_STR, GRANT_LOAN_STR,
+        INTEREST_FEE_STR, LOAN_FEE_STR, LOAN_STR, MULTISIG_SEED_STR, NFT_ESCROW_STR,
         PLATFORM_FEES_SEED_STR,
     },
 };
 
 pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
+    grant(ctx, None, None)
+}
+
+/// grants a loan through a frontend, which earns `referral.bps` of the platform fees
+pub fn grant_loan_with_referral(ctx: Context<GrantLoanContext>, referral: Referral) -> Result<()> {
+    require!(
+        referral.bps <= ctx.accounts.platform_fees.max_referral_bps,
+        ErrorCode::ReferralFeeTooHigh
+    );
+    grant(ctx, None, Some(referral))
+}
+
+/// grants a loan accruing at the benchmark rate + `spread` instead of the platform rate,
+/// the benchmark rate account is expected as the first remaining account. Only requests
+/// accepting a floating rate can be granted one, up to the spread the borrower agreed to
+pub fn grant_floating_loan(ctx: Context<GrantLoanContext>, spread: u32) -> Result<()> {
+    let max_spread = ctx
+        .accounts
+        .loan_request
+        .max_spread
+        .ok_or(ErrorCode::SpreadTooHigh)?;
+    require!(
+        spread <= max_spread && spread <= ctx.accounts.platform_fees.max_spread,
+        ErrorCode::SpreadTooHigh
+    );
+    grant(ctx, Some(spread), None)
+}
+
+fn grant(
+    ctx: Context<GrantLoanContext>,
+    spread: Option<u32>,
+    referral: Option<Referral>,
+) -> Result<()> {
+    require!(!ctx.accounts.platform_fees.paused, ErrorCode::Paused);
     let loan_token_escrow_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
     let bump = &[*loan_token_escrow_bump.unwrap()][..];
     let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
@@ -27,7 +65,7 @@ pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
 
     let fee = calculate_fees(
         ctx.accounts.loan_request.requested_amount,
-        ctx.accounts.platform_fees.fee_percentage,
+        ctx.accounts.loan_request.fee_percentage,
     )
     .unwrap()
     .try_round_u64()
@@ -36,7 +74,8 @@ pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
     let remainder = ctx.accounts.loan_request.requested_amount - fee;
     assert_eq!(fee + remainder, ctx.accounts.loan_request.requested_amount);
     // checks that could not be done using anchor constraints
-    assert!(fee > 0);
+    // origination fee can be switched off in favour of a fee on interest
+    assert!(fee > 0 || ctx.accounts.loan_request.fee_percentage == 0);
     if ctx.accounts.requested_token_mint.key() == Pubkey::default() {
         //we're using lamports
         require!(
@@ -61,36 +100,40 @@ pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
             ErrorCode::InvalidAccount
         );
 
-        anchor_spl::associated_token::create(
+        anchor_spl::associated_token::create_idempotent(
             ctx.accounts
                 .create_loan_token_escrow_context()
                 .with_signer(outer.as_slice()),
         )?;
-        anchor_spl::associated_token::create(
+        anchor_spl::associated_token::create_idempotent(
             ctx.accounts
                 .create_loan_fee_token_escrow_context()
                 .with_signer(outer.as_slice()),
         )?;
 
         // complete transfer
-        anchor_spl::token::transfer(
+        // lender covers any token-2022 transfer fee so both escrows receive the exact amounts
+        let decimals = mint_decimals(&ctx.accounts.requested_token_mint)?;
+        anchor_spl::token_interface::transfer_checked(
             ctx.accounts
                 .transfer_spl_tokens_to_loan_fee_escrow_context()
                 .with_signer(outer.as_slice()),
-            fee,
+            gross_transfer_amount(&ctx.accounts.requested_token_mint, fee)?,
+            decimals,
         )
         .expect("transfer failed");
-        anchor_spl::token::transfer(
+        anchor_spl::token_interface::transfer_checked(
             ctx.accounts
                 .transfer_spl_tokens_to_escrow_context()
                 .with_signer(outer.as_slice()),
-            remainder,
+            gross_transfer_amount(&ctx.accounts.requested_token_mint, remainder)?,
+            decimals,
         )
         .expect("transfer failed");
     }
 
     //grant program authority to mint
-    anchor_spl::token::set_authority(
+    anchor_spl::token_interface::set_authority(
         ctx.accounts.set_mint_authority_context(),
         AuthorityType::MintTokens,
         Some(ctx.accounts.platform_fees.key()),
@@ -98,7 +141,7 @@ pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
     .expect("set_authority failed");
 
     //mint lend nft (an nft to represent lender's collateral and amount given out as loan)
-    anchor_spl::token::mint_to(
+    anchor_spl::token_interface::mint_to(
         ctx.accounts
             .mint_loan_nft_context()
             .with_signer(outer.as_slice()),
@@ -121,14 +164,48 @@ pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
         ctx.accounts.loan_request.borrow_nft_mint.key(),
         ctx.accounts.lend_nft_mint.key(),
         ctx.accounts.requested_token_mint.key(),
-        ctx.accounts.platform_fees.ltv,
-        ctx.accounts.platform_fees.fee_percentage,
-        ctx.accounts.platform_fees.interest_rate,
+        ctx.accounts.loan_request.ltv,
+        ctx.accounts.loan_request.fee_percentage,
+        ctx.accounts.loan_request.interest_rate,
         ctx.accounts.loan_request.nft_worth,
         ctx.accounts.loan_request.requested_amount,
         ctx.accounts.loan_request.slot_duration,
         clock.slot,
     )?;
+    ctx.accounts.loan.notice_slots = ctx.accounts.loan_request.notice_slots;
+    ctx.accounts.loan.interest_fee_percentage = ctx.accounts.platform_fees.interest_fee_percentage;
+    ctx.accounts.loan.set_late_terms(
+        ctx.accounts.platform_fees.grace_slots,
+        ctx.accounts.platform_fees.penalty_rate,
+        ctx.accounts.platform_fees.late_fee,
+    );
+    record_collection_exposure(
+        ctx.remaining_accounts,
+        ctx.program_id,
+        &ctx.accounts.loan_request.collection,
+        &mut ctx.accounts.loan,
+    )?;
+    ctx.accounts
+        .currency_config
+        .add_debt(ctx.accounts.loan.requested_amount)?;
+    ctx.accounts.loan.currency_debt = ctx.accounts.loan.requested_amount;
+    if let Some(spread) = spread {
+        let benchmark_rate = load_benchmark_rate(ctx.remaining_accounts, ctx.program_id)?;
+        // interest_rate only records the rate at origination, accrual follows the benchmark
+        ctx.accounts.loan.interest_rate = benchmark_rate
+            .current_rate()
+            .checked_add(spread)
+            .ok_or(ErrorCode::MathOverflow)?;
+        ctx.accounts.loan.spread = Some(spread);
+    }
+    // referrers claim their cut of every fee on the loan like any other fee recipient
+    ctx.accounts.loan.referrals = ctx
+        .accounts
+        .loan_request
+        .referral
+        .into_iter()
+        .chain(referral)
+        .collect();
     //init loan fee
     LoanFee::init(
         &mut ctx.accounts.loan_fee,
@@ -136,18 +213,13 @@ pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
         ctx.accounts.requested_token_mint.key(),
         ctx.accounts.loan.key(),
         ctx.accounts.loan_fee_escrow.key(),
-        ctx.accounts.multisig.owners.clone(),
+        referral_shares(
+            ctx.accounts.multisig.fee_shares(),
+            &ctx.accounts.loan.referrals,
+        ),
     );
     ctx.accounts.loan_request.loan = Some(ctx.accounts.loan.key());
 
-    if ctx.accounts.platform_fees.uncollected_fees.len() == 50 {
-        return Err(ErrorCode::FeesListFull.into());
-    }
-    ctx.accounts
-        .platform_fees
-        .uncollected_fees
-        .push(ctx.accounts.loan_fee.key());
-
     emit!(LoanGranted {
         nft_mint: ctx.accounts.loan_request.nft_mint,
         loan_request: ctx.accounts.loan_request.key(),
@@ -192,34 +264,139 @@ pub fn lender_withdraw_tokens(ctx: Context<LenderWithdrawTokenContext>) -> Resul
         LoanStatus::Sold => withdrawal_amount = ctx.accounts.loan.nft_worth,
         _ => return Err(ErrorCode::InvalidLoanState.into()),
     }
-    if ctx.accounts.grant_loan_req.requested_token_mint == Pubkey::default() {
-        // lamports
 
+    // the platform's cut of the interest collected, sale proceeds above the debt are not interest
+    let total_debt = ctx.accounts.loan.paid_amount + ctx.accounts.loan.outstanding_debt;
+    let interest_collected = std::cmp::min(withdrawal_amount, total_debt)
+        .saturating_sub(ctx.accounts.loan.requested_amount);
+    let interest_fee = calculate_fees(
+        interest_collected,
+        ctx.accounts.loan.interest_fee_percentage,
+    )?
+    .try_round_u64()?;
+    // the lender's share covers any token-2022 transfer fee so the fee escrow receives the
+    // exact interest_fee that is booked below
+    let fee_transfer_amount = if ctx.accounts.grant_loan_req.requested_token_mint
+        == Pubkey::default()
+        || interest_fee == 0
+    {
+        interest_fee
+    } else {
+        gross_transfer_amount(&ctx.accounts.requested_token_mint, interest_fee)?
+    };
+    let lender_amount = withdrawal_amount
+        .checked_sub(fee_transfer_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        ctx.accounts.loan_fee_escrow.key()
+            == fee_escrow_address(
+                &ctx.accounts.multisig.key(),
+                &ctx.accounts.requested_token_mint.key(),
+                &ctx.accounts.token_program.key(),
+                ctx.program_id,
+            ),
+        ErrorCode::InvalidAccount
+    );
 
+    if ctx.accounts.grant_loan_req.requested_token_mint == Pubkey::default() {
+        // lamports
         system_program::transfer(
             ctx.accounts
                 .transfer_lamports_from_escrow_context()
                 .with_signer(outer.as_slice()),
-            withdrawal_amount * LAMPORTS_PER_SOL,
+            lender_amount * LAMPORTS_PER_SOL,
         )
         .expect("transfer failed");
+        if interest_fee > 0 {
+            system_program::transfer(
+                ctx.accounts
+                    .transfer_lamports_to_fee_escrow_context()
+                    .with_signer(outer.as_slice()),
+                interest_fee * LAMPORTS_PER_SOL,
+            )?;
+        }
     } else {
-        // spl_token
-        anchor_spl::token::transfer(
+        // spl_token / token-2022
+        // escrow is owned by platform_fees, any transfer fee is borne by the lender
+        let platform_fees_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
+        let bump = &[*platform_fees_bump.unwrap()][..];
+        let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
+        let outer = vec![inner.as_slice()];
+        let decimals = mint_decimals(&ctx.accounts.requested_token_mint)?;
+
+        anchor_spl::token_interface::transfer_checked(
             ctx.accounts
                 .transfer_spl_tokens_from_escrow_context()
                 .with_signer(outer.as_slice()),
-            withdrawal_amount,
+            lender_amount,
+            decimals,
         )
         .expect("transfer failed");
+        if interest_fee > 0 {
+            anchor_spl::token_interface::transfer_checked(
+                ctx.accounts
+                    .transfer_spl_tokens_to_fee_escrow_context()
+                    .with_signer(outer.as_slice()),
+                fee_transfer_amount,
+                decimals,
+            )?;
+        }
     }
-    anchor_spl::token::burn(ctx.accounts.burn_lend_nft_context(), 1)?;
+
+    // booked like the origination fee so multisig owners withdraw it through withdraw_fee
+    if interest_fee > 0 {
+        let mut loan_fee = create_interest_fee(
+            &ctx.accounts.interest_fee,
+            &ctx.accounts.lender,
+            &ctx.accounts.system_program,
+            &ctx.accounts.loan.key(),
+            *ctx.bumps.get("interest_fee").unwrap(),
+        )?;
+        LoanFee::init(
+            &mut loan_fee,
+            interest_fee,
+            ctx.accounts.requested_token_mint.key(),
+            ctx.accounts.loan.key(),
+            ctx.accounts.loan_fee_escrow.key(),
+            referral_shares(
+                ctx.accounts.multisig.fee_shares(),
+                &ctx.accounts.loan.referrals,
+            ),
+        );
+        loan_fee.exit(ctx.program_id)?;
+    }
+    anchor_spl::token_interface::burn(ctx.accounts.burn_lend_nft_context(), 1)?;
 
     ctx.accounts.loan.status = LoanStatus::Completed.to_code();
 
     Ok(())
 }
 
+///lend nft holder calls an open-term loan, the borrower has `notice_slots` to repay
+pub fn call_loan(ctx: Context<CallLoanContext>) -> Result<()> {
+    require!(
+        ctx.accounts.loan.is_open_term() && ctx.accounts.loan.call_slot.is_none(),
+        ErrorCode::InvalidLoanState
+    );
+    require!(
+        matches!(
+            LoanStatus::from(ctx.accounts.loan.status)?,
+            LoanStatus::Started | LoanStatus::TokensWithdrawn
+        ),
+        ErrorCode::InvalidLoanState
+    );
+
+    let clock = Clock::get()?;
+    ctx.accounts.loan.call_slot = Some(clock.slot);
+
+    emit!(LoanCalled {
+        loan: ctx.accounts.loan.key(),
+        call_slot: clock.slot,
+        due_slot: ctx.accounts.loan.end_slot(),
+    });
+    Ok(())
+}
+
 pub fn seize_nft(ctx: Context<SeizeNftContext>) -> Result<()> {
     require!(
         LoanStatus::from(ctx.accounts.loan.status)
@@ -237,15 +414,16 @@ pub fn seize_nft(ctx: Context<SeizeNftContext>) -> Result<()> {
     let outer = vec![inner.as_slice()];
 
     // transfer nft to lender
-    anchor_spl::token::transfer(
+    anchor_spl::token_interface::transfer_checked(
         ctx.accounts
             .transfer_nft_from_escrow_context()
             .with_signer(outer.as_slice()),
         1,
+        ctx.accounts.nft_mint.decimals,
     )?;
 
     //burn lend nft
-    anchor_spl::token::burn(ctx.accounts.burn_lend_token_context(), 1)?;
+    anchor_spl::token_interface::burn(ctx.accounts.burn_lend_token_context(), 1)?;
 
     //set state to Seize
     ctx.accounts.loan.status = LoanStatus::Seize.to_code();
@@ -264,19 +442,36 @@ pub fn sell_nft(ctx: Context<SellNftContext>) -> Result<()> {
     Ok(())
 }
 
+#[derive(Accounts)]
+pub struct CallLoanContext<'info> {
+    #[account(
+        constraint=loan.lend_nft_mint == lend_nft_mint.key()
+    )]
+    lend_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        token::mint=lend_nft_mint,
+        token::authority=lender,
+        constraint=lend_nft_account.amount == 1 @ ErrorCode::InvalidAccount,
+    )]
+    lend_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    loan: Box<Account<'info, Loan>>,
+    pub lender: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct SellNftContext<'info> {
     #[account(
         constraint=loan.lend_nft_mint == lend_nft_mint.key()
     )]
-    lend_nft_mint: Account<'info, Mint>,
+    lend_nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        associated_token::mint=lend_nft_mint,
-        associated_token::authority=lender,
+        token::mint=lend_nft_mint,
+        token::authority=lender,
         // close = lender
     )]
-    lend_nft_account: Account<'info, TokenAccount>,
+    lend_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
     loan: Box<Account<'info, Loan>>,
     #[account(mut)]
@@ -284,22 +479,30 @@ pub struct SellNftContext<'info> {
 }
 #[derive(Accounts)]
 pub struct SeizeNftContext<'info> {
-    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mint::token_program=nft_token_program,
+    )]
+    pub nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        associated_token::mint=nft_mint,
-        associated_token::authority=lender
+        token::mint=nft_mint,
+        token::authority=lender,
+        token::token_program=nft_token_program,
     )]
-    pub lender_nft_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    lend_nft_mint: Account<'info, Mint>,
+    pub lender_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        mint::token_program=token_program,
+    )]
+    lend_nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        associated_token::mint=lend_nft_mint,
-        associated_token::authority=lender,
+        token::mint=lend_nft_mint,
+        token::authority=lender,
+        token::token_program=token_program,
         // close = lender
     )]
-    lend_nft_account: Account<'info, TokenAccount>,
+    lend_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         seeds = [
@@ -314,8 +517,9 @@ pub struct SeizeNftContext<'info> {
         bump,
         token::mint=nft_mint,
         token::authority=platform_fees,
+        token::token_program=nft_token_program,
     )]
-    pub nft_escrow: Account<'info, TokenAccount>,
+    pub nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         seeds = [GRANT_LOAN_STR.as_bytes(), lend_nft_mint.key().as_ref()],
@@ -329,7 +533,10 @@ pub struct SeizeNftContext<'info> {
     #[account(mut)]
     pub lender: Signer<'info>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    /// program of the lend nft
+    pub token_program: Interface<'info, TokenInterface>,
+    /// program of the collateral nft (spl-token or token-2022)
+    pub nft_token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> SeizeNftContext<'info> {
@@ -343,28 +550,33 @@ impl<'info> SeizeNftContext<'info> {
     }
     pub fn transfer_nft_from_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_accounts = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
             from: self.nft_escrow.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
             to: self.lender_nft_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
         CpiContext::new(
-            self.token_program.to_account_info().clone(),
+            self.nft_token_program.to_account_info().clone(),
             transfer_accounts,
         )
     }
 }
 #[derive(Accounts)]
 pub struct LenderWithdrawTokenContext<'info> {
-    #[account(mut)]
-    lend_nft_mint: Account<'info, Mint>,
     #[account(
         mut,
-        associated_token::mint=lend_nft_mint,
-        associated_token::authority=lender
+        mint::token_program=position_token_program,
+    )]
+    lend_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint=lend_nft_mint,
+        token::authority=lender,
+        token::token_program=position_token_program,
     )]
-    lend_nft_account: Account<'info, TokenAccount>,
+    lend_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
     /// CHECK: requested_token_mint is the mint for the requested token Data is never read or written to
     #[account(
         constraint=requested_token_mint.key() == grant_loan_req.requested_token_mint.key()
@@ -403,24 +615,58 @@ pub struct LenderWithdrawTokenContext<'info> {
     /// CHECK: loan_token_escrow is the mint for the requested token Data is never read or written to
     #[account(mut)]
     loan_token_escrow: UncheckedAccount<'info>,
+    /// CHECK: the loan's interest fee, a LoanFee created in the handler only when there is one
+    #[account(
+        mut,
+        seeds = [INTEREST_FEE_STR.as_bytes(), loan.key().as_ref()],
+        bump,
+    )]
+    interest_fee: UncheckedAccount<'info>,
+    /// CHECK: multisig fee escrow PDA for SOL, the multisig's token account otherwise, checked in the handler
+    #[account(mut)]
+    loan_fee_escrow: UncheckedAccount<'info>,
     #[account(mut)]
     lender: Signer<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+    /// program of the requested token
+    token_program: Interface<'info, TokenInterface>,
+    /// program of the lend nft
+    position_token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> LenderWithdrawTokenContext<'info> {
+    pub fn transfer_spl_tokens_to_fee_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.loan_fee_escrow.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_lamports_to_fee_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let transfer_acct = system_program::Transfer {
+            from: self.loan_token_escrow.to_account_info().clone(),
+            to: self.loan_fee_escrow.to_account_info().clone(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+    }
     pub fn transfer_spl_tokens_from_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             from: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
             to: self.requested_token_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
     pub fn transfer_lamports_from_escrow_context(
         &self,
@@ -437,29 +683,41 @@ impl<'info> LenderWithdrawTokenContext<'info> {
             authority: self.lender.to_account_info().clone(),
             mint: self.lend_nft_mint.to_account_info().clone(),
         };
-        CpiContext::new(self.token_program.to_account_info().clone(), burn_accounts)
+        CpiContext::new(self.position_token_program.to_account_info().clone(), burn_accounts)
     }
 }
 #[derive(Accounts)]
 pub struct GrantLoanContext<'info> {
-    #[account(mut)]
-    lend_nft_mint: Account<'info, Mint>,
     #[account(
         mut,
-        associated_token::mint=lend_nft_mint,
-        associated_token::authority=lender
+        mint::token_program=position_token_program,
     )]
-    lend_nft_account: Account<'info, TokenAccount>,
+    lend_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint=lend_nft_mint,
+        token::authority=lender,
+        token::token_program=position_token_program,
+    )]
+    lend_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
     /// CHECK: requested_token_mint is the mint for the requested token Data is never read or written to
     #[account(
         constraint=requested_token_mint.key() == loan_request.requested_token_mint.key()
     )]
     requested_token_mint: UncheckedAccount<'info>, //could be Pubkey::Default()
+    #[account(
+        mut,
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), requested_token_mint.key().as_ref()],
+        bump = currency_config.bump,
+    )]
+    currency_config: Box<Account<'info, CurrencyConfig>>,
     /// CHECK: requested_token_account is the mint for the requested token Data is never read or written to
     #[account(mut)]
     requested_token_account: UncheckedAccount<'info>, //could be Pubkey::Default()
-    // #[account(
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint=loan_request.loan.is_none() @ ErrorCode::InvalidLoanState,
+    )]
     loan_request: Box<Account<'info, LoanRequest>>,
     #[account(
         init,
@@ -507,7 +765,10 @@ pub struct GrantLoanContext<'info> {
     #[account(mut)]
     lender: Signer<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+    /// program of the requested token
+    token_program: Interface<'info, TokenInterface>,
+    /// program of the lend nft
+    position_token_program: Interface<'info, TokenInterface>,
     associated_token_program: Program<'info, AssociatedToken>,
     rent: Sysvar<'info, Rent>,
 }
@@ -523,7 +784,6 @@ impl<'info> GrantLoanContext<'info> {
             mint: self.requested_token_mint.to_account_info().clone(),
             system_program: self.system_program.to_account_info().clone(),
             token_program: self.token_program.to_account_info().clone(),
-            rent: self.rent.to_account_info().clone(),
         };
 
         CpiContext::new(
@@ -539,7 +799,6 @@ impl<'info> GrantLoanContext<'info> {
             mint: self.requested_token_mint.to_account_info().clone(),
             system_program: self.system_program.to_account_info().clone(),
             token_program: self.token_program.to_account_info().clone(),
-            rent: self.rent.to_account_info().clone(),
         };
 
         CpiContext::new(
@@ -549,23 +808,25 @@ impl<'info> GrantLoanContext<'info> {
     }
     pub fn transfer_spl_tokens_to_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             from: self.requested_token_account.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
             to: self.loan_token_escrow.to_account_info().clone(),
             authority: self.lender.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
     pub fn transfer_spl_tokens_to_loan_fee_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             from: self.requested_token_account.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
             to: self.loan_fee_escrow.to_account_info().clone(),
             authority: self.lender.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
     pub fn transfer_lamports_to_escrow_context(
         &self,
@@ -590,7 +851,7 @@ impl<'info> GrantLoanContext<'info> {
             account_or_mint: self.lend_nft_mint.to_account_info().clone(),
             current_authority: self.lender.to_account_info().clone(),
         };
-        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+        CpiContext::new(self.position_token_program.to_account_info().clone(), cpi_accounts)
     }
     pub fn mint_loan_nft_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
         let cpi_accounts = MintTo {
@@ -598,7 +859,7 @@ impl<'info> GrantLoanContext<'info> {
             to: self.lend_nft_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
-        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+        CpiContext::new(self.position_token_program.to_account_info().clone(), cpi_accounts)
     }
 }
 
@@ -611,3 +872,10 @@ pub struct LoanGranted {
     pub duration: u64,
     pub lend_nft_mint: Pubkey,
 }
+
+#[event]
+pub struct LoanCalled {
+    pub loan: Pubkey,
+    pub call_slot: u64,
+    pub due_slot: u64,
+}
@@ -0,0 +1,410 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token_interface::{
+    CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+
+use crate::{
+    errors::ErrorCode,
+    state::{
+        loan::{Loan, LoanStatus},
+        PositionListing,
+    },
+    utils::{gross_transfer_amount, mint_decimals, POSITION_ESCROW_STR, POSITION_LISTING_STR},
+};
+
+///holder of a lend or borrow nft lists it for sale, the nft is escrowed until bought or cancelled
+pub fn list_position(ctx: Context<ListPositionContext>, price: u64) -> Result<()> {
+    require!(price > 0, ErrorCode::InsufficientFunds);
+    require!(
+        !matches!(
+            LoanStatus::from(ctx.accounts.loan.status)?,
+            LoanStatus::Completed | LoanStatus::Seize | LoanStatus::Settled
+        ),
+        ErrorCode::InvalidLoanState
+    );
+
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts.transfer_into_escrow_context(),
+        1,
+        ctx.accounts.position_mint.decimals,
+    )?;
+
+    PositionListing::init(
+        &mut ctx.accounts.listing,
+        ctx.accounts.seller.key(),
+        ctx.accounts.position_mint.key(),
+        ctx.accounts.loan.key(),
+        price,
+        ctx.accounts.loan.requested_token_mint,
+        *ctx.bumps.get("listing").unwrap(),
+    );
+
+    let loan = &ctx.accounts.loan;
+    emit!(PositionListed {
+        listing: ctx.accounts.listing.key(),
+        loan: loan.key(),
+        position_mint: ctx.accounts.position_mint.key(),
+        is_lend_position: loan.lend_nft_mint == ctx.accounts.position_mint.key(),
+        price,
+        price_mint: loan.requested_token_mint,
+        outstanding_debt: loan.outstanding_debt,
+        paid_amount: loan.paid_amount,
+        maturity_slot: loan.end_slot(),
+        last_updated_slot: loan.last_updated_slot,
+        status: loan.status,
+    });
+    Ok(())
+}
+
+///seller takes back a listed position nft
+pub fn cancel_position_listing(ctx: Context<CancelPositionListingContext>) -> Result<()> {
+    let position_mint = ctx.accounts.position_mint.key();
+    let bump = &[ctx.accounts.listing.bump][..];
+    let inner = vec![POSITION_LISTING_STR.as_bytes(), position_mint.as_ref(), bump];
+    let outer = vec![inner.as_slice()];
+
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_from_escrow_context()
+            .with_signer(outer.as_slice()),
+        1,
+        ctx.accounts.position_mint.decimals,
+    )?;
+    anchor_spl::token_interface::close_account(
+        ctx.accounts
+            .close_escrow_account_context()
+            .with_signer(outer.as_slice()),
+    )?;
+
+    emit!(PositionListingCancelled {
+        listing: ctx.accounts.listing.key(),
+        position_mint,
+    });
+    Ok(())
+}
+
+///buyer pays the listing price to the seller and receives the position nft in one go
+///
+/// `price` must match the listing so a seller can't reprice under a pending buy
+pub fn buy_position(ctx: Context<BuyPositionContext>, price: u64) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    require!(listing.price == price, ErrorCode::ListingPriceChanged);
+
+    if listing.price_mint == Pubkey::default() {
+        //we're using lamports
+        require!(
+            ctx.accounts.buyer_payment_account.key() == ctx.accounts.buyer.key()
+                && ctx.accounts.seller_payment_account.key() == listing.seller,
+            ErrorCode::InvalidAccount
+        );
+        system_program::transfer(
+            ctx.accounts.transfer_lamports_to_seller_context(),
+            LAMPORTS_PER_SOL * price,
+        )?;
+    } else {
+        //we're using spl token
+        let seller_payment_account = TokenAccount::try_deserialize(
+            &mut &ctx.accounts.seller_payment_account.data.borrow()[..],
+        )?;
+        require!(
+            seller_payment_account.owner == listing.seller
+                && seller_payment_account.mint == listing.price_mint,
+            ErrorCode::InvalidAccount
+        );
+
+        // buyer covers any token-2022 transfer fee so the seller receives the listing price
+        anchor_spl::token_interface::transfer_checked(
+            ctx.accounts.transfer_spl_tokens_to_seller_context(),
+            gross_transfer_amount(&ctx.accounts.price_mint, price)?,
+            mint_decimals(&ctx.accounts.price_mint)?,
+        )?;
+    }
+
+    let position_mint = ctx.accounts.position_mint.key();
+    let bump = &[ctx.accounts.listing.bump][..];
+    let inner = vec![POSITION_LISTING_STR.as_bytes(), position_mint.as_ref(), bump];
+    let outer = vec![inner.as_slice()];
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_to_buyer_context()
+            .with_signer(outer.as_slice()),
+        1,
+        ctx.accounts.position_mint.decimals,
+    )?;
+    anchor_spl::token_interface::close_account(
+        ctx.accounts
+            .close_escrow_account_context()
+            .with_signer(outer.as_slice()),
+    )?;
+
+    let loan = &ctx.accounts.loan;
+    emit!(PositionSold {
+        listing: ctx.accounts.listing.key(),
+        loan: loan.key(),
+        position_mint,
+        seller: ctx.accounts.seller.key(),
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        price_mint: ctx.accounts.listing.price_mint,
+        outstanding_debt: loan.outstanding_debt,
+        maturity_slot: loan.end_slot(),
+        status: loan.status,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ListPositionContext<'info> {
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        constraint=position_mint.key() == loan.lend_nft_mint
+            || position_mint.key() == loan.borrow_nft_mint @ ErrorCode::InvalidAccount,
+        constraint=position_mint.supply == 1 @ ErrorCode::InvalidAccount,
+        mint::token_program=token_program,
+    )]
+    position_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint=position_mint,
+        token::authority=seller,
+        token::token_program=token_program,
+    )]
+    seller_position_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + PositionListing::MAX_SIZE,
+        seeds = [POSITION_LISTING_STR.as_bytes(), position_mint.key().as_ref()],
+        bump,
+    )]
+    listing: Box<Account<'info, PositionListing>>,
+    #[account(
+        init,
+        payer = seller,
+        seeds = [POSITION_ESCROW_STR.as_bytes(), position_mint.key().as_ref()],
+        bump,
+        token::mint=position_mint,
+        token::authority=listing,
+        token::token_program=token_program,
+    )]
+    position_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    seller: Signer<'info>,
+    system_program: Program<'info, System>,
+    /// program of the position nft
+    token_program: Interface<'info, TokenInterface>,
+    rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> ListPositionContext<'info> {
+    pub fn transfer_into_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
+            from: self.seller_position_account.to_account_info().clone(),
+            mint: self.position_mint.to_account_info().clone(),
+            to: self.position_escrow.to_account_info().clone(),
+            authority: self.seller.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), transfer_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelPositionListingContext<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_LISTING_STR.as_bytes(), position_mint.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        has_one = position_mint,
+        close = seller,
+    )]
+    listing: Box<Account<'info, PositionListing>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    position_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [POSITION_ESCROW_STR.as_bytes(), position_mint.key().as_ref()],
+        bump,
+        token::mint=position_mint,
+        token::authority=listing,
+        token::token_program=token_program,
+    )]
+    position_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=position_mint,
+        token::authority=seller,
+        token::token_program=token_program,
+    )]
+    seller_position_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    seller: Signer<'info>,
+    /// program of the position nft
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CancelPositionListingContext<'info> {
+    pub fn transfer_from_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
+            from: self.position_escrow.to_account_info().clone(),
+            mint: self.position_mint.to_account_info().clone(),
+            to: self.seller_position_account.to_account_info().clone(),
+            authority: self.listing.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), transfer_accounts)
+    }
+    pub fn close_escrow_account_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        let close_accounts = CloseAccount {
+            account: self.position_escrow.to_account_info().clone(),
+            destination: self.seller.to_account_info().clone(),
+            authority: self.listing.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), close_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct BuyPositionContext<'info> {
+    #[account(
+        mut,
+        seeds = [POSITION_LISTING_STR.as_bytes(), position_mint.key().as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        has_one = position_mint,
+        has_one = loan,
+        close = seller,
+    )]
+    listing: Box<Account<'info, PositionListing>>,
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        mint::token_program=position_token_program,
+    )]
+    position_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [POSITION_ESCROW_STR.as_bytes(), position_mint.key().as_ref()],
+        bump,
+        token::mint=position_mint,
+        token::authority=listing,
+        token::token_program=position_token_program,
+    )]
+    position_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=position_mint,
+        token::authority=buyer,
+        token::token_program=position_token_program,
+    )]
+    buyer_position_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: price_mint is the mint for the loan currency Data is never read or written to
+    #[account(
+        constraint=price_mint.key() == listing.price_mint
+    )]
+    price_mint: UncheckedAccount<'info>, //could be Pubkey::Default()
+    /// CHECK: buyer_payment_account is the buyer's token account (or the buyer for SOL), checked by the transfer
+    #[account(mut)]
+    buyer_payment_account: UncheckedAccount<'info>,
+    /// CHECK: seller_payment_account is the seller's token account (or the seller for SOL), checked in the instruction
+    #[account(mut)]
+    seller_payment_account: UncheckedAccount<'info>,
+    /// CHECK: seller receives the rent of the closed listing and escrow
+    #[account(mut)]
+    seller: UncheckedAccount<'info>,
+    #[account(mut)]
+    buyer: Signer<'info>,
+    system_program: Program<'info, System>,
+    /// program of the loan currency
+    token_program: Interface<'info, TokenInterface>,
+    /// program of the position nft
+    position_token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> BuyPositionContext<'info> {
+    pub fn transfer_lamports_to_seller_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let transfer_acct = system_program::Transfer {
+            from: self.buyer.to_account_info().clone(),
+            to: self.seller.to_account_info().clone(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_spl_tokens_to_seller_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.buyer_payment_account.to_account_info().clone(),
+            mint: self.price_mint.to_account_info().clone(),
+            to: self.seller_payment_account.to_account_info().clone(),
+            authority: self.buyer.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_to_buyer_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
+            from: self.position_escrow.to_account_info().clone(),
+            mint: self.position_mint.to_account_info().clone(),
+            to: self.buyer_position_account.to_account_info().clone(),
+            authority: self.listing.to_account_info().clone(),
+        };
+        CpiContext::new(self.position_token_program.to_account_info().clone(), transfer_accounts)
+    }
+    pub fn close_escrow_account_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
+        let close_accounts = CloseAccount {
+            account: self.position_escrow.to_account_info().clone(),
+            destination: self.seller.to_account_info().clone(),
+            authority: self.listing.to_account_info().clone(),
+        };
+        CpiContext::new(self.position_token_program.to_account_info().clone(), close_accounts)
+    }
+}
+
+//Events
+#[event]
+pub struct PositionListed {
+    pub listing: Pubkey,
+    pub loan: Pubkey,
+    pub position_mint: Pubkey,
+    pub is_lend_position: bool,
+    pub price: u64,
+    pub price_mint: Pubkey,
+    //live loan state so buyers can price the claim
+    pub outstanding_debt: u64,
+    pub paid_amount: u64,
+    pub maturity_slot: u64,
+    pub last_updated_slot: u64,
+    pub status: u8,
+}
+
+#[event]
+pub struct PositionListingCancelled {
+    pub listing: Pubkey,
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct PositionSold {
+    pub listing: Pubkey,
+    pub loan: Pubkey,
+    pub position_mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub price_mint: Pubkey,
+    pub outstanding_debt: u64,
+    pub maturity_slot: u64,
+    pub status: u8,
+}
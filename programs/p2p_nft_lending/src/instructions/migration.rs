@@ -0,0 +1,170 @@
+use anchor_lang::{prelude::*, system_program, Discriminator};
+use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+
+use crate::{
+    errors::ErrorCode,
+    state::{Multisig, PlatformFees},
+    utils::{MULTISIG_SEED_STR, PLATFORM_FEES_SEED_STR},
+};
+
+/// Multisig as it was first deployed, later fields are appended after `owners`
+#[derive(AnchorDeserialize)]
+pub(crate) struct MultisigV0 {
+    pub threshold: u64,
+    pub seqno: u32,
+    pub owners: Vec<Pubkey>,
+}
+
+/// PlatformFees as it was first deployed, its trailing `uncollected_fees` list is dropped on
+/// migration, the LoanFee accounts it listed stay withdrawable and sweepable on their own
+#[derive(AnchorDeserialize)]
+pub(crate) struct PlatformFeesV0 {
+    pub fee_percentage: u32,
+    pub interest_rate: u32,
+    pub ltv: u32,
+}
+
+impl MultisigV0 {
+    pub const MAX_SIZE: usize = 8 + 4  //threshold, seqno
+    + 4 + (32 * 100); //100 owners max
+}
+
+impl PlatformFeesV0 {
+    pub const MAX_SIZE: usize = 4 // fee_percentage
+    + 4  //interest
+    + 4 //ltv
+    + 4 + (100 * 32); //100 uncollected_fees at a time
+}
+
+// the multisig grows to its current size within a single realloc, platform fees shrink
+const _: () = assert!(
+    Multisig::MAX_SIZE - MultisigV0::MAX_SIZE <= MAX_PERMITTED_DATA_INCREASE
+        && PlatformFees::MAX_SIZE < PlatformFeesV0::MAX_SIZE
+);
+
+///resizes the multisig and platform fees accounts of a deployment that predates their later
+/// fields to the current layout, every new setting starts out off. Permissionless, the payer
+/// covers any extra rent, accounts already at the current layout are left as they are
+pub fn migrate_state(ctx: Context<MigrateStateContext>) -> Result<()> {
+    let multisig = ctx.accounts.multisig.to_account_info();
+    if let Some(legacy) = legacy_state::<MultisigV0>(
+        &multisig,
+        &Multisig::discriminator(),
+        8 + Multisig::MAX_SIZE,
+    )? {
+        let migrated = Multisig {
+            threshold: legacy.threshold,
+            // proposals made before the upgrade can't be read in the new layout
+            seqno: legacy.seqno + 1,
+            owners: legacy.owners,
+            proposal_count: 0,
+            execution_delay: 0,
+            max_proposal_slots: 0,
+            vote_weights: vec![],
+            weights: vec![],
+            treasury: Pubkey::default(),
+            treasury_bps: 0,
+            insurance_bps: 0,
+            roles: vec![],
+            version: Multisig::VERSION,
+        };
+        ctx.accounts.resize(&multisig, 8 + Multisig::MAX_SIZE)?;
+        migrated.try_serialize(&mut &mut multisig.try_borrow_mut_data()?[..])?;
+    }
+
+    let platform_fees = ctx.accounts.platform_fees.to_account_info();
+    if let Some(legacy) = legacy_state::<PlatformFeesV0>(
+        &platform_fees,
+        &PlatformFees::discriminator(),
+        8 + PlatformFees::MAX_SIZE,
+    )? {
+        let migrated = PlatformFees {
+            fee_percentage: legacy.fee_percentage,
+            interest_rate: legacy.interest_rate,
+            ltv: legacy.ltv,
+            interest_fee_percentage: 0,
+            grace_slots: 0,
+            penalty_rate: 0,
+            late_fee: 0,
+            max_referral_bps: 0,
+            paused: false,
+            max_spread: 0,
+            version: PlatformFees::VERSION,
+        };
+        ctx.accounts.resize(&platform_fees, 8 + PlatformFees::MAX_SIZE)?;
+        migrated.try_serialize(&mut &mut platform_fees.try_borrow_mut_data()?[..])?;
+    }
+
+    emit!(StateMigrated {
+        multisig_version: Multisig::VERSION,
+        platform_fees_version: PlatformFees::VERSION,
+    });
+    Ok(())
+}
+
+// the account's first layout, None once it has been resized to `size`
+fn legacy_state<T: AnchorDeserialize>(
+    info: &AccountInfo,
+    discriminator: &[u8; 8],
+    size: usize,
+) -> Result<Option<T>> {
+    let data = info.try_borrow_data()?;
+    require!(
+        *info.owner == crate::ID && data.len() >= 8 && data[..8] == discriminator[..],
+        ErrorCode::InvalidAccount
+    );
+    if data.len() == size {
+        return Ok(None);
+    }
+    Ok(Some(T::deserialize(&mut &data[8..])?))
+}
+
+#[derive(Accounts)]
+pub struct MigrateStateContext<'info> {
+    /// CHECK: the multisig in the layout it was created with, checked in the handler
+    #[account(
+        mut,
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: UncheckedAccount<'info>,
+    /// CHECK: platform fees in the layout it was created with, checked in the handler
+    #[account(
+        mut,
+        seeds = [PLATFORM_FEES_SEED_STR.as_bytes()],
+        bump,
+    )]
+    platform_fees: UncheckedAccount<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateStateContext<'info> {
+    fn resize(&self, account: &AccountInfo<'info>, size: usize) -> Result<()> {
+        let rent = Rent::get()?
+            .minimum_balance(size)
+            .saturating_sub(account.lamports());
+        if rent > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: self.payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                rent,
+            )?;
+        }
+        account.realloc(size, true)?;
+        Ok(())
+    }
+}
+
+//Events
+#[event]
+pub struct StateMigrated {
+    pub multisig_version: u8,
+    pub platform_fees_version: u8,
+}
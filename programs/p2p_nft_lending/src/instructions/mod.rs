@@ -2,8 +2,18 @@ pub mod multisig;
 pub mod borrower;
 pub mod lender;
 pub mod third_party;
+pub mod pool;
+pub mod syndicate;
+pub mod marketplace;
+pub mod treasury;
+pub mod migration;
 
 pub use multisig::*;
 pub use borrower::*;
 pub use lender::*;
 pub use third_party::*;
+pub use pool::*;
+pub use syndicate::*;
+pub use marketplace::*;
+pub use treasury::*;
+pub use migration::*;
@@ -1,12 +1,11 @@
-use std::ops::Deref;
 
-use crate::math::{Decimal, TryDiv};
-use crate::state::{ Multisig, Transaction, TransactionAccount, PlatformFees, PlatformListing, Loan, LoanFee};
-use crate::utils::{MULTISIG_TX_SEED_STR, PLATFORM_FEES_SEED_STR, PLATFORM_LISTING, LOAN_FEE_STR};
+use crate::state::{ BenchmarkRate, CollectionConfig, CollectionParams, CurrencyConfig, CurrencyParams, Multisig, PricingTable, PricingTier, Proposal, RoleConfig, Spender, SpendingLimits, Transaction, TransactionAccount, TransactionInstruction, PlatformFees, PlatformListing, Loan, LoanFee};
+use crate::utils::{ed25519_signers, mint_decimals, BENCHMARK_RATE_STR, COLLECTION_CONFIG_STR, CURRENCY_CONFIG_STR, MULTISIG_TX_SEED_STR, PRICING_TABLE_STR, PLATFORM_FEES_SEED_STR, SPENDING_LIMITS_STR, PLATFORM_LISTING};
 use crate::{errors::ErrorCode, utils::MULTISIG_SEED_STR};
 use anchor_lang::{prelude::*, system_program, };
-use anchor_spl::token::{Token, Transfer};
+use anchor_spl::token_interface::{TokenAccount, TokenInterface, TransferChecked};
 use solana_program::instruction::Instruction;
+use solana_program::sysvar::{self, instructions::load_instruction_at_checked};
 use solana_program::native_token::LAMPORTS_PER_SOL;
 
 ///initialize multisig acct with other needed accounts
@@ -19,6 +18,7 @@ pub fn initialize_multisig(
 
     Multisig::init(&mut ctx.accounts.multisig, owners, threshold)?;
     // PlatformFees::init(&mut ctx.accounts.platform_fees, fee, interest, ltv);
+    ctx.accounts.platform_fees.version = PlatformFees::VERSION;
     
     emit!(MultisigCreated {
         owners: multisig.owners.clone(),
@@ -45,11 +45,267 @@ pub fn set_platform_fees(
 }
 
 
-// Sets the owners field on the multisig. The only way this can be invoked
-// is via a recursive call from execute_transaction -> set_owners.
+// Sets the fee model: an origination fee on principal, a fee on interest collected, or
+// both (0 disables either). The only way this can be invoked is via a recursive call
+// from execute_transaction -> set_fee_model.
+pub fn set_fee_model(
+    ctx: Context<PlatformFeeAuthContext>,
+    origination_fee: u32,
+    interest_fee: u32,
+) -> Result<()> {
+    require!(
+        origination_fee < 1000 && interest_fee < 1000,
+        ErrorCode::InvalidFeeModel
+    );
+    ctx.accounts.platform_fees.fee_percentage = origination_fee;
+    ctx.accounts.platform_fees.interest_fee_percentage = interest_fee;
+
+    emit!(FeeModelSet {
+        origination_fee,
+        interest_fee,
+    });
+    Ok(())
+}
+
+
+// Sets the late payment terms snapshotted by new loans: a grace period after maturity during
+// which debt accrues at the penalty APR on top of the contract rate, plus a flat late fee.
+// The only way this can be invoked is via a recursive call from
+// execute_transaction -> set_late_payment_terms.
+pub fn set_late_payment_terms(
+    ctx: Context<PlatformFeeAuthContext>,
+    grace_slots: u64,
+    penalty_rate: u32,
+    late_fee: u64,
+) -> Result<()> {
+    ctx.accounts.platform_fees.grace_slots = grace_slots;
+    ctx.accounts.platform_fees.penalty_rate = penalty_rate;
+    ctx.accounts.platform_fees.late_fee = late_fee;
+
+    emit!(LatePaymentTermsSet {
+        grace_slots,
+        penalty_rate,
+        late_fee,
+    });
+    Ok(())
+}
+
+
+// Caps the share of the platform fee, in bps, a referring frontend can take on each side of
+// a loan, 0 turns referrals off. The only way this can be invoked is via a recursive call
+// from execute_transaction -> set_max_referral_fee.
+pub fn set_max_referral_fee(
+    ctx: Context<PlatformFeeAuthContext>,
+    max_referral_bps: u16,
+) -> Result<()> {
+    // borrower and lender referrers together never take the whole fee
+    require!(
+        max_referral_bps <= Multisig::TOTAL_BPS / 2,
+        ErrorCode::ReferralFeeTooHigh
+    );
+    ctx.accounts.platform_fees.max_referral_bps = max_referral_bps;
+
+    emit!(MaxReferralFeeSet { max_referral_bps });
+    Ok(())
+}
+
+
+// Caps the spread over the benchmark rate borrowers can accept on a floating rate loan, 0
+// only allows the benchmark rate itself. The only way this can be invoked is via a recursive
+// call from execute_transaction -> set_max_spread.
+pub fn set_max_spread(ctx: Context<PlatformFeeAuthContext>, max_spread: u32) -> Result<()> {
+    ctx.accounts.platform_fees.max_spread = max_spread;
+
+    emit!(MaxSpreadSet { max_spread });
+    Ok(())
+}
+
+
+// Sets the basis point fee share of each owner (in owners order), of the treasury and of the
+// insurance fund. Weights must add up to 10_000 with treasury_bps and insurance_bps, empty
+// weights split what's left equally. The only way this can be invoked is via a recursive call
+// from execute_transaction -> set_fee_shares.
+pub fn set_fee_shares(
+    ctx: Context<MultisigAuth>,
+    weights: Vec<u16>,
+    treasury: Pubkey,
+    treasury_bps: u16,
+    insurance_bps: u16,
+) -> Result<()> {
+    ctx.accounts
+        .multisig
+        .set_fee_shares(weights.clone(), treasury, treasury_bps, insurance_bps)?;
+
+    emit!(FeeSharesSet {
+        owners: ctx.accounts.multisig.owners.clone(),
+        weights,
+        treasury,
+        treasury_bps,
+        insurance_bps,
+    });
+    Ok(())
+}
+
+
+// Sets how many slots approved transactions wait before they can be executed, giving owners
+// a window to veto and users notice of parameter changes, 0 executes right away. The only
+// way this can be invoked is via a recursive call from execute_transaction -> set_execution_delay.
+pub fn set_execution_delay(ctx: Context<MultisigAuth>, execution_delay: u64) -> Result<()> {
+    ctx.accounts.multisig.execution_delay = execution_delay;
+
+    emit!(ExecutionDelaySet { execution_delay });
+    Ok(())
+}
+
+
+// Sets the longest a proposal stays open, in slots, and the default lifetime of proposals
+// created without one, 0 lets proposals stay open until executed. The only way this can be
+// invoked is via a recursive call from execute_transaction -> set_max_proposal_slots.
+pub fn set_max_proposal_slots(ctx: Context<MultisigAuth>, max_proposal_slots: u64) -> Result<()> {
+    ctx.accounts.multisig.max_proposal_slots = max_proposal_slots;
+
+    emit!(MaxProposalSlotsSet { max_proposal_slots });
+    Ok(())
+}
+
+
+// Sets the roles, each an owner subset with its own threshold over its admin instructions:
+// fee admin, treasury, pause guardian and owner management. A role without a config is left
+// to the owners and their threshold. The only way this can be invoked is via a recursive call
+// from execute_transaction -> set_roles.
+pub fn set_roles(ctx: Context<MultisigAuth>, roles: Vec<RoleConfig>) -> Result<()> {
+    ctx.accounts.multisig.set_roles(roles.clone())?;
+
+    emit!(RolesSet { roles });
+    Ok(())
+}
+
+
+// Pauses or resumes new loans, repayments and withdrawals stay open. The only way this can be
+// invoked is via a recursive call from execute_transaction -> set_paused.
+pub fn set_paused(ctx: Context<PlatformFeeAuthContext>, paused: bool) -> Result<()> {
+    ctx.accounts.platform_fees.paused = paused;
+
+    emit!(PausedSet { paused });
+    Ok(())
+}
+
+
+// Records a new benchmark rate for floating rate loans. The only way this can be invoked
+// is via a recursive call from execute_transaction -> set_benchmark_rate.
+pub fn set_benchmark_rate(ctx: Context<BenchmarkRateAuthContext>, rate: u32) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.benchmark_rate.push(rate, clock.slot);
+
+    emit!(BenchmarkRateSet {
+        rate,
+        slot: clock.slot,
+    });
+    Ok(())
+}
+
+
+// Replaces the pricing tiers of the global (Pubkey::default()) or a collection's pricing
+// table. The only way this can be invoked is via a recursive call from
+// execute_transaction -> set_pricing_tiers.
+pub fn set_pricing_tiers(
+    ctx: Context<PricingTableAuthContext>,
+    collection: Pubkey,
+    tiers: Vec<PricingTier>,
+) -> Result<()> {
+    require!(
+        tiers.len() <= PricingTable::MAX_TIERS,
+        ErrorCode::InvalidPricingTier
+    );
+    require!(
+        tiers.iter().all(|tier| tier.max_ltv <= 1000),
+        ErrorCode::InvalidPricingTier
+    );
+
+    let pricing_table = &mut ctx.accounts.pricing_table;
+    pricing_table.collection = collection;
+    pricing_table.tiers = tiers.clone();
+    pricing_table.bump = *ctx.bumps.get("pricing_table").unwrap();
+
+    emit!(PricingTiersSet { collection, tiers });
+    Ok(())
+}
+
+
+// Registers or updates a verified collection's risk parameters. The only way this can be
+// invoked is via a recursive call from execute_transaction -> set_collection_config.
+pub fn set_collection_config(
+    ctx: Context<CollectionConfigAuthContext>,
+    collection: Pubkey,
+    params: CollectionParams,
+) -> Result<()> {
+    let collection_config = &mut ctx.accounts.collection_config;
+    collection_config.collection = collection;
+    collection_config.bump = *ctx.bumps.get("collection_config").unwrap();
+    collection_config.set_params(params)?;
+
+    emit!(CollectionConfigSet {
+        collection,
+        allowed: params.allowed,
+        max_ltv: params.max_ltv,
+        exposure_cap: params.exposure_cap,
+    });
+    Ok(())
+}
+
+
+// Allowlists a loan currency or updates its limits, Pubkey::default() for SOL. The only way
+// this can be invoked is via a recursive call from execute_transaction -> set_currency_config.
+pub fn set_currency_config(
+    ctx: Context<CurrencyConfigAuthContext>,
+    mint: Pubkey,
+    params: CurrencyParams,
+) -> Result<()> {
+    let currency_config = &mut ctx.accounts.currency_config;
+    currency_config.mint = mint;
+    currency_config.bump = *ctx.bumps.get("currency_config").unwrap();
+    currency_config.set_params(params)?;
+
+    emit!(CurrencyConfigSet {
+        mint,
+        enabled: params.enabled,
+        debt_ceiling: params.debt_ceiling,
+        fee_override: params.fee_override,
+    });
+    Ok(())
+}
+
+
+// Sets how much of a mint's fee treasury an owner, or a role's members together, can withdraw
+// each period without a proposal, an amount of 0 removes the limit. The only way this can be
+// invoked is via a recursive call from execute_transaction -> set_spending_limit.
+pub fn set_spending_limit(
+    ctx: Context<SpendingLimitAuthContext>,
+    mint: Pubkey,
+    spender: Spender,
+    amount: u64,
+    period_slots: u64,
+) -> Result<()> {
+    let spending_limits = &mut ctx.accounts.spending_limits;
+    spending_limits.mint = mint;
+    spending_limits.bump = *ctx.bumps.get("spending_limits").unwrap();
+    spending_limits.set(spender, amount, period_slots, Clock::get()?.slot)?;
+
+    emit!(SpendingLimitSet {
+        mint,
+        spender,
+        amount,
+        period_slots,
+    });
+    Ok(())
+}
+
+
+// Sets the owners field on the multisig, retained owners keep their vote weight and fee share.
+// The only way this can be invoked is via a recursive call from execute_transaction -> set_owners.
 pub fn set_owners(ctx: Context<MultisigAuth>, owners: Vec<Pubkey>) -> Result<()> {
     
-    Multisig::update_owners(&mut ctx.accounts.multisig, owners.clone());
+    Multisig::update_owners(&mut ctx.accounts.multisig, owners.clone())?;
     let old_owners = owners.clone();
    
     emit!(OwnersListUpdated {
@@ -66,17 +322,8 @@ pub fn withdraw_fee(
 ) -> Result<()> {
     
 // either call individually or withdraw collectively
-    assert_eq!(ctx.accounts.admin.key, ctx.accounts.admin_token_account.key);
     let fee = ctx.accounts.loan_fee.amount;
 
-    let multisig_admins = &ctx.accounts.multisig.owners;
-    let loan_fee_admins = &ctx.accounts.loan_fee.owners;
-    let uncollected_fees_list = &ctx.accounts.platform_fees.uncollected_fees;
-
-    require!(uncollected_fees_list.contains(&ctx.accounts.loan_fee.key()), ErrorCode::FeeAlreadyCollected);
-    require!(multisig_admins.contains(ctx.accounts.admin.key), ErrorCode::InvalidOwner);
-    require!(loan_fee_admins.contains(ctx.accounts.admin.key), ErrorCode::FeeAlreadyWithdrawn);
-
     let multisig = &ctx.accounts.multisig.key();
     let token_program = &ctx.accounts.token_program.key();
     let token_mint = &ctx.accounts.loan_fee.token_mint.key();
@@ -100,48 +347,47 @@ pub fn withdraw_fee(
     ];
     let outer = vec![inner.as_slice()];
 
+    // shares were snapshotted when the fee was taken, so owners removed since can still claim
     if ctx.accounts.loan_fee.token_mint == Pubkey::default() {
-        let fee = Decimal::from(fee * LAMPORTS_PER_SOL)
-        .try_div(ctx.accounts.multisig.owners.len() as u64)
-        .unwrap()
-        .try_floor_u64()?;
-       
+        assert_eq!(ctx.accounts.admin.key, ctx.accounts.admin_token_account.key);
+        let share = ctx
+            .accounts
+            .loan_fee
+            .take_share(ctx.accounts.admin.key, fee * LAMPORTS_PER_SOL)
+            .ok_or(ErrorCode::FeeAlreadyWithdrawn)?;
 
         system_program::transfer(
             ctx.accounts.transfer_lamports_to_admin_context().with_signer(outer.as_slice()),
-            fee,
+            share,
         )?;
     }else{
-        
-    }
-// send token to accounts
-    if loan_fee_admins.len() == 1 {
-          
-            let uncollected_fees_index = ctx
-            .accounts
-            .platform_fees
-            .uncollected_fees
-            .iter()
-            .position(|a| *a == ctx.accounts.loan_fee.key()).unwrap();
-
-            ctx
-            .accounts
-            .platform_fees
-            .uncollected_fees.remove(uncollected_fees_index);
-        }else{
-            let loan_fees_index = ctx
+        let admin_token_account = TokenAccount::try_deserialize(
+            &mut &ctx.accounts.admin_token_account.try_borrow_data()?[..],
+        )?;
+        require!(
+            admin_token_account.owner == ctx.accounts.admin.key(),
+            ErrorCode::InvalidAccount
+        );
+        let share = ctx
             .accounts
             .loan_fee
-            .owners
-            .iter()
-            .position(|a| *a == ctx.accounts.admin.key()).unwrap();
+            .take_share(ctx.accounts.admin.key, fee)
+            .ok_or(ErrorCode::FeeAlreadyWithdrawn)?;
 
-            // remove admin from loan_fee_admins
-            ctx
-            .accounts
-            .loan_fee
-            .owners.remove(loan_fees_index);
-        }
+        // spl escrow is the multisig's token account
+        let multisig_bump = ctx.bumps.get(MULTISIG_SEED_STR);
+        let bump = &[*multisig_bump.unwrap()][..];
+        let inner = vec![MULTISIG_SEED_STR.as_bytes(), bump];
+        let outer = vec![inner.as_slice()];
+
+        anchor_spl::token_interface::transfer_checked(
+            ctx.accounts
+                .transfer_spl_tokens_to_admin_context()
+                .with_signer(outer.as_slice()),
+            share,
+            mint_decimals(&ctx.accounts.token_mint)?,
+        )?;
+    }
     
     Ok(())
 }
@@ -166,11 +412,39 @@ pub fn withdraw_fee(
         change_threshold(ctx, threshold)
     }
 
-// change_threshold.
+
+// Sets the owners with their vote weight (in owners order, empty for one each) and the weight
+// threshold at once, pending transactions are invalidated. The only way this can be invoked
+// is via a recursive call from execute_transaction -> set_owners_and_weights.
+pub fn set_owners_and_weights(
+    ctx: Context<MultisigAuth>,
+    owners: Vec<Pubkey>,
+    vote_weights: Vec<u16>,
+    threshold: u64,
+) -> Result<()> {
+    let multisig = &mut ctx.accounts.multisig;
+    let old_owners = multisig.owners.clone();
+    multisig.update_owners_and_vote_weights(owners.clone(), vote_weights.clone(), threshold)?;
+
+    emit!(OwnersListUpdated {
+        old_owners,
+        new_owners: owners.clone(),
+    });
+    emit!(VoteWeightsSet {
+        owners,
+        vote_weights,
+        threshold,
+    });
+    Ok(())
+}
+
+// Sets the vote weight needed to approve a transaction. The only way this can be invoked
+// is via a recursive call from execute_transaction -> change_threshold.
 pub fn change_threshold(ctx: Context<MultisigAuth>, threshold: u64) -> Result<()> {
     require!(threshold > 0, ErrorCode::InvalidThreshold);
 
-    if threshold > ctx.accounts.multisig.owners.len() as u64 {
+    let multisig = &ctx.accounts.multisig;
+    if threshold > multisig.weight_of(&multisig.owners) {
         return Err(ErrorCode::InvalidThreshold.into());
     }
     let multisig = &mut ctx.accounts.multisig;
@@ -183,35 +457,264 @@ pub fn create_transaction(
     program_id: Pubkey,
     transaction_accounts: Vec<TransactionAccount>,
     data: Vec<u8>,
+    expires_in: Option<u64>,
 ) -> Result<()> {
-    let owner_index = ctx
-        .accounts
-        .multisig
+    let instruction = TransactionInstruction {
+        program_id,
+        accounts: transaction_accounts,
+        data,
+    };
+    propose(
+        &mut ctx.accounts.multisig,
+        &mut ctx.accounts.transaction,
+        &ctx.accounts.proposer,
+        vec![instruction],
+        None,
+        expires_in,
+    )
+}
+
+/// Proposes several instructions executed atomically, in order, by a single execute_transaction.
+pub fn create_batch_transaction(
+    ctx: Context<CreateBatchTransaction>,
+    instructions: Vec<TransactionInstruction>,
+    expires_in: Option<u64>,
+) -> Result<()> {
+    propose(
+        &mut ctx.accounts.multisig,
+        &mut ctx.accounts.transaction,
+        &ctx.accounts.proposer,
+        instructions,
+        None,
+        expires_in,
+    )
+}
+
+/// Proposes one of the program's admin instructions with decodable arguments, the instruction
+/// is built from them when the transaction executes.
+pub fn create_proposal(
+    ctx: Context<CreateProposal>,
+    proposal: Proposal,
+    expires_in: Option<u64>,
+) -> Result<()> {
+    propose(
+        &mut ctx.accounts.multisig,
+        &mut ctx.accounts.transaction,
+        &ctx.accounts.proposer,
+        vec![],
+        Some(proposal),
+        expires_in,
+    )
+}
+
+fn propose(
+    multisig: &mut Account<Multisig>,
+    tx: &mut Account<Transaction>,
+    proposer: &Signer,
+    instructions: Vec<TransactionInstruction>,
+    proposal: Option<Proposal>,
+    expires_in: Option<u64>,
+) -> Result<()> {
+    tx.multisig = multisig.key();
+    tx.proposer = proposer.key();
+    tx.instructions = instructions;
+    tx.proposal = proposal;
+    let instructions = tx.instructions();
+    require!(!instructions.is_empty(), ErrorCode::EmptyTransaction);
+    // proposals stay open for `expires_in` slots, at most max_proposal_slots when it's set
+    let max_proposal_slots = multisig.max_proposal_slots;
+    let lifetime = match expires_in {
+        Some(expires_in) => {
+            require!(
+                max_proposal_slots == 0 || expires_in <= max_proposal_slots,
+                ErrorCode::InvalidExpiry
+            );
+            Some(expires_in)
+        }
+        None if max_proposal_slots > 0 => Some(max_proposal_slots),
+        None => None,
+    };
+    let slot = Clock::get()?.slot;
+    let owner_index = multisig
         .owners
         .iter()
-        .position(|a| a == ctx.accounts.proposer.key)
+        .position(|a| a == proposer.key)
         .ok_or(ErrorCode::InvalidOwner)?;
+    require!(
+        multisig.can_propose(proposer.key, &instructions),
+        ErrorCode::MissingRole
+    );
 
-    let mut signers: Vec<bool> = Vec::new();
-    signers.resize(ctx.accounts.multisig.owners.len(), false);
-    signers[owner_index] = true;
-
-    let tx = &mut ctx.accounts.transaction;
-    tx.program_id = program_id;
-    tx.accounts = transaction_accounts;
-    tx.data = data;
-    tx.signers = signers;
-    tx.multisig = ctx.accounts.multisig.key();
+    tx.signers = vec![false; multisig.owners.len()];
+    tx.rejections = vec![false; multisig.owners.len()];
+    tx.vetoes = vec![false; multisig.owners.len()];
+    tx.failed = false;
+    tx.approved_slot = None;
+    tx.expiry_slot = lifetime.map(|lifetime| slot.saturating_add(lifetime));
+    tx.vote(owner_index, true);
+    multisig.tally(tx, slot);
     tx.did_execute = false;
-    tx.seqno = ctx.accounts.multisig.seqno;
-    tx.proposer = ctx.accounts.proposer.key();
+    tx.seqno = multisig.seqno;
+    tx.index = multisig.proposal_count;
 
-    
+    // proposals are independent, the next one gets a fresh account
+    multisig.proposal_count = multisig
+        .proposal_count
+        .checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(TransactionCreated {
+        transaction: tx.key(),
+        index: tx.index,
+        seqno: tx.seqno,
+        proposer: proposer.key(),
+    });
     Ok(())
 }
 
 /// Approves a transaction on behalf of an owner of the multisig.
 pub fn approve(ctx: Context<Approve>) -> Result<()> {
+    let owner_index = voter_index(&ctx)?;
+    ctx.accounts.transaction.vote(owner_index, true);
+    ctx.accounts
+        .multisig
+        .tally(&mut ctx.accounts.transaction, Clock::get()?.slot);
+
+    Ok(())
+}
+
+/// Approves a transaction for every owner whose off-chain signature over its approval message
+/// (see Transaction::approval_message) is carried by an Ed25519 program instruction of the same
+/// transaction. Anyone can submit the signatures, owners never need to send a transaction.
+pub fn approve_with_signatures(ctx: Context<ApproveWithSignatures>, expiry_slot: u64) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    check_open(&ctx.accounts.transaction)?;
+    require!(slot <= expiry_slot, ErrorCode::ApprovalExpired);
+
+    let transaction = &mut ctx.accounts.transaction;
+    let message = transaction.approval_message(&transaction.key(), expiry_slot);
+    let mut owners = vec![];
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, &ctx.accounts.instructions) {
+        for signer in ed25519_signers(&instruction, &message)? {
+            let owner_index = ctx.accounts.multisig.owners.iter().position(|a| *a == signer);
+            if let Some(owner_index) = owner_index {
+                transaction.vote(owner_index, true);
+                owners.push(signer);
+            }
+        }
+        index += 1;
+    }
+    require!(!owners.is_empty(), ErrorCode::InvalidSignatures);
+    ctx.accounts.multisig.tally(transaction, slot);
+
+    emit!(TransactionApprovedWithSignatures {
+        transaction: transaction.key(),
+        index: transaction.index,
+        owners,
+    });
+    Ok(())
+}
+
+/// Votes against a transaction, replacing the owner's approval if it had one. The transaction
+/// fails once too few owners (or role members) are left to reach the threshold.
+pub fn reject(ctx: Context<Approve>) -> Result<()> {
+    let owner_index = voter_index(&ctx)?;
+    let transaction = &mut ctx.accounts.transaction;
+    transaction.vote(owner_index, false);
+    ctx.accounts.multisig.tally(transaction, Clock::get()?.slot);
+
+    emit!(TransactionRejected {
+        transaction: transaction.key(),
+        index: transaction.index,
+        owner: ctx.accounts.owner.key(),
+        failed: transaction.failed,
+    });
+    Ok(())
+}
+
+/// Withdraws an owner's approval.
+pub fn revoke_approval(ctx: Context<Approve>) -> Result<()> {
+    let owner_index = voter_index(&ctx)?;
+    require!(
+        ctx.accounts.transaction.signers[owner_index],
+        ErrorCode::InvalidOwner
+    );
+    ctx.accounts.transaction.revoke(owner_index);
+    ctx.accounts
+        .multisig
+        .tally(&mut ctx.accounts.transaction, Clock::get()?.slot);
+
+    emit!(ApprovalRevoked {
+        transaction: ctx.accounts.transaction.key(),
+        index: ctx.accounts.transaction.index,
+        owner: ctx.accounts.owner.key(),
+    });
+    Ok(())
+}
+
+// owner index of the voter, failed or expired transactions take no more votes
+fn voter_index(ctx: &Context<Approve>) -> Result<usize> {
+    check_open(&ctx.accounts.transaction)?;
+    ctx.accounts
+        .multisig
+        .owners
+        .iter()
+        .position(|a| a == ctx.accounts.owner.key)
+        .ok_or(ErrorCode::InvalidOwner.into())
+}
+
+// votes are closed once the transaction failed or expired
+fn check_open(transaction: &Transaction) -> Result<()> {
+    require!(!transaction.failed, ErrorCode::TransactionFailed);
+    require!(
+        !transaction.is_expired(Clock::get()?.slot),
+        ErrorCode::TransactionExpired
+    );
+    Ok(())
+}
+
+/// Closes a transaction and refunds its rent to the proposer. The proposer can cancel at any
+/// time, including proposals left stale by an owner change, any owner can once owners with
+/// threshold vote weight rejected it or it failed.
+pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
+    let transaction = &ctx.accounts.transaction;
+    let multisig = &ctx.accounts.multisig;
+    let canceller = ctx.accounts.canceller.key();
+    let voted_down = multisig.seqno == transaction.seqno
+        && multisig.owners.contains(&canceller)
+        && (transaction.failed || multisig.rejected_weight(transaction) >= multisig.threshold);
+    require!(
+        canceller == transaction.proposer || voted_down,
+        ErrorCode::UnableToCancelTransaction
+    );
+
+    emit!(TransactionCancelled {
+        transaction: transaction.key(),
+        index: transaction.index,
+        canceller,
+    });
+    Ok(())
+}
+
+/// Closes an expired transaction and refunds its rent to the proposer, anyone can call it.
+pub fn close_expired_transaction(ctx: Context<CloseExpiredTransaction>) -> Result<()> {
+    require!(
+        ctx.accounts.transaction.is_expired(Clock::get()?.slot),
+        ErrorCode::TransactionNotExpired
+    );
+
+    emit!(TransactionExpired {
+        transaction: ctx.accounts.transaction.key(),
+        index: ctx.accounts.transaction.index,
+        expiry_slot: ctx.accounts.transaction.expiry_slot.unwrap_or_default(),
+    });
+    Ok(())
+}
+
+/// Vetoes an approved transaction still waiting out the execution delay. The transaction is
+/// cancelled, its rent refunded to the proposer, once owners with threshold vote weight vetoed.
+pub fn veto(ctx: Context<Veto>) -> Result<()> {
     let owner_index = ctx
         .accounts
         .multisig
@@ -219,51 +722,91 @@ pub fn approve(ctx: Context<Approve>) -> Result<()> {
         .iter()
         .position(|a| a == ctx.accounts.owner.key)
         .ok_or(ErrorCode::InvalidOwner)?;
+    let approved_slot = ctx
+        .accounts
+        .transaction
+        .approved_slot
+        .ok_or(ErrorCode::VetoWindowClosed)?;
+    require!(
+        Clock::get()?.slot < approved_slot.saturating_add(ctx.accounts.multisig.execution_delay),
+        ErrorCode::VetoWindowClosed
+    );
 
-    ctx.accounts.transaction.signers[owner_index] = true;
+    let transaction = &mut ctx.accounts.transaction;
+    transaction.vetoes[owner_index] = true;
+    let multisig = &ctx.accounts.multisig;
+    let cancelled = multisig.vetoed_weight(transaction) >= multisig.threshold;
+    if cancelled {
+        transaction.close(ctx.accounts.proposer.to_account_info())?;
+    }
 
+    emit!(TransactionVetoed {
+        transaction: transaction.key(),
+        index: transaction.index,
+        owner: ctx.accounts.owner.key(),
+        cancelled,
+    });
     Ok(())
 }
 
-/// Executes the given transaction if threshold owners have signed it.
+/// Executes the given transaction if owners with threshold vote weight have signed it.
 pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
 
     if ctx.accounts.transaction.did_execute{
         return Err(ErrorCode::AlreadyExecuted.into());
     }
+    require!(!ctx.accounts.transaction.failed, ErrorCode::TransactionFailed);
+    require!(
+        !ctx.accounts.transaction.is_expired(Clock::get()?.slot),
+        ErrorCode::TransactionExpired
+    );
 
-    //check if number of signers are up to threshold
-    let sign_count = ctx
-    .accounts
-    .transaction
-    .signers
-    .iter()
-    .filter( |&did_sign| *did_sign)
-    .count() as u64;
-
-    if sign_count < ctx.accounts.multisig.threshold{
+    //roles may have changed hands since the transaction was proposed
+    require!(
+        ctx.accounts
+            .multisig
+            .can_propose(&ctx.accounts.transaction.proposer, &ctx.accounts.transaction.instructions()),
+        ErrorCode::MissingRole
+    );
+    //check if the signers are up to the threshold of the owners and of each role involved
+    if !ctx.accounts.multisig.is_approved(&ctx.accounts.transaction) {
         return Err(ErrorCode::NotEnoughSigners.into());
     }
-    //execute 
-    let mut ix: Instruction = (*ctx.accounts.transaction).deref().into();
-    ix.accounts = ix
-        .accounts
-        .iter()
-        .map(|acc| {
-            let mut acc = acc.clone();
-            if &acc.pubkey == ctx.accounts.multisig_signer.key {
-                acc.is_signer = true;
-            }
-            acc
-        })
-        .collect();
 
-        let (_authority, bump) = Pubkey::find_program_address(&[MULTISIG_SEED_STR.as_bytes()], ctx.program_id);
+    //timelocked, the transaction is public for execution_delay slots once approved
+    let execution_delay = ctx.accounts.multisig.execution_delay;
+    if execution_delay > 0 {
+        let approved_slot = ctx
+            .accounts
+            .transaction
+            .approved_slot
+            .ok_or(ErrorCode::NotEnoughSigners)?;
+        require!(
+            Clock::get()?.slot >= approved_slot.saturating_add(execution_delay),
+            ErrorCode::TimelockNotElapsed
+        );
+    }
+    let (_authority, bump) = Pubkey::find_program_address(&[MULTISIG_SEED_STR.as_bytes()], ctx.program_id);
+    let seeds = &[MULTISIG_SEED_STR.as_bytes(), &[bump]];
+    let signer = &[&seeds[..]];
 
-        let seeds = &[MULTISIG_SEED_STR.as_bytes(), &[bump]];
-        let signer = &[&seeds[..]];
-        let accounts = ctx.remaining_accounts;
-        solana_program::program::invoke_signed(&ix, accounts, signer)?;
+    //execute, remaining accounts hold the accounts and programs of every instruction
+    //a failing instruction reverts the ones before it
+    for instruction in ctx.accounts.transaction.instructions().iter() {
+        let mut ix: Instruction = instruction.into();
+        ix.accounts = ix
+            .accounts
+            .iter()
+            .map(|acc| {
+                let mut acc = acc.clone();
+                if &acc.pubkey == ctx.accounts.multisig_signer.key {
+                    acc.is_signer = true;
+                }
+                acc
+            })
+            .collect();
+        solana_program::program::invoke_signed(&ix, ctx.remaining_accounts, signer)?;
+    }
 
         ctx.accounts.transaction.did_execute = true;
     Ok(())
@@ -327,6 +870,79 @@ pub struct Approve<'info> {
     owner: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ApproveWithSignatures<'info> {
+    #[account(
+        seeds = [
+            MULTISIG_SEED_STR.as_bytes(),
+        ],
+        bump,
+        constraint = multisig.seqno == transaction.seqno
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut, has_one = multisig)]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: instructions sysvar, holds the Ed25519 program instructions with the signatures
+    #[account(address = sysvar::instructions::ID)]
+    instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTransaction<'info> {
+    #[account(
+        seeds = [
+            MULTISIG_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut,
+        has_one = multisig,
+        has_one = proposer,
+        close = proposer,
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: receives the rent of the closed transaction
+    #[account(mut)]
+    proposer: UncheckedAccount<'info>,
+    // The proposer or a multisig owner. Checked in the handler.
+    canceller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredTransaction<'info> {
+    #[account(mut,
+        close = proposer,
+        has_one = proposer,
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: receives the rent of the closed transaction
+    #[account(mut)]
+    proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Veto<'info> {
+    #[account(
+        seeds = [
+            MULTISIG_SEED_STR.as_bytes(),
+        ],
+        bump,
+        constraint = multisig.seqno == transaction.seqno
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut,
+        has_one = multisig,
+        has_one = proposer,
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    /// CHECK: receives the rent of the transaction once it's cancelled
+    #[account(mut)]
+    proposer: UncheckedAccount<'info>,
+    // One of the multisig owners. Checked in the handler.
+    owner: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ExecuteTransaction<'info> {
     #[account(
@@ -382,20 +998,25 @@ pub struct WithdrawFeeAuth<'info> {
     )]
     loan: Box<Account<'info, Loan>>,
     /// CHECK: nothing to see here 😀
-    #[account(mut)]
-    loan_fee_escrow: UncheckedAccount<'info>,
     #[account(
         mut,
+        constraint = loan_fee_escrow.key() == loan_fee.escrow @ ErrorCode::InvalidAccount,
+    )]
+    loan_fee_escrow: UncheckedAccount<'info>,
+    /// CHECK: token_mint is the mint of the fee (Pubkey::default() for SOL)
+    #[account(constraint = token_mint.key() == loan_fee.token_mint @ ErrorCode::InvalidAccount)]
+    token_mint: UncheckedAccount<'info>,
+    #[account(
         seeds = [
             PLATFORM_FEES_SEED_STR.as_bytes(),
         ],
         bump,
     )]
     platform_fees: Box<Account<'info, PlatformFees>>,
+    // origination or interest fee of the loan, a drained one is closed by sweep_fees
     #[account(
         mut,
-        seeds = [LOAN_FEE_STR.as_bytes(),loan.key().as_ref(),],
-        bump,
+        constraint = loan_fee.loan == loan.key() @ ErrorCode::InvalidAccount,
     )]
     loan_fee: Box<Account<'info, LoanFee>>,
     /// CHECK: nothing to see here 😀
@@ -411,20 +1032,21 @@ pub struct WithdrawFeeAuth<'info> {
     #[account(mut)]
     admin: Signer<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+    token_program: Interface<'info, TokenInterface>,
     rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> WithdrawFeeAuth<'info> {
     pub fn transfer_spl_tokens_to_admin_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             from: self.loan_fee_escrow.to_account_info().clone(),
+            mint: self.token_mint.to_account_info().clone(),
             to: self.admin_token_account.to_account_info().clone(),
             authority: self.multisig.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
     pub fn transfer_lamports_to_admin_context(
         &self,
@@ -454,9 +1076,114 @@ pub struct PlatformFeeAuthContext<'info> {
     multisig_signer: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct BenchmarkRateAuthContext<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + BenchmarkRate::MAX_SIZE,
+        seeds = [BENCHMARK_RATE_STR.as_bytes()],
+        bump,
+    )]
+    benchmark_rate: Account<'info, BenchmarkRate>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection: Pubkey)]
+pub struct PricingTableAuthContext<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + PricingTable::MAX_SIZE,
+        seeds = [PRICING_TABLE_STR.as_bytes(), collection.as_ref()],
+        bump,
+    )]
+    pricing_table: Account<'info, PricingTable>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection: Pubkey)]
+pub struct CollectionConfigAuthContext<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CollectionConfig::MAX_SIZE,
+        seeds = [COLLECTION_CONFIG_STR.as_bytes(), collection.as_ref()],
+        bump,
+    )]
+    collection_config: Account<'info, CollectionConfig>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct CurrencyConfigAuthContext<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CurrencyConfig::MAX_SIZE,
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), mint.as_ref()],
+        bump,
+    )]
+    currency_config: Account<'info, CurrencyConfig>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SpendingLimitAuthContext<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + SpendingLimits::MAX_SIZE,
+        seeds = [SPENDING_LIMITS_STR.as_bytes(), mint.as_ref()],
+        bump,
+    )]
+    spending_limits: Account<'info, SpendingLimits>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct CreateTransaction<'info> {
     #[account(
+        mut,
         seeds = [
             MULTISIG_SEED_STR.as_bytes(),
         ],
@@ -464,12 +1191,66 @@ pub struct CreateTransaction<'info> {
     )]
     multisig: Box<Account<'info, Multisig>>,
     #[account(
-        init_if_needed,
+        init,
         payer = proposer,
         space = 8 + Transaction::MAX_SIZE,
         seeds = [
             MULTISIG_TX_SEED_STR.as_bytes(),
-            multisig.seqno.to_le_bytes().as_ref(),
+            multisig.proposal_count.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    #[account(mut)]
+    proposer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(instructions: Vec<TransactionInstruction>)]
+pub struct CreateBatchTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [
+            MULTISIG_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Transaction::space(&instructions),
+        seeds = [
+            MULTISIG_TX_SEED_STR.as_bytes(),
+            multisig.proposal_count.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    transaction: Box<Account<'info, Transaction>>,
+    #[account(mut)]
+    proposer: Signer<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proposal: Proposal)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [
+            MULTISIG_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Transaction::BASE_SIZE + proposal.size(),
+        seeds = [
+            MULTISIG_TX_SEED_STR.as_bytes(),
+            multisig.proposal_count.to_le_bytes().as_ref(),
         ],
         bump
     )]
@@ -487,8 +1268,155 @@ pub struct MultisigCreated {
     pub owners: Vec<Pubkey>,
 }
 
+#[event]
+pub struct TransactionCreated {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub seqno: u32,
+    pub proposer: Pubkey,
+}
+
 #[event]
 pub struct OwnersListUpdated {
     pub old_owners: Vec<Pubkey>,
     pub new_owners: Vec<Pubkey>,
 }
+
+#[event]
+pub struct BenchmarkRateSet {
+    pub rate: u32,
+    pub slot: u64,
+}
+
+#[event]
+pub struct PricingTiersSet {
+    pub collection: Pubkey,
+    pub tiers: Vec<PricingTier>,
+}
+
+#[event]
+pub struct CollectionConfigSet {
+    pub collection: Pubkey,
+    pub allowed: bool,
+    pub max_ltv: u32,
+    pub exposure_cap: u64,
+}
+
+#[event]
+pub struct SpendingLimitSet {
+    pub mint: Pubkey,
+    pub spender: Spender,
+    pub amount: u64,
+    pub period_slots: u64,
+}
+
+#[event]
+pub struct CurrencyConfigSet {
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub debt_ceiling: u64,
+    pub fee_override: Option<u32>,
+}
+
+#[event]
+pub struct FeeModelSet {
+    pub origination_fee: u32,
+    pub interest_fee: u32,
+}
+
+#[event]
+pub struct LatePaymentTermsSet {
+    pub grace_slots: u64,
+    pub penalty_rate: u32,
+    pub late_fee: u64,
+}
+
+#[event]
+pub struct FeeSharesSet {
+    pub owners: Vec<Pubkey>,
+    pub weights: Vec<u16>,
+    pub treasury: Pubkey,
+    pub treasury_bps: u16,
+    pub insurance_bps: u16,
+}
+
+#[event]
+pub struct MaxReferralFeeSet {
+    pub max_referral_bps: u16,
+}
+
+#[event]
+pub struct MaxSpreadSet {
+    pub max_spread: u32,
+}
+
+#[event]
+pub struct ExecutionDelaySet {
+    pub execution_delay: u64,
+}
+
+#[event]
+pub struct TransactionVetoed {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub owner: Pubkey,
+    pub cancelled: bool,
+}
+
+#[event]
+pub struct TransactionApprovedWithSignatures {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub owners: Vec<Pubkey>,
+}
+
+#[event]
+pub struct TransactionRejected {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub owner: Pubkey,
+    pub failed: bool,
+}
+
+#[event]
+pub struct ApprovalRevoked {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct TransactionCancelled {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub canceller: Pubkey,
+}
+
+#[event]
+pub struct MaxProposalSlotsSet {
+    pub max_proposal_slots: u64,
+}
+
+#[event]
+pub struct TransactionExpired {
+    pub transaction: Pubkey,
+    pub index: u32,
+    pub expiry_slot: u64,
+}
+
+#[event]
+pub struct VoteWeightsSet {
+    pub owners: Vec<Pubkey>,
+    pub vote_weights: Vec<u16>,
+    pub threshold: u64,
+}
+
+#[event]
+pub struct RolesSet {
+    pub roles: Vec<RoleConfig>,
+}
+
+#[event]
+pub struct PausedSet {
+    pub paused: bool,
+}
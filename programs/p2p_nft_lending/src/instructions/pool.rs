@@ -0,0 +1,925 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{Metadata, MetadataAccount},
+    token_interface::{
+        spl_token_2022::instruction::AuthorityType, Burn, Mint, MintTo, SetAuthority,
+        TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    errors::ErrorCode,
+    state::{
+        loan::{Loan, LoanRequest, LoanStatus},
+        CurrencyConfig, LendingPool, Multisig, PlatformFees, PoolConfig,
+    },
+    utils::{
+        amount_for_shares, gross_transfer_amount, load_collection_config, max_amount_allowed,
+        pool_interest_rate, record_collection_exposure,
+        pool_utilization, shares_for_deposit, transfer_fee, uncompounded_interest,
+        CURRENCY_CONFIG_STR, LENDING_POOL_STR, LOAN_REQUEST_STR, LOAN_STR, MULTISIG_SEED_STR, NFT_ESCROW_STR,
+        PLATFORM_FEES_SEED_STR, POOL_SHARE_MINT_STR, POOL_VAULT_STR,
+    },
+};
+
+// Creates a (collection, currency) lending pool. The only way this can be invoked
+// is via a recursive call from execute_transaction -> create_pool.
+pub fn create_pool(ctx: Context<CreatePoolContext>, config: PoolConfig) -> Result<()> {
+    LendingPool::init(
+        &mut ctx.accounts.pool,
+        ctx.accounts.collection_mint.key(),
+        ctx.accounts.currency_mint.key(),
+        ctx.accounts.share_mint.key(),
+        ctx.accounts.vault.key(),
+        *ctx.bumps.get("pool").unwrap(),
+        config,
+    )?;
+
+    emit!(PoolCreated {
+        pool: ctx.accounts.pool.key(),
+        collection: ctx.accounts.collection_mint.key(),
+        currency_mint: ctx.accounts.currency_mint.key(),
+        share_mint: ctx.accounts.share_mint.key(),
+    });
+    Ok(())
+}
+
+// Updates the pool's valuation, ltv and rate curve. The only way this can be invoked
+// is via a recursive call from execute_transaction -> set_pool_config.
+pub fn set_pool_config(ctx: Context<PoolConfigAuthContext>, config: PoolConfig) -> Result<()> {
+    LendingPool::set_config(&mut ctx.accounts.pool, config)
+}
+
+///lender deposits tokens into the pool and receives pool shares
+pub fn deposit_to_pool(ctx: Context<PoolDepositContext>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InsufficientFunds);
+    // written down collateral is only valued again once its sale is settled, new shares
+    // priced in between would take part of the proceeds
+    require!(
+        ctx.accounts.pool.pending_sales == 0,
+        ErrorCode::PoolSettlementPending
+    );
+
+    let shares = shares_for_deposit(
+        amount,
+        ctx.accounts.share_mint.supply,
+        ctx.accounts.pool.total_value(Clock::get()?.slot)?,
+    )?;
+    require!(shares > 0, ErrorCode::InsufficientFunds);
+
+    // lender covers any token-2022 transfer fee so the vault receives `amount`
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts.transfer_to_vault_context(),
+        gross_transfer_amount(&ctx.accounts.currency_mint.to_account_info(), amount)?,
+        ctx.accounts.currency_mint.decimals,
+    )?;
+
+    let pool = &ctx.accounts.pool;
+    let bump = &[pool.bump][..];
+    let inner = vec![
+        LENDING_POOL_STR.as_bytes(),
+        pool.collection.as_ref(),
+        pool.currency_mint.as_ref(),
+        bump,
+    ];
+    let outer = vec![inner.as_slice()];
+    anchor_spl::token_interface::mint_to(
+        ctx.accounts
+            .mint_shares_context()
+            .with_signer(outer.as_slice()),
+        shares,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.available_liquidity = pool
+        .available_liquidity
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    emit!(PoolDeposit {
+        pool: pool.key(),
+        lender: ctx.accounts.lender.key(),
+        amount,
+        shares,
+    });
+    Ok(())
+}
+
+///lender burns pool shares for their portion of the pool's idle liquidity
+pub fn withdraw_from_pool(ctx: Context<PoolWithdrawContext>, shares: u64) -> Result<()> {
+    let amount = amount_for_shares(
+        shares,
+        ctx.accounts.share_mint.supply,
+        ctx.accounts.pool.total_value(Clock::get()?.slot)?,
+    )?;
+    require!(amount > 0, ErrorCode::InsufficientFunds);
+    require!(
+        amount <= ctx.accounts.pool.available_liquidity,
+        ErrorCode::InsufficientLiquidity
+    );
+
+    anchor_spl::token_interface::burn(ctx.accounts.burn_shares_context(), shares)?;
+
+    let pool = &ctx.accounts.pool;
+    let bump = &[pool.bump][..];
+    let inner = vec![
+        LENDING_POOL_STR.as_bytes(),
+        pool.collection.as_ref(),
+        pool.currency_mint.as_ref(),
+        bump,
+    ];
+    let outer = vec![inner.as_slice()];
+    // any transfer fee is borne by the lender
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_from_vault_context()
+            .with_signer(outer.as_slice()),
+        amount,
+        ctx.accounts.currency_mint.decimals,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.available_liquidity -= amount;
+
+    emit!(PoolWithdrawal {
+        pool: pool.key(),
+        lender: ctx.accounts.lender.key(),
+        amount,
+        shares,
+    });
+    Ok(())
+}
+
+///borrower draws from the pool against an nft of the pool's collection
+///
+/// the loan is a regular `Loan` (with the pool as its lender) so it is repaid through
+/// `repay_loan` and defaults through `refresh_loan` -> `sell_pool_collateral` -> `buy_nft`
+pub fn borrow_from_pool(
+    ctx: Context<PoolBorrowContext>,
+    amount: u64,
+    slot_duration: u64,
+) -> Result<()> {
+    require!(!ctx.accounts.platform_fees.paused, ErrorCode::Paused);
+    let pool = &ctx.accounts.pool;
+    require!(
+        slot_duration > 0 && slot_duration <= pool.max_slot_duration,
+        ErrorCode::InvalidPoolConfig
+    );
+    require!(
+        amount > 0 && amount <= pool.available_liquidity,
+        ErrorCode::InsufficientLiquidity
+    );
+
+    // rate is taken from the utilization the pool will be at once this loan is drawn
+    let total_borrowed = pool
+        .total_borrowed
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let utilization = pool_utilization(total_borrowed, pool.available_liquidity - amount)?;
+    let mut interest_rate = pool_interest_rate(
+        utilization,
+        pool.base_rate,
+        pool.optimal_utilization,
+        pool.slope1,
+        pool.slope2,
+    )?;
+
+    // a registered collection's risk parameters bound pool loans as they do P2P requests
+    let mut ltv = pool.ltv;
+    let collection_config =
+        load_collection_config(ctx.remaining_accounts, ctx.program_id, &pool.collection)?;
+    if let Some(collection_config) = &collection_config {
+        require!(collection_config.allowed, ErrorCode::CollectionNotAllowed);
+        require!(
+            slot_duration <= collection_config.max_slot_duration,
+            ErrorCode::CollectionLimitExceeded
+        );
+        ltv = std::cmp::min(ltv, collection_config.max_ltv);
+        interest_rate = interest_rate.clamp(
+            collection_config.min_interest_rate,
+            collection_config.max_interest_rate,
+        );
+    }
+
+    let compounded_interest = uncompounded_interest(amount, interest_rate)?;
+    let max_borrow = max_amount_allowed(pool.nft_valuation, ltv)?;
+    require!(
+        compounded_interest <= max_borrow,
+        ErrorCode::MaxBorrowExceeded
+    );
+
+    let platform_fees_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
+    let bump = &[*platform_fees_bump.unwrap()][..];
+    let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
+    let outer = vec![inner.as_slice()];
+
+    //move nft into escrow
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts.transfer_nft_into_escrow_context(),
+        1,
+        ctx.accounts.nft_mint.decimals,
+    )?;
+
+    //change mint authority to platform_fees and mint borrow nft
+    assert_eq!(ctx.accounts.borrow_nft_mint.supply, 0);
+    anchor_spl::token_interface::set_authority(
+        ctx.accounts.set_mint_authority_context(),
+        AuthorityType::MintTokens,
+        Some(ctx.accounts.platform_fees.key()),
+    )?;
+    anchor_spl::token_interface::mint_to(
+        ctx.accounts
+            .mint_borrow_nft_context()
+            .with_signer(outer.as_slice()),
+        1,
+    )?;
+
+    // draw the loan straight out of the vault, any transfer fee is borne by the borrower
+    let pool = &ctx.accounts.pool;
+    let bump = &[pool.bump][..];
+    let inner = vec![
+        LENDING_POOL_STR.as_bytes(),
+        pool.collection.as_ref(),
+        pool.currency_mint.as_ref(),
+        bump,
+    ];
+    let outer = vec![inner.as_slice()];
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_from_vault_context()
+            .with_signer(outer.as_slice()),
+        amount,
+        ctx.accounts.currency_mint.decimals,
+    )?;
+
+    let clock = Clock::get()?;
+    let nft_valuation = ctx.accounts.pool.nft_valuation;
+    LoanRequest::init(
+        &mut ctx.accounts.loan_request,
+        nft_valuation,
+        ctx.accounts.nft_mint.key(),
+        amount,
+        ctx.accounts.currency_mint.key(),
+        slot_duration,
+        ctx.accounts.borrow_nft_mint.key(),
+    )?;
+    ctx.accounts.loan_request.loan = Some(ctx.accounts.loan.key());
+
+    //the pool stands in for the lend nft
+    Loan::init(
+        &mut ctx.accounts.loan,
+        ctx.accounts.nft_mint.key(),
+        ctx.accounts.borrow_nft_mint.key(),
+        ctx.accounts.pool.key(),
+        ctx.accounts.currency_mint.key(),
+        ltv,
+        0,
+        interest_rate,
+        nft_valuation,
+        amount,
+        slot_duration,
+        clock.slot,
+    )?;
+    //tokens went straight to the borrower
+    ctx.accounts.loan.status = LoanStatus::TokensWithdrawn.to_code();
+    if collection_config.is_some() {
+        let collection = ctx.accounts.pool.collection;
+        record_collection_exposure(
+            ctx.remaining_accounts,
+            ctx.program_id,
+            &collection,
+            &mut ctx.accounts.loan,
+        )?;
+        // lets release_collection_exposure find the config once the loan is settled
+        ctx.accounts.loan_request.collection = collection;
+    }
+
+    ctx.accounts.currency_config.add_debt(amount)?;
+    ctx.accounts.loan.currency_debt = amount;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.available_liquidity -= amount;
+    pool.add_loan(amount, interest_rate, clock.slot)?;
+
+    emit!(PoolLoanDrawn {
+        pool: pool.key(),
+        loan: ctx.accounts.loan.key(),
+        loan_request: ctx.accounts.loan_request.key(),
+        nft_mint: ctx.accounts.nft_mint.key(),
+        amount,
+        interest_rate,
+        duration: slot_duration,
+    });
+    Ok(())
+}
+
+///a multisig owner lists the collateral of a defaulted pool loan for sale (the pool can't
+/// sign `sell_nft`)
+///
+/// the loan is written off the pool's books until the sale proceeds are settled. A pool loan
+/// only defaults once, refunding the borrower closes it as Settled
+pub fn sell_pool_collateral(ctx: Context<SellPoolCollateralContext>) -> Result<()> {
+    require!(
+        ctx.accounts.multisig.owners.contains(ctx.accounts.lister.key),
+        ErrorCode::InvalidOwner
+    );
+    require!(
+        ctx.accounts.loan.status == LoanStatus::Defaulted.to_code(),
+        ErrorCode::InvalidLoanState
+    );
+    ctx.accounts.loan.status = LoanStatus::Sell.to_code();
+
+    let loan = &ctx.accounts.loan;
+    let pool = &mut ctx.accounts.pool;
+    pool.remove_loan(loan.requested_amount, loan.interest_rate, loan.start_slot)?;
+    pool.pending_sales += 1;
+    Ok(())
+}
+
+// Takes listed pool collateral that found no buyer out of escrow, the loan stays written down
+// and deposits reopen. The only way this can be invoked is via a recursive call from
+// execute_transaction -> seize_pool_collateral.
+pub fn seize_pool_collateral(ctx: Context<SeizePoolCollateralContext>) -> Result<()> {
+    require!(
+        ctx.accounts.loan.status == LoanStatus::Sell.to_code(),
+        ErrorCode::InvalidLoanState
+    );
+
+    let platform_fees_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
+    let bump = &[*platform_fees_bump.unwrap()][..];
+    let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
+    let outer = vec![inner.as_slice()];
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_nft_from_escrow_context()
+            .with_signer(outer.as_slice()),
+        1,
+        ctx.accounts.nft_mint.decimals,
+    )?;
+
+    ctx.accounts.loan.status = LoanStatus::Seize.to_code();
+    let pool = &mut ctx.accounts.pool;
+    pool.pending_sales = pool.pending_sales.saturating_sub(1);
+
+    emit!(PoolCollateralSeized {
+        pool: pool.key(),
+        loan: ctx.accounts.loan.key(),
+        nft_mint: ctx.accounts.nft_mint.key(),
+        destination: ctx.accounts.destination.key(),
+    });
+    Ok(())
+}
+
+///moves the repayment / sale proceeds of a pool loan from the loan escrow back into the pool
+pub fn settle_pool_loan(ctx: Context<SettlePoolLoanContext>) -> Result<()> {
+    let (proceeds, status) = match LoanStatus::from(ctx.accounts.loan.status)? {
+        LoanStatus::Repaid => (ctx.accounts.loan.paid_amount, LoanStatus::Settled),
+        // borrower can still withdraw what they paid before defaulting
+        LoanStatus::Sold => (ctx.accounts.loan.nft_worth, LoanStatus::Completed),
+        _ => return Err(ErrorCode::InvalidLoanState.into()),
+    };
+
+    let platform_fees_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
+    let bump = &[*platform_fees_bump.unwrap()][..];
+    let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
+    let outer = vec![inner.as_slice()];
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_to_vault_context()
+            .with_signer(outer.as_slice()),
+        proceeds,
+        ctx.accounts.currency_mint.decimals,
+    )?;
+    let received = proceeds - transfer_fee(&ctx.accounts.currency_mint.to_account_info(), proceeds)?;
+
+    let loan = &ctx.accounts.loan;
+    let pool = &mut ctx.accounts.pool;
+    if matches!(status, LoanStatus::Settled) {
+        pool.remove_loan(loan.requested_amount, loan.interest_rate, loan.start_slot)?;
+    } else {
+        // written down when its collateral was listed
+        pool.pending_sales = pool.pending_sales.saturating_sub(1);
+    }
+    pool.available_liquidity = pool
+        .available_liquidity
+        .checked_add(received)
+        .ok_or(ErrorCode::MathOverflow)?;
+    ctx.accounts.loan.status = status.to_code();
+
+    emit!(PoolLoanSettled {
+        pool: pool.key(),
+        loan: ctx.accounts.loan.key(),
+        principal: ctx.accounts.loan.requested_amount,
+        proceeds: received,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreatePoolContext<'info> {
+    collection_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    currency_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + LendingPool::MAX_SIZE,
+        seeds = [
+            LENDING_POOL_STR.as_bytes(),
+            collection_mint.key().as_ref(),
+            currency_mint.key().as_ref(),
+        ],
+        bump,
+    )]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [POOL_SHARE_MINT_STR.as_bytes(), pool.key().as_ref()],
+        bump,
+        mint::decimals = currency_mint.decimals,
+        mint::authority = pool,
+        mint::token_program = token_program,
+    )]
+    share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [POOL_VAULT_STR.as_bytes(), pool.key().as_ref()],
+        bump,
+        token::mint = currency_mint,
+        token::authority = pool,
+        token::token_program = token_program,
+    )]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    #[account(mut)]
+    payer: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PoolConfigAuthContext<'info> {
+    #[account(mut)]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PoolDepositContext<'info> {
+    #[account(
+        mut,
+        has_one = currency_mint,
+        has_one = share_mint,
+        has_one = vault,
+    )]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    currency_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=currency_mint,
+        token::authority=lender,
+    )]
+    lender_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=share_mint,
+        token::authority=lender,
+    )]
+    lender_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    lender: Signer<'info>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> PoolDepositContext<'info> {
+    pub fn transfer_to_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.lender_token_account.to_account_info().clone(),
+            mint: self.currency_mint.to_account_info().clone(),
+            to: self.vault.to_account_info().clone(),
+            authority: self.lender.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn mint_shares_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.share_mint.to_account_info().clone(),
+            to: self.lender_share_account.to_account_info().clone(),
+            authority: self.pool.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct PoolWithdrawContext<'info> {
+    #[account(
+        mut,
+        has_one = currency_mint,
+        has_one = share_mint,
+        has_one = vault,
+    )]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    currency_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=currency_mint,
+        token::authority=lender,
+    )]
+    lender_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=share_mint,
+        token::authority=lender,
+    )]
+    lender_share_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    lender: Signer<'info>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> PoolWithdrawContext<'info> {
+    pub fn burn_shares_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let burn_accounts = Burn {
+            from: self.lender_share_account.to_account_info().clone(),
+            authority: self.lender.to_account_info().clone(),
+            mint: self.share_mint.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), burn_accounts)
+    }
+    pub fn transfer_from_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.vault.to_account_info().clone(),
+            mint: self.currency_mint.to_account_info().clone(),
+            to: self.lender_token_account.to_account_info().clone(),
+            authority: self.pool.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[derive(Accounts)]
+pub struct PoolBorrowContext<'info> {
+    #[account(
+        mut,
+        has_one = currency_mint,
+        has_one = vault,
+    )]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    currency_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), currency_mint.key().as_ref()],
+        bump = currency_config.bump,
+    )]
+    currency_config: Box<Account<'info, CurrencyConfig>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=currency_mint,
+        token::authority=borrower,
+    )]
+    borrower_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mint::token_program=nft_token_program,
+    )]
+    nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [
+            b"metadata",
+            token_metadata_program.key().as_ref(),
+            nft_mint.key().as_ref(),
+        ],
+        seeds::program = token_metadata_program.key(),
+        bump,
+        constraint = nft_metadata
+            .collection
+            .as_ref()
+            .map(|collection| collection.verified && collection.key == pool.collection)
+            .unwrap_or(false) @ ErrorCode::IneligibleCollateral,
+    )]
+    nft_metadata: Box<Account<'info, MetadataAccount>>,
+    #[account(
+        mut,
+        token::mint=nft_mint,
+        token::authority=borrower,
+        token::token_program=nft_token_program,
+    )]
+    nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        mint::token_program=position_token_program,
+    )]
+    borrow_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint=borrow_nft_mint,
+        token::authority=borrower,
+        token::token_program=position_token_program,
+    )]
+    borrow_nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        seeds = [LOAN_REQUEST_STR.as_bytes(), borrow_nft_mint.key().as_ref()],
+        bump,
+        payer = borrower,
+        space = 8 + LoanRequest::MAX_SIZE,
+    )]
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::MAX_SIZE,
+        seeds = [LOAN_STR.as_bytes(), loan_request.key().as_ref(), pool.key().as_ref()],
+        bump,
+    )]
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        seeds = [
+            PLATFORM_FEES_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    platform_fees: Box<Account<'info, PlatformFees>>,
+    #[account(
+        init,
+        payer = borrower,
+        seeds=[NFT_ESCROW_STR.as_bytes(), loan_request.key().as_ref()],
+        bump,
+        token::mint=nft_mint,
+        token::authority=platform_fees,
+        token::token_program=nft_token_program,
+    )]
+    nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    borrower: Signer<'info>,
+    system_program: Program<'info, System>,
+    /// program of the pool currency
+    token_program: Interface<'info, TokenInterface>,
+    /// program of the collateral nft (spl-token or token-2022)
+    nft_token_program: Interface<'info, TokenInterface>,
+    /// program of the borrow nft
+    position_token_program: Interface<'info, TokenInterface>,
+    token_metadata_program: Program<'info, Metadata>,
+    rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> PoolBorrowContext<'info> {
+    pub fn transfer_nft_into_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
+            from: self.nft_token_account.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
+            to: self.nft_escrow.to_account_info().clone(),
+            authority: self.borrower.to_account_info().clone(),
+        };
+        CpiContext::new(
+            self.nft_token_program.to_account_info().clone(),
+            transfer_accounts,
+        )
+    }
+    pub fn set_mint_authority_context(&self) -> CpiContext<'_, '_, '_, 'info, SetAuthority<'info>> {
+        let cpi_accounts = SetAuthority {
+            account_or_mint: self.borrow_nft_mint.to_account_info().clone(),
+            current_authority: self.borrower.to_account_info().clone(),
+        };
+        CpiContext::new(self.position_token_program.to_account_info().clone(), cpi_accounts)
+    }
+    pub fn mint_borrow_nft_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.borrow_nft_mint.to_account_info().clone(),
+            to: self.borrow_nft_token_account.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+        };
+        CpiContext::new(self.position_token_program.to_account_info().clone(), cpi_accounts)
+    }
+    pub fn transfer_from_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.vault.to_account_info().clone(),
+            mint: self.currency_mint.to_account_info().clone(),
+            to: self.borrower_token_account.to_account_info().clone(),
+            authority: self.pool.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SellPoolCollateralContext<'info> {
+    #[account(mut)]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        mut,
+        constraint=loan.lend_nft_mint == pool.key() @ ErrorCode::InvalidAccount,
+    )]
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    lister: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SeizePoolCollateralContext<'info> {
+    #[account(mut)]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        mut,
+        constraint=loan.lend_nft_mint == pool.key() @ ErrorCode::InvalidAccount,
+    )]
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        seeds = [LOAN_REQUEST_STR.as_bytes(), loan.borrow_nft_mint.as_ref()],
+        bump,
+    )]
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        mint::token_program=nft_token_program,
+        constraint=nft_mint.key() == loan.nft_mint @ ErrorCode::InvalidAccount,
+    )]
+    nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds=[NFT_ESCROW_STR.as_bytes(), loan_request.key().as_ref()],
+        bump,
+        token::mint=nft_mint,
+        token::authority=platform_fees,
+        token::token_program=nft_token_program,
+    )]
+    nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// token account the multisig takes the collateral into
+    #[account(
+        mut,
+        token::mint=nft_mint,
+        token::token_program=nft_token_program,
+    )]
+    destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [
+            PLATFORM_FEES_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    platform_fees: Box<Account<'info, PlatformFees>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig_signer: Signer<'info>,
+    nft_token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SeizePoolCollateralContext<'info> {
+    pub fn transfer_nft_from_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.nft_escrow.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
+            to: self.destination.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+        };
+        CpiContext::new(self.nft_token_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SettlePoolLoanContext<'info> {
+    #[account(
+        mut,
+        has_one = currency_mint,
+        has_one = vault,
+    )]
+    pool: Box<Account<'info, LendingPool>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    currency_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint=loan.lend_nft_mint == pool.key() @ ErrorCode::InvalidAccount,
+    )]
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        seeds = [
+            PLATFORM_FEES_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    platform_fees: Box<Account<'info, PlatformFees>>,
+    #[account(
+        mut,
+        token::mint=currency_mint,
+        token::authority=platform_fees,
+    )]
+    loan_token_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SettlePoolLoanContext<'info> {
+    pub fn transfer_to_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.currency_mint.to_account_info().clone(),
+            to: self.vault.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+}
+
+//Events
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub collection: Pubkey,
+    pub currency_mint: Pubkey,
+    pub share_mint: Pubkey,
+}
+
+#[event]
+pub struct PoolDeposit {
+    pub pool: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct PoolWithdrawal {
+    pub pool: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct PoolLoanDrawn {
+    pub pool: Pubkey,
+    pub loan: Pubkey,
+    pub loan_request: Pubkey,
+    pub nft_mint: Pubkey,
+    pub amount: u64,
+    pub interest_rate: u32,
+    pub duration: u64,
+}
+
+#[event]
+pub struct PoolCollateralSeized {
+    pub pool: Pubkey,
+    pub loan: Pubkey,
+    pub nft_mint: Pubkey,
+    pub destination: Pubkey,
+}
+
+#[event]
+pub struct PoolLoanSettled {
+    pub pool: Pubkey,
+    pub loan: Pubkey,
+    pub principal: u64,
+    pub proceeds: u64,
+}
@@ -0,0 +1,808 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{AssociatedToken, Create},
+    token_interface::{Burn, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    errors::ErrorCode,
+    state::{
+        loan::{Loan, LoanRequest, LoanStatus},
+        CurrencyConfig, LoanFee, Multisig, PlatformFees, Syndicate,
+    },
+    utils::{
+        amount_for_shares, calculate_fees, create_interest_fee, fee_escrow_address, gross_transfer_amount,
+        record_collection_exposure, referral_shares, transfer_fee, CURRENCY_CONFIG_STR, INTEREST_FEE_STR,
+        LOAN_FEE_STR, LOAN_STR, MULTISIG_SEED_STR, PLATFORM_FEES_SEED_STR,
+        SYNDICATE_NOTE_MINT_STR, SYNDICATE_STR, SYNDICATE_VAULT_STR,
+    },
+};
+
+///borrower opens their loan request to partial funding from several lenders
+pub fn open_syndicate(ctx: Context<OpenSyndicateContext>, deadline_slot: u64) -> Result<()> {
+    require!(
+        deadline_slot > Clock::get()?.slot,
+        ErrorCode::SyndicateClosed
+    );
+
+    Syndicate::init(
+        &mut ctx.accounts.syndicate,
+        ctx.accounts.loan_request.key(),
+        ctx.accounts.requested_token_mint.key(),
+        ctx.accounts.note_mint.key(),
+        ctx.accounts.vault.key(),
+        deadline_slot,
+        *ctx.bumps.get("syndicate").unwrap(),
+    );
+    // blocks grant_loan / cancel_loan_request while the syndicate is open
+    ctx.accounts.loan_request.loan = Some(ctx.accounts.syndicate.key());
+
+    emit!(SyndicateOpened {
+        syndicate: ctx.accounts.syndicate.key(),
+        loan_request: ctx.accounts.loan_request.key(),
+        note_mint: ctx.accounts.note_mint.key(),
+        requested_amount: ctx.accounts.loan_request.requested_amount,
+        deadline_slot,
+    });
+    Ok(())
+}
+
+///lender funds part of a syndicated loan and receives lend notes 1:1
+pub fn fund_syndicate(ctx: Context<FundSyndicateContext>, amount: u64) -> Result<()> {
+    let syndicate = &ctx.accounts.syndicate;
+    require!(
+        syndicate.loan.is_none() && Clock::get()?.slot <= syndicate.deadline_slot,
+        ErrorCode::SyndicateClosed
+    );
+    let remaining = ctx.accounts.loan_request.requested_amount - syndicate.funded_amount;
+    require!(amount > 0 && amount <= remaining, ErrorCode::InsufficientFunds);
+
+    // lender covers any token-2022 transfer fee so the vault receives `amount`
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts.transfer_to_vault_context(),
+        gross_transfer_amount(&ctx.accounts.requested_token_mint.to_account_info(), amount)?,
+        ctx.accounts.requested_token_mint.decimals,
+    )?;
+
+    let loan_request = ctx.accounts.loan_request.key();
+    let bump = &[syndicate.bump][..];
+    let inner = vec![SYNDICATE_STR.as_bytes(), loan_request.as_ref(), bump];
+    let outer = vec![inner.as_slice()];
+    anchor_spl::token_interface::mint_to(
+        ctx.accounts
+            .mint_notes_context()
+            .with_signer(outer.as_slice()),
+        amount,
+    )?;
+
+    ctx.accounts.syndicate.funded_amount += amount;
+
+    emit!(SyndicateFunded {
+        syndicate: ctx.accounts.syndicate.key(),
+        lender: ctx.accounts.lender.key(),
+        amount,
+        funded_amount: ctx.accounts.syndicate.funded_amount,
+    });
+    Ok(())
+}
+
+///borrower draws a fully funded syndicated loan, the platform fee is taken from the vault
+pub fn draw_syndicated_loan(ctx: Context<DrawSyndicatedLoanContext>) -> Result<()> {
+    require!(!ctx.accounts.platform_fees.paused, ErrorCode::Paused);
+    let requested_amount = ctx.accounts.loan_request.requested_amount;
+    require!(
+        ctx.accounts.syndicate.loan.is_none(),
+        ErrorCode::InvalidLoanState
+    );
+    require!(
+        ctx.accounts.syndicate.funded_amount == requested_amount,
+        ErrorCode::SyndicateNotFunded
+    );
+
+    let fee = calculate_fees(requested_amount, ctx.accounts.loan_request.fee_percentage)
+        .unwrap()
+        .try_round_u64()
+        .unwrap();
+    // origination fee can be switched off in favour of a fee on interest
+    assert!(fee > 0 || ctx.accounts.loan_request.fee_percentage == 0);
+    let remainder = requested_amount - fee;
+
+    // repayments land in the loan token escrow, which grant_loan would otherwise have created
+    anchor_spl::associated_token::create_idempotent(ctx.accounts.create_loan_token_escrow_context())?;
+    anchor_spl::associated_token::create_idempotent(ctx.accounts.create_loan_fee_escrow_context())?;
+
+    // vault -> fee escrow / borrower, recipients bear any transfer fee
+    let loan_request = ctx.accounts.loan_request.key();
+    let bump = &[ctx.accounts.syndicate.bump][..];
+    let inner = vec![SYNDICATE_STR.as_bytes(), loan_request.as_ref(), bump];
+    let outer = vec![inner.as_slice()];
+    let decimals = ctx.accounts.requested_token_mint.decimals;
+    if fee > 0 {
+        anchor_spl::token_interface::transfer_checked(
+            ctx.accounts
+                .transfer_fee_from_vault_context()
+                .with_signer(outer.as_slice()),
+            fee,
+            decimals,
+        )?;
+    }
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_to_borrower_context()
+            .with_signer(outer.as_slice()),
+        remainder,
+        decimals,
+    )?;
+
+    //the syndicate stands in for the lend nft
+    let clock = Clock::get()?;
+    Loan::init(
+        &mut ctx.accounts.loan,
+        ctx.accounts.loan_request.nft_mint,
+        ctx.accounts.loan_request.borrow_nft_mint,
+        ctx.accounts.syndicate.key(),
+        ctx.accounts.requested_token_mint.key(),
+        ctx.accounts.loan_request.ltv,
+        ctx.accounts.loan_request.fee_percentage,
+        ctx.accounts.loan_request.interest_rate,
+        ctx.accounts.loan_request.nft_worth,
+        requested_amount,
+        ctx.accounts.loan_request.slot_duration,
+        clock.slot,
+    )?;
+    //tokens went straight to the borrower
+    ctx.accounts.loan.status = LoanStatus::TokensWithdrawn.to_code();
+    ctx.accounts.loan.notice_slots = ctx.accounts.loan_request.notice_slots;
+    ctx.accounts.loan.interest_fee_percentage = ctx.accounts.platform_fees.interest_fee_percentage;
+    ctx.accounts.loan.set_late_terms(
+        ctx.accounts.platform_fees.grace_slots,
+        ctx.accounts.platform_fees.penalty_rate,
+        ctx.accounts.platform_fees.late_fee,
+    );
+    record_collection_exposure(
+        ctx.remaining_accounts,
+        ctx.program_id,
+        &ctx.accounts.loan_request.collection,
+        &mut ctx.accounts.loan,
+    )?;
+    ctx.accounts
+        .currency_config
+        .add_debt(ctx.accounts.loan.requested_amount)?;
+    ctx.accounts.loan.currency_debt = ctx.accounts.loan.requested_amount;
+    // the borrower's referrer earns their cut of every fee on the loan, as on granted loans
+    ctx.accounts.loan.referrals = ctx.accounts.loan_request.referral.into_iter().collect();
+
+    let fee_received =
+        fee - transfer_fee(&ctx.accounts.requested_token_mint.to_account_info(), fee)?;
+    LoanFee::init(
+        &mut ctx.accounts.loan_fee,
+        fee_received,
+        ctx.accounts.requested_token_mint.key(),
+        ctx.accounts.loan.key(),
+        ctx.accounts.loan_fee_escrow.key(),
+        referral_shares(
+            ctx.accounts.multisig.fee_shares(),
+            &ctx.accounts.loan.referrals,
+        ),
+    );
+
+    ctx.accounts.loan_request.loan = Some(ctx.accounts.loan.key());
+    ctx.accounts.syndicate.loan = Some(ctx.accounts.loan.key());
+
+    emit!(SyndicatedLoanDrawn {
+        syndicate: ctx.accounts.syndicate.key(),
+        loan: ctx.accounts.loan.key(),
+        loan_request: ctx.accounts.loan_request.key(),
+        requested_amount,
+        duration: ctx.accounts.loan_request.slot_duration,
+    });
+    Ok(())
+}
+
+///lender burns notes of a syndicate that never filled and gets their contribution back
+pub fn refund_syndicate(ctx: Context<RefundSyndicateContext>, notes: u64) -> Result<()> {
+    let syndicate = &ctx.accounts.syndicate;
+    require!(syndicate.loan.is_none(), ErrorCode::InvalidLoanState);
+    require!(
+        Clock::get()?.slot > syndicate.deadline_slot,
+        ErrorCode::SyndicateStillOpen
+    );
+    require!(notes > 0, ErrorCode::InsufficientFunds);
+
+    anchor_spl::token_interface::burn(ctx.accounts.burn_notes_context(), notes)?;
+
+    let loan_request = ctx.accounts.loan_request.key();
+    let bump = &[syndicate.bump][..];
+    let inner = vec![SYNDICATE_STR.as_bytes(), loan_request.as_ref(), bump];
+    let outer = vec![inner.as_slice()];
+    // any transfer fee is borne by the lender
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_from_vault_context()
+            .with_signer(outer.as_slice()),
+        notes,
+        ctx.accounts.requested_token_mint.decimals,
+    )?;
+    ctx.accounts.syndicate.funded_amount -= notes;
+
+    // release the loan request so the borrower can cancel it or look for a single lender
+    if ctx.accounts.loan_request.loan == Some(ctx.accounts.syndicate.key()) {
+        ctx.accounts.loan_request.loan = None;
+    }
+
+    emit!(SyndicateRefunded {
+        syndicate: ctx.accounts.syndicate.key(),
+        lender: ctx.accounts.lender.key(),
+        amount: notes,
+    });
+    Ok(())
+}
+
+///lists the collateral of a defaulted syndicated loan for sale (note holders can't sign `sell_nft`)
+pub fn sell_syndicate_collateral(ctx: Context<SellSyndicateCollateralContext>) -> Result<()> {
+    require!(
+        ctx.accounts.loan.status == LoanStatus::Defaulted.to_code(),
+        ErrorCode::InvalidLoanState
+    );
+    ctx.accounts.loan.status = LoanStatus::Sell.to_code();
+    Ok(())
+}
+
+///note holder burns notes for their pro rata share of the repayment / sale proceeds
+pub fn claim_syndicate(ctx: Context<ClaimSyndicateContext>, notes: u64) -> Result<()> {
+    let proceeds = match LoanStatus::from(ctx.accounts.loan.status)? {
+        LoanStatus::Repaid => ctx.accounts.loan.paid_amount,
+        LoanStatus::Sold => ctx.accounts.loan.nft_worth,
+        _ => return Err(ErrorCode::InvalidLoanState.into()),
+    };
+    // the platform's cut of the interest collected, as lender_withdraw_tokens takes it
+    let total_debt = ctx.accounts.loan.paid_amount + ctx.accounts.loan.outstanding_debt;
+    let interest_collected = std::cmp::min(proceeds, total_debt)
+        .saturating_sub(ctx.accounts.loan.requested_amount);
+    let interest_fee = calculate_fees(
+        interest_collected,
+        ctx.accounts.loan.interest_fee_percentage,
+    )?
+    .try_round_u64()?;
+    // note holders cover any transfer fee so the fee escrow receives the booked interest_fee
+    let fee_transfer_amount = if interest_fee > 0 {
+        gross_transfer_amount(&ctx.accounts.requested_token_mint.to_account_info(), interest_fee)?
+    } else {
+        0
+    };
+    let unclaimed = proceeds
+        .checked_sub(fee_transfer_amount)
+        .and_then(|distributable| distributable.checked_sub(ctx.accounts.syndicate.claimed_amount))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let note_supply = ctx.accounts.note_mint.supply;
+    let amount = amount_for_shares(notes, note_supply, unclaimed)?;
+    require!(amount > 0, ErrorCode::InsufficientFunds);
+
+    anchor_spl::token_interface::burn(ctx.accounts.burn_notes_context(), notes)?;
+
+    let platform_fees_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
+    let bump = &[*platform_fees_bump.unwrap()][..];
+    let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump];
+    let outer = vec![inner.as_slice()];
+    // the first claim books the interest fee for every note holder
+    if ctx.accounts.syndicate.claimed_amount == 0 {
+        require!(
+            ctx.accounts.loan_fee_escrow.key()
+                == fee_escrow_address(
+                    &ctx.accounts.multisig.key(),
+                    &ctx.accounts.requested_token_mint.key(),
+                    &ctx.accounts.token_program.key(),
+                    ctx.program_id,
+                ),
+            ErrorCode::InvalidAccount
+        );
+        if interest_fee > 0 {
+            let mut loan_fee = create_interest_fee(
+                &ctx.accounts.interest_fee,
+                &ctx.accounts.lender,
+                &ctx.accounts.system_program,
+                &ctx.accounts.loan.key(),
+                *ctx.bumps.get("interest_fee").unwrap(),
+            )?;
+            LoanFee::init(
+                &mut loan_fee,
+                interest_fee,
+                ctx.accounts.requested_token_mint.key(),
+                ctx.accounts.loan.key(),
+                ctx.accounts.loan_fee_escrow.key(),
+                referral_shares(
+                    ctx.accounts.multisig.fee_shares(),
+                    &ctx.accounts.loan.referrals,
+                ),
+            );
+            loan_fee.exit(ctx.program_id)?;
+            anchor_spl::token_interface::transfer_checked(
+                ctx.accounts
+                    .transfer_fee_from_escrow_context()
+                    .with_signer(outer.as_slice()),
+                fee_transfer_amount,
+                ctx.accounts.requested_token_mint.decimals,
+            )?;
+        }
+    }
+    // any transfer fee is borne by the lender
+    anchor_spl::token_interface::transfer_checked(
+        ctx.accounts
+            .transfer_from_escrow_context()
+            .with_signer(outer.as_slice()),
+        amount,
+        ctx.accounts.requested_token_mint.decimals,
+    )?;
+    ctx.accounts.syndicate.claimed_amount += amount;
+
+    //last note holder out
+    if notes == note_supply {
+        ctx.accounts.loan.status = LoanStatus::Completed.to_code();
+    }
+
+    emit!(SyndicateClaimed {
+        syndicate: ctx.accounts.syndicate.key(),
+        lender: ctx.accounts.lender.key(),
+        notes,
+        amount,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct OpenSyndicateContext<'info> {
+    #[account(
+        mut,
+        constraint=loan_request.loan.is_none() @ ErrorCode::InvalidLoanState,
+    )]
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        token::mint=loan_request.borrow_nft_mint,
+        token::authority=borrower,
+        constraint=borrow_nft_token_account.amount == 1 @ ErrorCode::InvalidAccount,
+    )]
+    borrow_nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        constraint=requested_token_mint.key() == loan_request.requested_token_mint @ ErrorCode::InvalidAccount,
+        mint::token_program=token_program,
+    )]
+    requested_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Syndicate::MAX_SIZE,
+        seeds = [SYNDICATE_STR.as_bytes(), loan_request.key().as_ref()],
+        bump,
+    )]
+    syndicate: Box<Account<'info, Syndicate>>,
+    #[account(
+        init,
+        payer = borrower,
+        seeds = [SYNDICATE_NOTE_MINT_STR.as_bytes(), syndicate.key().as_ref()],
+        bump,
+        mint::decimals = requested_token_mint.decimals,
+        mint::authority = syndicate,
+        mint::token_program = token_program,
+    )]
+    note_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = borrower,
+        seeds = [SYNDICATE_VAULT_STR.as_bytes(), syndicate.key().as_ref()],
+        bump,
+        token::mint = requested_token_mint,
+        token::authority = syndicate,
+        token::token_program = token_program,
+    )]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    borrower: Signer<'info>,
+    system_program: Program<'info, System>,
+    /// program of the requested token
+    token_program: Interface<'info, TokenInterface>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundSyndicateContext<'info> {
+    #[account(
+        mut,
+        seeds = [SYNDICATE_STR.as_bytes(), loan_request.key().as_ref()],
+        bump = syndicate.bump,
+        has_one = loan_request,
+        has_one = requested_token_mint,
+        has_one = note_mint,
+        has_one = vault,
+    )]
+    syndicate: Box<Account<'info, Syndicate>>,
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    requested_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    note_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=requested_token_mint,
+        token::authority=lender,
+    )]
+    lender_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=note_mint,
+        token::authority=lender,
+    )]
+    lender_note_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    lender: Signer<'info>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FundSyndicateContext<'info> {
+    pub fn transfer_to_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.lender_token_account.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.vault.to_account_info().clone(),
+            authority: self.lender.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn mint_notes_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.note_mint.to_account_info().clone(),
+            to: self.lender_note_account.to_account_info().clone(),
+            authority: self.syndicate.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+#[derive(Accounts)]
+pub struct DrawSyndicatedLoanContext<'info> {
+    #[account(
+        mut,
+        seeds = [SYNDICATE_STR.as_bytes(), loan_request.key().as_ref()],
+        bump = syndicate.bump,
+        has_one = loan_request,
+        has_one = requested_token_mint,
+        has_one = vault,
+    )]
+    syndicate: Box<Account<'info, Syndicate>>,
+    #[account(mut)]
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        token::mint=loan_request.borrow_nft_mint,
+        token::authority=borrower,
+        constraint=borrow_nft_token_account.amount == 1 @ ErrorCode::InvalidAccount,
+    )]
+    borrow_nft_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    requested_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), requested_token_mint.key().as_ref()],
+        bump = currency_config.bump,
+    )]
+    currency_config: Box<Account<'info, CurrencyConfig>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=requested_token_mint,
+        token::authority=borrower,
+    )]
+    borrower_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + Loan::MAX_SIZE,
+        seeds = [LOAN_STR.as_bytes(), loan_request.key().as_ref(), syndicate.key().as_ref()],
+        bump,
+    )]
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        init,
+        payer = borrower,
+        space = 8 + LoanFee::MAX_SIZE,
+        seeds = [LOAN_FEE_STR.as_bytes(), loan.key().as_ref()],
+        bump,
+    )]
+    loan_fee: Box<Account<'info, LoanFee>>,
+    /// CHECK: loan_fee_escrow is the multisig's associated token account, created if needed
+    #[account(mut)]
+    loan_fee_escrow: UncheckedAccount<'info>,
+    /// CHECK: loan_token_escrow is the platform_fees associated token account, created if needed
+    #[account(mut)]
+    loan_token_escrow: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            PLATFORM_FEES_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    platform_fees: Box<Account<'info, PlatformFees>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut)]
+    borrower: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> DrawSyndicatedLoanContext<'info> {
+    pub fn create_loan_token_escrow_context(&self) -> CpiContext<'_, '_, '_, 'info, Create<'info>> {
+        let create_loan_escrow_account = Create {
+            payer: self.borrower.to_account_info().clone(),
+            associated_token: self.loan_token_escrow.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            system_program: self.system_program.to_account_info().clone(),
+            token_program: self.token_program.to_account_info().clone(),
+        };
+        CpiContext::new(
+            self.associated_token_program.to_account_info().clone(),
+            create_loan_escrow_account,
+        )
+    }
+    pub fn create_loan_fee_escrow_context(&self) -> CpiContext<'_, '_, '_, 'info, Create<'info>> {
+        let create_loan_fee_escrow_accounts = Create {
+            payer: self.borrower.to_account_info().clone(),
+            associated_token: self.loan_fee_escrow.to_account_info().clone(),
+            authority: self.multisig.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            system_program: self.system_program.to_account_info().clone(),
+            token_program: self.token_program.to_account_info().clone(),
+        };
+        CpiContext::new(
+            self.associated_token_program.to_account_info().clone(),
+            create_loan_fee_escrow_accounts,
+        )
+    }
+    pub fn transfer_fee_from_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.vault.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.loan_fee_escrow.to_account_info().clone(),
+            authority: self.syndicate.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_to_borrower_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.vault.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.borrower_token_account.to_account_info().clone(),
+            authority: self.syndicate.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[derive(Accounts)]
+pub struct RefundSyndicateContext<'info> {
+    #[account(
+        mut,
+        seeds = [SYNDICATE_STR.as_bytes(), loan_request.key().as_ref()],
+        bump = syndicate.bump,
+        has_one = loan_request,
+        has_one = requested_token_mint,
+        has_one = note_mint,
+        has_one = vault,
+    )]
+    syndicate: Box<Account<'info, Syndicate>>,
+    #[account(mut)]
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    requested_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    note_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=requested_token_mint,
+        token::authority=lender,
+    )]
+    lender_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=note_mint,
+        token::authority=lender,
+    )]
+    lender_note_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    lender: Signer<'info>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RefundSyndicateContext<'info> {
+    pub fn burn_notes_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let burn_accounts = Burn {
+            from: self.lender_note_account.to_account_info().clone(),
+            authority: self.lender.to_account_info().clone(),
+            mint: self.note_mint.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), burn_accounts)
+    }
+    pub fn transfer_from_vault_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.vault.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.lender_token_account.to_account_info().clone(),
+            authority: self.syndicate.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[derive(Accounts)]
+pub struct SellSyndicateCollateralContext<'info> {
+    syndicate: Box<Account<'info, Syndicate>>,
+    #[account(
+        mut,
+        constraint=loan.lend_nft_mint == syndicate.key() @ ErrorCode::InvalidAccount,
+    )]
+    loan: Box<Account<'info, Loan>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSyndicateContext<'info> {
+    #[account(
+        mut,
+        has_one = requested_token_mint,
+        has_one = note_mint,
+    )]
+    syndicate: Box<Account<'info, Syndicate>>,
+    #[account(
+        mut,
+        constraint=loan.lend_nft_mint == syndicate.key() @ ErrorCode::InvalidAccount,
+    )]
+    loan: Box<Account<'info, Loan>>,
+    #[account(
+        mint::token_program=token_program,
+    )]
+    requested_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    note_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [
+            PLATFORM_FEES_SEED_STR.as_bytes(),
+        ],
+        bump,
+    )]
+    platform_fees: Box<Account<'info, PlatformFees>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        mut,
+        token::mint=requested_token_mint,
+        token::authority=platform_fees,
+    )]
+    loan_token_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: the loan's interest fee, a LoanFee created by the first claim only when there is one
+    #[account(
+        mut,
+        seeds = [INTEREST_FEE_STR.as_bytes(), loan.key().as_ref()],
+        bump,
+    )]
+    interest_fee: UncheckedAccount<'info>,
+    /// CHECK: the multisig's associated token account, checked in the handler
+    #[account(mut)]
+    loan_fee_escrow: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint=requested_token_mint,
+        token::authority=lender,
+    )]
+    lender_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint=note_mint,
+        token::authority=lender,
+    )]
+    lender_note_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    lender: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimSyndicateContext<'info> {
+    pub fn burn_notes_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let burn_accounts = Burn {
+            from: self.lender_note_account.to_account_info().clone(),
+            authority: self.lender.to_account_info().clone(),
+            mint: self.note_mint.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), burn_accounts)
+    }
+    pub fn transfer_from_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.lender_token_account.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_fee_from_escrow_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.loan_token_escrow.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
+            to: self.loan_fee_escrow.to_account_info().clone(),
+            authority: self.platform_fees.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+}
+
+//Events
+#[event]
+pub struct SyndicateOpened {
+    pub syndicate: Pubkey,
+    pub loan_request: Pubkey,
+    pub note_mint: Pubkey,
+    pub requested_amount: u64,
+    pub deadline_slot: u64,
+}
+
+#[event]
+pub struct SyndicateFunded {
+    pub syndicate: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+    pub funded_amount: u64,
+}
+
+#[event]
+pub struct SyndicatedLoanDrawn {
+    pub syndicate: Pubkey,
+    pub loan: Pubkey,
+    pub loan_request: Pubkey,
+    pub requested_amount: u64,
+    pub duration: u64,
+}
+
+#[event]
+pub struct SyndicateRefunded {
+    pub syndicate: Pubkey,
+    pub lender: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SyndicateClaimed {
+    pub syndicate: Pubkey,
+    pub lender: Pubkey,
+    pub notes: u64,
+    pub amount: u64,
+}
@@ -1,9 +1,11 @@
 use crate::errors::ErrorCode;
-use crate::math::{Decimal, TrySub};
-use crate::state::{Loan, LoanStatus, PlatformFees};
-use crate::utils::{compound_interest, PLATFORM_FEES_SEED_STR};
+use crate::state::{CollectionConfig, CurrencyConfig, Loan, LoanRequest, LoanStatus, PlatformFees};
+use crate::utils::{
+    accrued_interest, gross_transfer_amount, late_charges, mint_decimals, COLLECTION_CONFIG_STR,
+    CURRENCY_CONFIG_STR, LOAN_REQUEST_STR, PLATFORM_FEES_SEED_STR,
+};
 use anchor_lang::{prelude::*, system_program};
-use anchor_spl::token::{Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
 use solana_program::native_token::LAMPORTS_PER_SOL;
 
 pub fn refresh_loan(ctx: Context<RefreshContext>) -> Result<()> {
@@ -16,22 +18,19 @@ pub fn refresh_loan(ctx: Context<RefreshContext>) -> Result<()> {
     let clock = Clock::get().unwrap();
     let current_slot = clock.slot;
 
-    let expected_loan_end_slot = ctx.accounts.loan.start_slot + ctx.accounts.loan.slot_duration;
+    let expected_loan_default_slot = ctx.accounts.loan.default_slot();
 
-    let slots_elapsed = current_slot - ctx.accounts.loan.last_updated_slot;
-
-    let compounded_so_far = compound_interest(
-        ctx.accounts.loan.requested_amount,
-        ctx.accounts.loan.interest_rate,
-        slots_elapsed,
+    // floating rate loans pass the benchmark rate account in remaining_accounts
+    ctx.accounts.loan.outstanding_debt += accrued_interest(
+        &ctx.accounts.loan,
+        current_slot,
+        ctx.remaining_accounts,
+        ctx.program_id,
     )?;
-    let new_interest_accrued =
-        compounded_so_far.try_sub(Decimal::from(ctx.accounts.loan.requested_amount))?;
-
-    ctx.accounts.loan.outstanding_debt += new_interest_accrued.try_round_u64()?;
+    charge_late_payment(&mut ctx.accounts.loan, current_slot)?;
 
-    // change state to Defaulted if expired
-    if current_slot > expected_loan_end_slot {
+    // change state to Defaulted once the grace period is over
+    if current_slot > expected_loan_default_slot {
         ctx.accounts.loan.status = LoanStatus::Defaulted.to_code();
     }
 
@@ -40,6 +39,71 @@ pub fn refresh_loan(ctx: Context<RefreshContext>) -> Result<()> {
     Ok(())
 }
 
+/// adds the late charges accrued since the last update to `loan`'s debt
+pub fn charge_late_payment(loan: &mut Account<Loan>, current_slot: u64) -> Result<()> {
+    let (penalty_interest, late_fee) = late_charges(loan, current_slot)?;
+    if penalty_interest == 0 && late_fee == 0 {
+        return Ok(());
+    }
+    loan.add_late_charges(penalty_interest, late_fee);
+
+    emit!(LateChargesAccrued {
+        loan: loan.key(),
+        penalty_interest,
+        late_fee,
+        total_penalty_interest: loan.penalty_interest,
+        total_late_fees: loan.late_fee_charged,
+        outstanding_debt: loan.outstanding_debt,
+    });
+    Ok(())
+}
+
+///frees a finished loan's principal from its collection's exposure cap (permissionless)
+pub fn release_collection_exposure(ctx: Context<ReleaseExposureContext>) -> Result<()> {
+    let finished = matches!(
+        LoanStatus::from(ctx.accounts.loan.status)?,
+        LoanStatus::Repaid
+            | LoanStatus::Seize
+            | LoanStatus::Completed
+            | LoanStatus::Sold
+            | LoanStatus::Settled
+    );
+    require!(
+        finished && ctx.accounts.loan.collection_exposure > 0,
+        ErrorCode::InvalidLoanState
+    );
+
+    let collection_config = &mut ctx.accounts.collection_config;
+    collection_config.exposure = collection_config
+        .exposure
+        .saturating_sub(ctx.accounts.loan.collection_exposure);
+    ctx.accounts.loan.collection_exposure = 0;
+    Ok(())
+}
+
+///frees a finished loan's principal from its currency's debt ceiling (permissionless)
+pub fn release_currency_debt(ctx: Context<ReleaseDebtContext>) -> Result<()> {
+    let finished = matches!(
+        LoanStatus::from(ctx.accounts.loan.status)?,
+        LoanStatus::Repaid
+            | LoanStatus::Seize
+            | LoanStatus::Completed
+            | LoanStatus::Sold
+            | LoanStatus::Settled
+    );
+    require!(
+        finished && ctx.accounts.loan.currency_debt > 0,
+        ErrorCode::InvalidLoanState
+    );
+
+    let currency_config = &mut ctx.accounts.currency_config;
+    currency_config.total_debt = currency_config
+        .total_debt
+        .saturating_sub(ctx.accounts.loan.currency_debt);
+    ctx.accounts.loan.currency_debt = 0;
+    Ok(())
+}
+
 pub fn buy_nft(ctx: Context<BuyNftContext>) -> Result<()> {
     require!(
         ctx.accounts.loan.status == LoanStatus::Sell.to_code(),
@@ -54,9 +118,11 @@ pub fn buy_nft(ctx: Context<BuyNftContext>) -> Result<()> {
         )
         .expect("transfer failed");
     } else {
-        anchor_spl::token::transfer(
+        // buyer covers any token-2022 transfer fee so the escrow receives nft_worth
+        anchor_spl::token_interface::transfer_checked(
             ctx.accounts.transfer_spl_tokens_to_escrow_context(),
-            ctx.accounts.loan.nft_worth,
+            gross_transfer_amount(&ctx.accounts.requested_token_mint, ctx.accounts.loan.nft_worth)?,
+            mint_decimals(&ctx.accounts.requested_token_mint)?,
         )
         .expect("transfer failed");
     }
@@ -69,11 +135,12 @@ pub fn buy_nft(ctx: Context<BuyNftContext>) -> Result<()> {
 
     let inner = vec![PLATFORM_FEES_SEED_STR.as_bytes(), bump_vecs.as_ref()];
     let outer = vec![inner.as_slice()];
-    anchor_spl::token::transfer(
+    anchor_spl::token_interface::transfer_checked(
         ctx.accounts
             .transfer_nft_from_escrow_context()
             .with_signer(outer.as_slice()),
         1,
+        ctx.accounts.nft_mint.decimals,
     )?;
     // change state
     ctx.accounts.loan.status = LoanStatus::Sold.to_code();
@@ -83,13 +150,17 @@ pub fn buy_nft(ctx: Context<BuyNftContext>) -> Result<()> {
 
 #[derive(Accounts)]
 pub struct BuyNftContext<'info> {
-    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        mint::token_program=nft_token_program,
+    )]
+    pub nft_mint: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
-        associated_token::mint=nft_mint,
-        associated_token::authority=buyer
+        token::mint=nft_mint,
+        token::authority=buyer,
+        token::token_program=nft_token_program,
     )]
-    pub buyer_nft_account: Account<'info, TokenAccount>,
+    pub buyer_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
     /// CHECK
     #[account(
         constraint=requested_token_mint.key() == loan.requested_token_mint.key()
@@ -113,27 +184,32 @@ pub struct BuyNftContext<'info> {
         mut,
         token::mint=nft_mint,
         token::authority=platform_fees,
+        token::token_program=nft_token_program,
     )]
-    pub nft_escrow: Account<'info, TokenAccount>,
+    pub nft_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
     pub loan: Box<Account<'info, Loan>>,
     #[account(mut)]
     pub buyer: Signer<'info>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    /// program of the requested token
+    pub token_program: Interface<'info, TokenInterface>,
+    /// program of the collateral nft (spl-token or token-2022)
+    pub nft_token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> BuyNftContext<'info> {
     pub fn transfer_nft_from_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_accounts = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_accounts = TransferChecked {
             from: self.nft_escrow.to_account_info().clone(),
+            mint: self.nft_mint.to_account_info().clone(),
             to: self.buyer_nft_account.to_account_info().clone(),
             authority: self.platform_fees.to_account_info().clone(),
         };
         CpiContext::new(
-            self.token_program.to_account_info().clone(),
+            self.nft_token_program.to_account_info().clone(),
             transfer_accounts,
         )
     }
@@ -149,13 +225,14 @@ impl<'info> BuyNftContext<'info> {
     }
     pub fn transfer_spl_tokens_to_escrow_context(
         &self,
-    ) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let transfer_acct = Transfer {
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
             from: self.requested_token_account.to_account_info().clone(),
+            mint: self.requested_token_mint.to_account_info().clone(),
             to: self.loan_token_escrow.to_account_info().clone(),
             authority: self.buyer.to_account_info().clone(),
         };
-        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
     }
 }
 #[derive(Accounts)]
@@ -163,3 +240,42 @@ pub struct RefreshContext<'info> {
     #[account(mut)]
     pub loan: Box<Account<'info, Loan>>,
 }
+
+#[derive(Accounts)]
+pub struct ReleaseExposureContext<'info> {
+    #[account(
+        seeds = [LOAN_REQUEST_STR.as_bytes(), loan.borrow_nft_mint.as_ref()],
+        bump,
+    )]
+    pub loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(mut)]
+    pub loan: Box<Account<'info, Loan>>,
+    #[account(
+        mut,
+        seeds = [COLLECTION_CONFIG_STR.as_bytes(), loan_request.collection.as_ref()],
+        bump = collection_config.bump,
+    )]
+    pub collection_config: Box<Account<'info, CollectionConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseDebtContext<'info> {
+    #[account(mut)]
+    pub loan: Box<Account<'info, Loan>>,
+    #[account(
+        mut,
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), loan.requested_token_mint.as_ref()],
+        bump = currency_config.bump,
+    )]
+    pub currency_config: Box<Account<'info, CurrencyConfig>>,
+}
+
+#[event]
+pub struct LateChargesAccrued {
+    pub loan: Pubkey,
+    pub penalty_interest: u64,
+    pub late_fee: u64,
+    pub total_penalty_interest: u64,
+    pub total_late_fees: u64,
+    pub outstanding_debt: u64,
+}
@@ -0,0 +1,638 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+use solana_program::native_token::LAMPORTS_PER_SOL;
+
+use crate::{
+    errors::ErrorCode,
+    state::{
+        ClaimReason, CollectionConfig, CurrencyConfig, FeeTreasury, InsuranceClaim, Loan,
+        LoanFee, LoanRequest, LoanStatus, Multisig, Spender, SpendingLimits,
+    },
+    utils::{
+        fee_escrow_address, insurance_fund_address, mint_decimals, COLLECTION_CONFIG_STR,
+        CURRENCY_CONFIG_STR, FEE_TREASURY_STR, INSURANCE_CLAIM_STR, LOAN_REQUEST_STR,
+        MULTISIG_SEED_STR, SPENDING_LIMITS_STR,
+    },
+};
+
+/// credits the uncollected LoanFees of a mint passed in remaining_accounts to the mint's
+/// FeeTreasury, by each recipient's snapshotted share, and closes them. Callable by any
+/// multisig owner, who gets the rent back.
+pub fn sweep_fees<'info>(ctx: Context<'_, '_, '_, 'info, SweepFeesContext<'info>>) -> Result<()> {
+    require!(
+        ctx.accounts.multisig.owners.contains(ctx.accounts.sweeper.key),
+        ErrorCode::InvalidOwner
+    );
+
+    let mint = ctx.accounts.token_mint.key();
+    let vault = fee_escrow_address(
+        &ctx.accounts.multisig.key(),
+        &mint,
+        &ctx.accounts.token_program.key(),
+        ctx.program_id,
+    );
+    let fee_treasury = &mut ctx.accounts.fee_treasury;
+    fee_treasury.mint = mint;
+    fee_treasury.vault = vault;
+    fee_treasury.bump = *ctx.bumps.get("fee_treasury").unwrap();
+
+    let mut fees = 0;
+    let mut amount = 0u64;
+    for info in ctx.remaining_accounts.iter() {
+        let mut loan_fee: Account<LoanFee> = Account::try_from(info)?;
+        require!(
+            loan_fee.token_mint == mint && loan_fee.escrow == vault,
+            ErrorCode::InvalidAccount
+        );
+        // LoanFee amounts of SOL loans are in whole SOL, accruals are in lamports
+        let fee_amount = if mint == Pubkey::default() {
+            loan_fee.amount * LAMPORTS_PER_SOL
+        } else {
+            loan_fee.amount
+        };
+        // owners who already withdrew through withdraw_fee are no longer listed
+        while let Some(owner) = loan_fee.owners.first().copied() {
+            let share = loan_fee.take_share(&owner, fee_amount).unwrap();
+            fee_treasury.accrue(owner, share)?;
+            amount += share;
+        }
+        loan_fee.close(ctx.accounts.sweeper.to_account_info())?;
+        fees += 1;
+    }
+
+    emit!(FeesSwept {
+        mint,
+        fees,
+        amount,
+    });
+    Ok(())
+}
+
+/// pays out everything accrued to the signer in the mint's FeeTreasury
+pub fn claim_fees(ctx: Context<ClaimFeesContext>) -> Result<()> {
+    let amount = ctx.accounts.fee_treasury.claim(ctx.accounts.owner.key)?;
+    let mint = ctx.accounts.token_mint.key();
+
+    if mint == Pubkey::default() {
+        require!(
+            ctx.accounts.owner_token_account.key() == ctx.accounts.owner.key(),
+            ErrorCode::InvalidAccount
+        );
+        let multisig = ctx.accounts.multisig.key();
+        let token_program = ctx.accounts.token_program.key();
+        let (_authority, bump) = Pubkey::find_program_address(
+            &[multisig.as_ref(), token_program.as_ref(), mint.as_ref()],
+            ctx.program_id,
+        );
+        let bump_vecs = bump.to_le_bytes();
+        let inner = vec![
+            multisig.as_ref(),
+            token_program.as_ref(),
+            mint.as_ref(),
+            bump_vecs.as_ref(),
+        ];
+        let outer = vec![inner.as_slice()];
+
+        system_program::transfer(
+            ctx.accounts
+                .transfer_lamports_to_owner_context()
+                .with_signer(outer.as_slice()),
+            amount,
+        )?;
+    } else {
+        let owner_token_account = TokenAccount::try_deserialize(
+            &mut &ctx.accounts.owner_token_account.try_borrow_data()?[..],
+        )?;
+        require!(
+            owner_token_account.owner == ctx.accounts.owner.key()
+                && owner_token_account.mint == mint,
+            ErrorCode::InvalidAccount
+        );
+        // spl escrow is the multisig's token account
+        let multisig_bump = ctx.bumps.get(MULTISIG_SEED_STR);
+        let bump = &[*multisig_bump.unwrap()][..];
+        let inner = vec![MULTISIG_SEED_STR.as_bytes(), bump];
+        let outer = vec![inner.as_slice()];
+
+        anchor_spl::token_interface::transfer_checked(
+            ctx.accounts
+                .transfer_spl_tokens_to_owner_context()
+                .with_signer(outer.as_slice()),
+            amount,
+            mint_decimals(&ctx.accounts.token_mint)?,
+        )?;
+    }
+
+    emit!(FeesClaimed {
+        mint,
+        owner: ctx.accounts.owner.key(),
+        amount,
+    });
+    Ok(())
+}
+
+/// pays `amount` of the treasury's accrued share of the mint's fees to `destination`, letting
+/// the multisig spend it when the treasury is an address nobody signs for. The only way this
+/// can be invoked is via a recursive call from execute_transaction -> withdraw_treasury.
+pub fn withdraw_treasury(ctx: Context<WithdrawTreasuryContext>, amount: u64) -> Result<()> {
+    let treasury = ctx.accounts.multisig.treasury;
+    ctx.accounts.fee_treasury.debit(&treasury, amount)?;
+    let multisig_bump = *ctx.bumps.get(MULTISIG_SEED_STR).unwrap();
+    let multisig = ctx.accounts.multisig.to_account_info();
+    TreasuryPayout {
+        token_mint: ctx.accounts.token_mint.as_ref(),
+        fee_escrow: ctx.accounts.fee_escrow.as_ref(),
+        destination: ctx.accounts.destination.as_ref(),
+        multisig: &multisig,
+        system_program: ctx.accounts.system_program.as_ref(),
+        token_program: ctx.accounts.token_program.as_ref(),
+    }
+    .pay(ctx.program_id, multisig_bump, amount)?;
+
+    emit!(TreasuryWithdrawn {
+        mint: ctx.accounts.token_mint.key(),
+        treasury,
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+    Ok(())
+}
+
+/// withdraws `amount` of the treasury's accrued fees without a proposal, charged to the
+/// spending limit of the owner or of a role they hold
+pub fn spend_treasury(ctx: Context<SpendTreasuryContext>, amount: u64) -> Result<()> {
+    let spender = ctx.accounts.spending_limits.spend(
+        &ctx.accounts.multisig,
+        ctx.accounts.owner.key,
+        amount,
+        Clock::get()?.slot,
+    )?;
+    let treasury = ctx.accounts.multisig.treasury;
+    ctx.accounts.fee_treasury.debit(&treasury, amount)?;
+    let multisig_bump = *ctx.bumps.get(MULTISIG_SEED_STR).unwrap();
+    let multisig = ctx.accounts.multisig.to_account_info();
+    TreasuryPayout {
+        token_mint: ctx.accounts.token_mint.as_ref(),
+        fee_escrow: ctx.accounts.fee_escrow.as_ref(),
+        destination: ctx.accounts.destination.as_ref(),
+        multisig: &multisig,
+        system_program: ctx.accounts.system_program.as_ref(),
+        token_program: ctx.accounts.token_program.as_ref(),
+    }
+    .pay(ctx.program_id, multisig_bump, amount)?;
+
+    emit!(TreasurySpent {
+        mint: ctx.accounts.token_mint.key(),
+        owner: ctx.accounts.owner.key(),
+        spender,
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+    Ok(())
+}
+
+// pays out of the multisig fee escrow of a mint, for both voted and limited withdrawals
+struct TreasuryPayout<'a, 'info> {
+    token_mint: &'a AccountInfo<'info>,
+    fee_escrow: &'a AccountInfo<'info>,
+    destination: &'a AccountInfo<'info>,
+    multisig: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+    token_program: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> TreasuryPayout<'a, 'info> {
+    fn pay(&self, program_id: &Pubkey, multisig_bump: u8, amount: u64) -> Result<()> {
+        let mint = self.token_mint.key();
+        if mint == Pubkey::default() {
+            // SOL escrow is a PDA of the multisig, token program and mint
+            let multisig = self.multisig.key();
+            let token_program = self.token_program.key();
+            let (_authority, bump) = Pubkey::find_program_address(
+                &[multisig.as_ref(), token_program.as_ref(), mint.as_ref()],
+                program_id,
+            );
+            let bump_vecs = bump.to_le_bytes();
+            let inner = vec![
+                multisig.as_ref(),
+                token_program.as_ref(),
+                mint.as_ref(),
+                bump_vecs.as_ref(),
+            ];
+            let outer = vec![inner.as_slice()];
+
+            let transfer_acct = system_program::Transfer {
+                from: self.fee_escrow.clone(),
+                to: self.destination.clone(),
+            };
+            system_program::transfer(
+                CpiContext::new(self.system_program.clone(), transfer_acct)
+                    .with_signer(outer.as_slice()),
+                amount,
+            )?;
+        } else {
+            let destination =
+                TokenAccount::try_deserialize(&mut &self.destination.try_borrow_data()?[..])?;
+            require!(destination.mint == mint, ErrorCode::InvalidAccount);
+            // spl escrow is the multisig's token account
+            let bump = &[multisig_bump][..];
+            let inner = vec![MULTISIG_SEED_STR.as_bytes(), bump];
+            let outer = vec![inner.as_slice()];
+
+            let transfer_acct = TransferChecked {
+                from: self.fee_escrow.clone(),
+                mint: self.token_mint.clone(),
+                to: self.destination.clone(),
+                authority: self.multisig.clone(),
+            };
+            anchor_spl::token_interface::transfer_checked(
+                CpiContext::new(self.token_program.clone(), transfer_acct)
+                    .with_signer(outer.as_slice()),
+                amount,
+                mint_decimals(self.token_mint)?,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// pays the lender of a sold loan on an allowlisted collection the debt the sale didn't
+/// cover, up to the currency's insurance cap, out of the insurance fund's share of the
+/// currency's fee treasury. One claim per loan, filed before the lender withdraws.
+pub fn file_insurance_claim(ctx: Context<FileInsuranceClaimContext>) -> Result<()> {
+    let loan = &ctx.accounts.loan;
+    require!(
+        loan.status == LoanStatus::Sold.to_code(),
+        ErrorCode::InvalidLoanState
+    );
+
+    // loan amounts of SOL loans are in whole SOL, the fee treasury is in lamports
+    let mint = loan.requested_token_mint;
+    let to_base_units = |amount: u64| {
+        if mint == Pubkey::default() {
+            amount * LAMPORTS_PER_SOL
+        } else {
+            amount
+        }
+    };
+    // the lender is owed the whole debt and only receives the sale price
+    let total_debt = loan.paid_amount + loan.outstanding_debt;
+    let shortfall = to_base_units(total_debt.saturating_sub(loan.nft_worth));
+    let insurance_fund = insurance_fund_address();
+    let (payout, reason) = InsuranceClaim::payout_for(
+        shortfall,
+        to_base_units(ctx.accounts.currency_config.insurance_cap),
+        ctx.accounts.fee_treasury.balance_of(&insurance_fund),
+    );
+    require!(payout > 0, ErrorCode::NoInsuredShortfall);
+    ctx.accounts.fee_treasury.debit(&insurance_fund, payout)?;
+
+    if mint == Pubkey::default() {
+        require!(
+            ctx.accounts.lender_token_account.key() == ctx.accounts.lender.key(),
+            ErrorCode::InvalidAccount
+        );
+        let multisig = ctx.accounts.multisig.key();
+        let token_program = ctx.accounts.token_program.key();
+        let (_authority, bump) = Pubkey::find_program_address(
+            &[multisig.as_ref(), token_program.as_ref(), mint.as_ref()],
+            ctx.program_id,
+        );
+        let bump_vecs = bump.to_le_bytes();
+        let inner = vec![
+            multisig.as_ref(),
+            token_program.as_ref(),
+            mint.as_ref(),
+            bump_vecs.as_ref(),
+        ];
+        let outer = vec![inner.as_slice()];
+
+        system_program::transfer(
+            ctx.accounts
+                .transfer_lamports_to_lender_context()
+                .with_signer(outer.as_slice()),
+            payout,
+        )?;
+    } else {
+        let lender_token_account = TokenAccount::try_deserialize(
+            &mut &ctx.accounts.lender_token_account.try_borrow_data()?[..],
+        )?;
+        require!(
+            lender_token_account.owner == ctx.accounts.lender.key()
+                && lender_token_account.mint == mint,
+            ErrorCode::InvalidAccount
+        );
+        let multisig_bump = ctx.bumps.get(MULTISIG_SEED_STR);
+        let bump = &[*multisig_bump.unwrap()][..];
+        let inner = vec![MULTISIG_SEED_STR.as_bytes(), bump];
+        let outer = vec![inner.as_slice()];
+
+        anchor_spl::token_interface::transfer_checked(
+            ctx.accounts
+                .transfer_spl_tokens_to_lender_context()
+                .with_signer(outer.as_slice()),
+            payout,
+            mint_decimals(&ctx.accounts.token_mint)?,
+        )?;
+    }
+
+    let clock = Clock::get()?;
+    let claim = &mut ctx.accounts.insurance_claim;
+    claim.loan = ctx.accounts.loan.key();
+    claim.lender = ctx.accounts.lender.key();
+    claim.mint = mint;
+    claim.shortfall = shortfall;
+    claim.payout = payout;
+    claim.reason = reason;
+    claim.slot = clock.slot;
+
+    emit!(InsuranceClaimPaid {
+        loan: claim.loan,
+        lender: claim.lender,
+        mint,
+        shortfall,
+        payout,
+        reason,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepFeesContext<'info> {
+    /// CHECK: token_mint is the mint of the swept fees (Pubkey::default() for SOL)
+    token_mint: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = sweeper,
+        space = 8 + FeeTreasury::MAX_SIZE,
+        seeds = [FEE_TREASURY_STR.as_bytes(), token_mint.key().as_ref()],
+        bump,
+    )]
+    fee_treasury: Box<Account<'info, FeeTreasury>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut)]
+    sweeper: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFeesContext<'info> {
+    /// CHECK: token_mint is the mint of the claimed fees (Pubkey::default() for SOL)
+    #[account(constraint = token_mint.key() == fee_treasury.mint @ ErrorCode::InvalidAccount)]
+    token_mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [FEE_TREASURY_STR.as_bytes(), token_mint.key().as_ref()],
+        bump = fee_treasury.bump,
+    )]
+    fee_treasury: Box<Account<'info, FeeTreasury>>,
+    /// CHECK: fee_escrow is the multisig fee escrow the treasury was swept into
+    #[account(
+        mut,
+        constraint = fee_escrow.key() == fee_treasury.vault @ ErrorCode::InvalidAccount,
+    )]
+    fee_escrow: UncheckedAccount<'info>,
+    /// CHECK: the owner itself for SOL, the owner's token account otherwise, checked in the handler
+    #[account(mut)]
+    owner_token_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    owner: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimFeesContext<'info> {
+    pub fn transfer_spl_tokens_to_owner_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.fee_escrow.to_account_info().clone(),
+            mint: self.token_mint.to_account_info().clone(),
+            to: self.owner_token_account.to_account_info().clone(),
+            authority: self.multisig.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_lamports_to_owner_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let transfer_acct = system_program::Transfer {
+            from: self.fee_escrow.to_account_info().clone(),
+            to: self.owner_token_account.to_account_info().clone(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasuryContext<'info> {
+    /// CHECK: token_mint is the mint of the withdrawn fees (Pubkey::default() for SOL)
+    #[account(constraint = token_mint.key() == fee_treasury.mint @ ErrorCode::InvalidAccount)]
+    token_mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [FEE_TREASURY_STR.as_bytes(), token_mint.key().as_ref()],
+        bump = fee_treasury.bump,
+    )]
+    fee_treasury: Box<Account<'info, FeeTreasury>>,
+    /// CHECK: fee_escrow is the multisig fee escrow the treasury was swept into
+    #[account(
+        mut,
+        constraint = fee_escrow.key() == fee_treasury.vault @ ErrorCode::InvalidAccount,
+    )]
+    fee_escrow: UncheckedAccount<'info>,
+    /// CHECK: any account for SOL, a token account of the mint otherwise, checked in the handler
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump
+    )]
+    multisig_signer: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SpendTreasuryContext<'info> {
+    /// CHECK: token_mint is the mint of the withdrawn fees (Pubkey::default() for SOL)
+    #[account(constraint = token_mint.key() == fee_treasury.mint @ ErrorCode::InvalidAccount)]
+    token_mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [FEE_TREASURY_STR.as_bytes(), token_mint.key().as_ref()],
+        bump = fee_treasury.bump,
+    )]
+    fee_treasury: Box<Account<'info, FeeTreasury>>,
+    #[account(
+        mut,
+        seeds = [SPENDING_LIMITS_STR.as_bytes(), token_mint.key().as_ref()],
+        bump = spending_limits.bump,
+    )]
+    spending_limits: Box<Account<'info, SpendingLimits>>,
+    /// CHECK: fee_escrow is the multisig fee escrow the treasury was swept into
+    #[account(
+        mut,
+        constraint = fee_escrow.key() == fee_treasury.vault @ ErrorCode::InvalidAccount,
+    )]
+    fee_escrow: UncheckedAccount<'info>,
+    /// CHECK: any account for SOL, a token account of the mint otherwise, checked in the handler
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    // An owner with a spending limit, or holding a role with one. Checked in the handler.
+    owner: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FileInsuranceClaimContext<'info> {
+    #[account(
+        constraint = loan.lend_nft_mint == lend_nft_mint.key()
+    )]
+    lend_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        token::mint = lend_nft_mint,
+        token::authority = lender,
+        constraint = lend_nft_account.amount == 1 @ ErrorCode::InvalidAccount,
+    )]
+    lend_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    loan: Box<Account<'info, Loan>>,
+    // only loans priced under an allowlisted collection's config are insured
+    #[account(
+        seeds = [LOAN_REQUEST_STR.as_bytes(), loan.borrow_nft_mint.as_ref()],
+        bump,
+        constraint = loan_request.collection != Pubkey::default() @ ErrorCode::CollectionNotAllowed,
+    )]
+    loan_request: Box<Account<'info, LoanRequest>>,
+    #[account(
+        seeds = [COLLECTION_CONFIG_STR.as_bytes(), loan_request.collection.as_ref()],
+        bump = collection_config.bump,
+        constraint = collection_config.allowed @ ErrorCode::CollectionNotAllowed,
+    )]
+    collection_config: Box<Account<'info, CollectionConfig>>,
+    #[account(
+        seeds = [CURRENCY_CONFIG_STR.as_bytes(), loan.requested_token_mint.as_ref()],
+        bump = currency_config.bump,
+    )]
+    currency_config: Box<Account<'info, CurrencyConfig>>,
+    /// CHECK: token_mint is the loan currency (Pubkey::default() for SOL)
+    #[account(constraint = token_mint.key() == loan.requested_token_mint @ ErrorCode::InvalidAccount)]
+    token_mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [FEE_TREASURY_STR.as_bytes(), token_mint.key().as_ref()],
+        bump = fee_treasury.bump,
+    )]
+    fee_treasury: Box<Account<'info, FeeTreasury>>,
+    /// CHECK: fee_escrow is the multisig fee escrow backing the treasury
+    #[account(
+        mut,
+        constraint = fee_escrow.key() == fee_treasury.vault @ ErrorCode::InvalidAccount,
+    )]
+    fee_escrow: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = lender,
+        space = 8 + InsuranceClaim::MAX_SIZE,
+        seeds = [INSURANCE_CLAIM_STR.as_bytes(), loan.key().as_ref()],
+        bump,
+    )]
+    insurance_claim: Box<Account<'info, InsuranceClaim>>,
+    /// CHECK: the lender itself for SOL, the lender's token account otherwise, checked in the handler
+    #[account(mut)]
+    lender_token_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [MULTISIG_SEED_STR.as_bytes()],
+        bump,
+    )]
+    multisig: Box<Account<'info, Multisig>>,
+    #[account(mut)]
+    lender: Signer<'info>,
+    system_program: Program<'info, System>,
+    token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FileInsuranceClaimContext<'info> {
+    pub fn transfer_spl_tokens_to_lender_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, TransferChecked<'info>> {
+        let transfer_acct = TransferChecked {
+            from: self.fee_escrow.to_account_info().clone(),
+            mint: self.token_mint.to_account_info().clone(),
+            to: self.lender_token_account.to_account_info().clone(),
+            authority: self.multisig.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info(), transfer_acct)
+    }
+    pub fn transfer_lamports_to_lender_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, system_program::Transfer<'info>> {
+        let transfer_acct = system_program::Transfer {
+            from: self.fee_escrow.to_account_info().clone(),
+            to: self.lender_token_account.to_account_info().clone(),
+        };
+        CpiContext::new(self.system_program.to_account_info(), transfer_acct)
+    }
+}
+
+#[event]
+pub struct FeesSwept {
+    pub mint: Pubkey,
+    pub fees: u32,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeesClaimed {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TreasurySpent {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    /// limit the withdrawal was charged to
+    pub spender: Spender,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct InsuranceClaimPaid {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub mint: Pubkey,
+    pub shortfall: u64,
+    pub payout: u64,
+    pub reason: ClaimReason,
+}
@@ -38,19 +38,138 @@ pub mod p2p_nft_lending {
     ) -> Result<()> {
         instructions::multisig::set_platform_fees(ctx, fee_percentage, interest_rate, ltv)
     }
+    pub fn set_fee_model(
+        ctx: Context<PlatformFeeAuthContext>,
+        origination_fee: u32,
+        interest_fee: u32,
+    ) -> Result<()> {
+        instructions::multisig::set_fee_model(ctx, origination_fee, interest_fee)
+    }
+    pub fn set_late_payment_terms(
+        ctx: Context<PlatformFeeAuthContext>,
+        grace_slots: u64,
+        penalty_rate: u32,
+        late_fee: u64,
+    ) -> Result<()> {
+        instructions::multisig::set_late_payment_terms(ctx, grace_slots, penalty_rate, late_fee)
+    }
+    pub fn set_execution_delay(ctx: Context<MultisigAuth>, execution_delay: u64) -> Result<()> {
+        instructions::multisig::set_execution_delay(ctx, execution_delay)
+    }
+    pub fn set_max_proposal_slots(ctx: Context<MultisigAuth>, max_proposal_slots: u64) -> Result<()> {
+        instructions::multisig::set_max_proposal_slots(ctx, max_proposal_slots)
+    }
+    pub fn set_roles(ctx: Context<MultisigAuth>, roles: Vec<RoleConfig>) -> Result<()> {
+        instructions::multisig::set_roles(ctx, roles)
+    }
+    pub fn set_paused(ctx: Context<PlatformFeeAuthContext>, paused: bool) -> Result<()> {
+        instructions::multisig::set_paused(ctx, paused)
+    }
+    pub fn set_max_referral_fee(
+        ctx: Context<PlatformFeeAuthContext>,
+        max_referral_bps: u16,
+    ) -> Result<()> {
+        instructions::multisig::set_max_referral_fee(ctx, max_referral_bps)
+    }
+    pub fn set_max_spread(ctx: Context<PlatformFeeAuthContext>, max_spread: u32) -> Result<()> {
+        instructions::multisig::set_max_spread(ctx, max_spread)
+    }
+    pub fn set_fee_shares(
+        ctx: Context<MultisigAuth>,
+        weights: Vec<u16>,
+        treasury: Pubkey,
+        treasury_bps: u16,
+        insurance_bps: u16,
+    ) -> Result<()> {
+        instructions::multisig::set_fee_shares(ctx, weights, treasury, treasury_bps, insurance_bps)
+    }
+    pub fn set_benchmark_rate(ctx: Context<BenchmarkRateAuthContext>, rate: u32) -> Result<()> {
+        instructions::multisig::set_benchmark_rate(ctx, rate)
+    }
+    pub fn set_pricing_tiers(
+        ctx: Context<PricingTableAuthContext>,
+        collection: Pubkey,
+        tiers: Vec<PricingTier>,
+    ) -> Result<()> {
+        instructions::multisig::set_pricing_tiers(ctx, collection, tiers)
+    }
+    pub fn set_collection_config(
+        ctx: Context<CollectionConfigAuthContext>,
+        collection: Pubkey,
+        params: CollectionParams,
+    ) -> Result<()> {
+        instructions::multisig::set_collection_config(ctx, collection, params)
+    }
+    pub fn set_currency_config(
+        ctx: Context<CurrencyConfigAuthContext>,
+        mint: Pubkey,
+        params: CurrencyParams,
+    ) -> Result<()> {
+        instructions::multisig::set_currency_config(ctx, mint, params)
+    }
     pub fn create_transaction(
         ctx: Context<CreateTransaction>,
         pid: Pubkey,
         transaction_accounts: Vec<TransactionAccount>,
         data: Vec<u8>,
+        expires_in: Option<u64>,
+    ) -> Result<()> {
+        instructions::multisig::create_transaction(
+            ctx,
+            pid,
+            transaction_accounts,
+            data,
+            expires_in,
+        )
+    }
+
+    pub fn create_batch_transaction(
+        ctx: Context<CreateBatchTransaction>,
+        instructions: Vec<TransactionInstruction>,
+        expires_in: Option<u64>,
     ) -> Result<()> {
-        instructions::multisig::create_transaction(ctx, pid, transaction_accounts, data)
+        instructions::multisig::create_batch_transaction(ctx, instructions, expires_in)
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        proposal: Proposal,
+        expires_in: Option<u64>,
+    ) -> Result<()> {
+        instructions::multisig::create_proposal(ctx, proposal, expires_in)
+    }
+
+    pub fn approve_with_signatures(
+        ctx: Context<ApproveWithSignatures>,
+        expiry_slot: u64,
+    ) -> Result<()> {
+        instructions::multisig::approve_with_signatures(ctx, expiry_slot)
     }
 
     pub fn approve(ctx: Context<Approve>) -> Result<()> {
         instructions::multisig::approve(ctx)
     }
 
+    pub fn reject(ctx: Context<Approve>) -> Result<()> {
+        instructions::multisig::reject(ctx)
+    }
+
+    pub fn revoke_approval(ctx: Context<Approve>) -> Result<()> {
+        instructions::multisig::revoke_approval(ctx)
+    }
+
+    pub fn cancel_transaction(ctx: Context<CancelTransaction>) -> Result<()> {
+        instructions::multisig::cancel_transaction(ctx)
+    }
+
+    pub fn close_expired_transaction(ctx: Context<CloseExpiredTransaction>) -> Result<()> {
+        instructions::multisig::close_expired_transaction(ctx)
+    }
+
+    pub fn veto(ctx: Context<Veto>) -> Result<()> {
+        instructions::multisig::veto(ctx)
+    }
+
     pub fn execute_transaction(ctx: Context<ExecuteTransaction>) -> Result<()> {
         instructions::multisig::execute_transaction(ctx)
     }
@@ -64,6 +183,20 @@ pub mod p2p_nft_lending {
         instructions::multisig::set_owners_and_change_threshold(ctx, owners, threshold)
     }
 
+    pub fn change_threshold(ctx: Context<MultisigAuth>, threshold: u64) -> Result<()> {
+        instructions::multisig::change_threshold(ctx, threshold)
+    }
+
+    // Set owners, their vote weights and the weight threshold at once.
+    pub fn set_owners_and_weights(
+        ctx: Context<MultisigAuth>,
+        owners: Vec<Pubkey>,
+        vote_weights: Vec<u16>,
+        threshold: u64,
+    ) -> Result<()> {
+        instructions::multisig::set_owners_and_weights(ctx, owners, vote_weights, threshold)
+    }
+
     //loans
 
     //Borrower
@@ -75,6 +208,44 @@ pub mod p2p_nft_lending {
     ) -> Result<()> {
         instructions::borrower::request_for_loan(ctx, nft_worth, requested_amount, duration)
     }
+    pub fn request_open_term_loan(
+        ctx: Context<LoanRequestContext>,
+        nft_worth: u64,
+        requested_amount: u64,
+        notice_slots: u64,
+    ) -> Result<()> {
+        instructions::borrower::request_open_term_loan(ctx, nft_worth, requested_amount, notice_slots)
+    }
+    pub fn request_floating_loan(
+        ctx: Context<LoanRequestContext>,
+        nft_worth: u64,
+        requested_amount: u64,
+        duration: u64,
+        max_spread: u32,
+    ) -> Result<()> {
+        instructions::borrower::request_floating_loan(
+            ctx,
+            nft_worth,
+            requested_amount,
+            duration,
+            max_spread,
+        )
+    }
+    pub fn request_for_loan_with_referral(
+        ctx: Context<LoanRequestContext>,
+        nft_worth: u64,
+        requested_amount: u64,
+        duration: u64,
+        referral: Referral,
+    ) -> Result<()> {
+        instructions::borrower::request_for_loan_with_referral(
+            ctx,
+            nft_worth,
+            requested_amount,
+            duration,
+            referral,
+        )
+    }
     pub fn cancel_loan_request(ctx: Context<CancelRequestContext>) -> Result<()> {
         instructions::borrower::cancel_loan_request(ctx)
     }
@@ -89,10 +260,22 @@ pub mod p2p_nft_lending {
     pub fn grant_loan(ctx: Context<GrantLoanContext>) -> Result<()> {
         instructions::lender::grant_loan(ctx)
     }
+    pub fn grant_loan_with_referral(
+        ctx: Context<GrantLoanContext>,
+        referral: Referral,
+    ) -> Result<()> {
+        instructions::lender::grant_loan_with_referral(ctx, referral)
+    }
+    pub fn grant_floating_loan(ctx: Context<GrantLoanContext>, spread: u32) -> Result<()> {
+        instructions::lender::grant_floating_loan(ctx, spread)
+    }
     pub fn lender_withdraw_tokens(ctx: Context<LenderWithdrawTokenContext>) -> Result<()> {
         instructions::lender::lender_withdraw_tokens(ctx)
     }
 
+    pub fn call_loan(ctx: Context<CallLoanContext>) -> Result<()> {
+        instructions::lender::call_loan(ctx)
+    }
     pub fn seize_nft(ctx: Context<SeizeNftContext>) -> Result<()> {
         instructions::lender::seize_nft(ctx)
     }
@@ -105,7 +288,109 @@ pub mod p2p_nft_lending {
     pub fn refresh_loan(ctx: Context<RefreshContext>) -> Result<()> {
         instructions::third_party::refresh_loan(ctx)
     }
+    pub fn release_collection_exposure(ctx: Context<ReleaseExposureContext>) -> Result<()> {
+        instructions::third_party::release_collection_exposure(ctx)
+    }
+    pub fn release_currency_debt(ctx: Context<ReleaseDebtContext>) -> Result<()> {
+        instructions::third_party::release_currency_debt(ctx)
+    }
     pub fn buy_nft(ctx: Context<BuyNftContext>) -> Result<()> {
         instructions::third_party::buy_nft(ctx)
     }
+
+    // lending pools
+    pub fn create_pool(ctx: Context<CreatePoolContext>, config: PoolConfig) -> Result<()> {
+        instructions::pool::create_pool(ctx, config)
+    }
+    pub fn set_pool_config(ctx: Context<PoolConfigAuthContext>, config: PoolConfig) -> Result<()> {
+        instructions::pool::set_pool_config(ctx, config)
+    }
+    pub fn deposit_to_pool(ctx: Context<PoolDepositContext>, amount: u64) -> Result<()> {
+        instructions::pool::deposit_to_pool(ctx, amount)
+    }
+    pub fn withdraw_from_pool(ctx: Context<PoolWithdrawContext>, shares: u64) -> Result<()> {
+        instructions::pool::withdraw_from_pool(ctx, shares)
+    }
+    pub fn borrow_from_pool(
+        ctx: Context<PoolBorrowContext>,
+        amount: u64,
+        slot_duration: u64,
+    ) -> Result<()> {
+        instructions::pool::borrow_from_pool(ctx, amount, slot_duration)
+    }
+    pub fn sell_pool_collateral(ctx: Context<SellPoolCollateralContext>) -> Result<()> {
+        instructions::pool::sell_pool_collateral(ctx)
+    }
+    pub fn seize_pool_collateral(ctx: Context<SeizePoolCollateralContext>) -> Result<()> {
+        instructions::pool::seize_pool_collateral(ctx)
+    }
+    pub fn settle_pool_loan(ctx: Context<SettlePoolLoanContext>) -> Result<()> {
+        instructions::pool::settle_pool_loan(ctx)
+    }
+
+    // syndicated loans
+    pub fn open_syndicate(ctx: Context<OpenSyndicateContext>, deadline_slot: u64) -> Result<()> {
+        instructions::syndicate::open_syndicate(ctx, deadline_slot)
+    }
+    pub fn fund_syndicate(ctx: Context<FundSyndicateContext>, amount: u64) -> Result<()> {
+        instructions::syndicate::fund_syndicate(ctx, amount)
+    }
+    pub fn draw_syndicated_loan(ctx: Context<DrawSyndicatedLoanContext>) -> Result<()> {
+        instructions::syndicate::draw_syndicated_loan(ctx)
+    }
+    pub fn refund_syndicate(ctx: Context<RefundSyndicateContext>, notes: u64) -> Result<()> {
+        instructions::syndicate::refund_syndicate(ctx, notes)
+    }
+    pub fn sell_syndicate_collateral(ctx: Context<SellSyndicateCollateralContext>) -> Result<()> {
+        instructions::syndicate::sell_syndicate_collateral(ctx)
+    }
+    pub fn claim_syndicate(ctx: Context<ClaimSyndicateContext>, notes: u64) -> Result<()> {
+        instructions::syndicate::claim_syndicate(ctx, notes)
+    }
+
+    // position nft marketplace
+    pub fn list_position(ctx: Context<ListPositionContext>, price: u64) -> Result<()> {
+        instructions::marketplace::list_position(ctx, price)
+    }
+    pub fn cancel_position_listing(ctx: Context<CancelPositionListingContext>) -> Result<()> {
+        instructions::marketplace::cancel_position_listing(ctx)
+    }
+    pub fn buy_position(ctx: Context<BuyPositionContext>, price: u64) -> Result<()> {
+        instructions::marketplace::buy_position(ctx, price)
+    }
+
+    // fee treasury
+    pub fn sweep_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, SweepFeesContext<'info>>,
+    ) -> Result<()> {
+        instructions::treasury::sweep_fees(ctx)
+    }
+    pub fn claim_fees(ctx: Context<ClaimFeesContext>) -> Result<()> {
+        instructions::treasury::claim_fees(ctx)
+    }
+    pub fn set_spending_limit(
+        ctx: Context<SpendingLimitAuthContext>,
+        mint: Pubkey,
+        spender: Spender,
+        amount: u64,
+        period_slots: u64,
+    ) -> Result<()> {
+        instructions::multisig::set_spending_limit(ctx, mint, spender, amount, period_slots)
+    }
+
+    pub fn spend_treasury(ctx: Context<SpendTreasuryContext>, amount: u64) -> Result<()> {
+        instructions::treasury::spend_treasury(ctx, amount)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasuryContext>, amount: u64) -> Result<()> {
+        instructions::treasury::withdraw_treasury(ctx, amount)
+    }
+    pub fn file_insurance_claim(ctx: Context<FileInsuranceClaimContext>) -> Result<()> {
+        instructions::treasury::file_insurance_claim(ctx)
+    }
+
+    // migration
+    pub fn migrate_state(ctx: Context<MigrateStateContext>) -> Result<()> {
+        instructions::migration::migrate_state(ctx)
+    }
 }
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// multisig governed benchmark rate that floating rate loans track
+pub struct BenchmarkRate {
+    /// rate changes, oldest first
+    pub history: Vec<RateSegment>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct RateSegment {
+    pub start_slot: u64,
+    /// same 3dp percentage as PlatformFees.interest_rate
+    pub rate: u32,
+}
+
+impl BenchmarkRate {
+    pub const MAX_HISTORY: usize = 50;
+    pub const MAX_SIZE: usize = 4 + (BenchmarkRate::MAX_HISTORY * (8 + 4)); //history
+
+    /// records a new rate from `slot` on. Once the history is full the oldest segment is
+    /// dropped, loans not refreshed since then accrue at the oldest rate still on record
+    pub fn push(&mut self, rate: u32, slot: u64) {
+        if let Some(last) = self.history.last_mut() {
+            if last.start_slot == slot {
+                last.rate = rate;
+                return;
+            }
+        }
+        if self.history.len() == BenchmarkRate::MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(RateSegment {
+            start_slot: slot,
+            rate,
+        });
+    }
+
+    pub fn current_rate(&self) -> u32 {
+        self.history.last().map(|segment| segment.rate).unwrap_or(0)
+    }
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+
+#[account]
+/// multisig managed risk parameters of a verified collection
+pub struct CollectionConfig {
+    pub collection: Pubkey,
+    pub allowed: bool,
+    pub max_ltv: u32,
+    pub max_slot_duration: u64,
+    pub min_interest_rate: u32,
+    pub max_interest_rate: u32,
+    pub exposure_cap: u64,
+    /// principal currently lent against the collection
+    pub exposure: u64,
+    pub bump: u8,
+}
+
+impl CollectionConfig {
+    pub const MAX_SIZE: usize = 32 //collection
+    + 1 //allowed
+    + 4 //max_ltv
+    + 8 //max_slot_duration
+    + 4 //min_interest_rate
+    + 4 //max_interest_rate
+    + 8 //exposure_cap
+    + 8 //exposure
+    + 1; //bump
+
+    pub fn set_params(&mut self, params: CollectionParams) -> Result<()> {
+        require!(
+            params.max_ltv > 0
+                && params.max_ltv < 1000
+                && params.min_interest_rate <= params.max_interest_rate,
+            ErrorCode::InvalidCollectionConfig
+        );
+        self.allowed = params.allowed;
+        self.max_ltv = params.max_ltv;
+        self.max_slot_duration = params.max_slot_duration;
+        self.min_interest_rate = params.min_interest_rate;
+        self.max_interest_rate = params.max_interest_rate;
+        self.exposure_cap = params.exposure_cap;
+        Ok(())
+    }
+
+    pub fn add_exposure(&mut self, amount: u64) -> Result<()> {
+        let exposure = self
+            .exposure
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            exposure <= self.exposure_cap,
+            ErrorCode::CollectionLimitExceeded
+        );
+        self.exposure = exposure;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CollectionParams {
+    pub allowed: bool,
+    pub max_ltv: u32,
+    /// u64::MAX to allow open-term loans
+    pub max_slot_duration: u64,
+    pub min_interest_rate: u32,
+    pub max_interest_rate: u32,
+    /// cap on principal lent against the collection, in the loan currency's units
+    pub exposure_cap: u64,
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+
+#[account]
+/// multisig managed allowlist entry of a loan currency, Pubkey::default() for SOL
+pub struct CurrencyConfig {
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    pub debt_ceiling: u64,
+    /// principal currently lent in the currency
+    pub total_debt: u64,
+    /// replaces the priced fee_percentage of new loans when set
+    pub fee_override: Option<u32>,
+    /// most the insurance fund pays towards a single loan's shortfall
+    pub insurance_cap: u64,
+    pub bump: u8,
+}
+
+impl CurrencyConfig {
+    pub const MAX_SIZE: usize = 32 //mint
+    + 1 //enabled
+    + 8 //min_loan_amount
+    + 8 //max_loan_amount
+    + 8 //debt_ceiling
+    + 8 //total_debt
+    + 1 + 4 //fee_override
+    + 8 //insurance_cap
+    + 1; //bump
+
+    pub fn set_params(&mut self, params: CurrencyParams) -> Result<()> {
+        require!(
+            params.min_loan_amount <= params.max_loan_amount
+                && params.fee_override.unwrap_or(0) < 1000,
+            ErrorCode::InvalidCurrencyConfig
+        );
+        self.enabled = params.enabled;
+        self.min_loan_amount = params.min_loan_amount;
+        self.max_loan_amount = params.max_loan_amount;
+        self.debt_ceiling = params.debt_ceiling;
+        self.fee_override = params.fee_override;
+        self.insurance_cap = params.insurance_cap;
+        Ok(())
+    }
+
+    /// checks a new loan of `amount` fits the currency's limits
+    pub fn check_loan(&self, amount: u64) -> Result<()> {
+        require!(self.enabled, ErrorCode::CurrencyNotAllowed);
+        require!(
+            amount >= self.min_loan_amount
+                && amount <= self.max_loan_amount
+                && amount <= self.debt_ceiling.saturating_sub(self.total_debt),
+            ErrorCode::CurrencyLimitExceeded
+        );
+        Ok(())
+    }
+
+    pub fn add_debt(&mut self, amount: u64) -> Result<()> {
+        self.check_loan(amount)?;
+        self.total_debt = self
+            .total_debt
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CurrencyParams {
+    pub enabled: bool,
+    pub min_loan_amount: u64,
+    pub max_loan_amount: u64,
+    pub debt_ceiling: u64,
+    pub fee_override: Option<u32>,
+    pub insurance_cap: u64,
+}
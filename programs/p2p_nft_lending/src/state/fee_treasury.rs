@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+
+#[account]
+/// fee ledger of a mint, swept LoanFees are credited here and claimed from the mint's fee escrow
+pub struct FeeTreasury {
+    pub mint: Pubkey,
+    /// multisig fee escrow holding the swept fees
+    pub vault: Pubkey,
+    /// amount each recipient can claim
+    pub accruals: Vec<FeeAccrual>,
+    pub total_swept: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct FeeAccrual {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+impl FeeTreasury {
+    pub const MAX_ACCRUALS: usize = 64;
+    pub const MAX_SIZE: usize = 32 //mint
+    + 32 //vault
+    + 4 + (FeeTreasury::MAX_ACCRUALS * (32 + 8)) //accruals
+    + 8 //total_swept
+    + 8 //total_claimed
+    + 1; //bump
+
+    pub fn accrue(&mut self, owner: Pubkey, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        match self.accruals.iter_mut().find(|accrual| accrual.owner == owner) {
+            Some(accrual) => {
+                accrual.amount = accrual
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(ErrorCode::MathOverflow)?
+            }
+            None => {
+                require!(
+                    self.accruals.len() < Self::MAX_ACCRUALS,
+                    ErrorCode::FeesListFull
+                );
+                self.accruals.push(FeeAccrual { owner, amount });
+            }
+        }
+        self.total_swept = self
+            .total_swept
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn balance_of(&self, owner: &Pubkey) -> u64 {
+        self.accruals
+            .iter()
+            .find(|accrual| accrual.owner == *owner)
+            .map(|accrual| accrual.amount)
+            .unwrap_or(0)
+    }
+
+    /// pays `amount` out of `owner`'s accrual, the entry is removed once empty
+    pub fn debit(&mut self, owner: &Pubkey, amount: u64) -> Result<()> {
+        let index = self
+            .accruals
+            .iter()
+            .position(|accrual| accrual.owner == *owner)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        let accrual = &mut self.accruals[index];
+        accrual.amount = accrual
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientFunds)?;
+        if accrual.amount == 0 {
+            self.accruals.remove(index);
+        }
+        self.total_claimed += amount;
+        Ok(())
+    }
+
+    /// removes `owner`'s entry and returns what it had accrued
+    pub fn claim(&mut self, owner: &Pubkey) -> Result<u64> {
+        let index = self
+            .accruals
+            .iter()
+            .position(|accrual| accrual.owner == *owner)
+            .ok_or(ErrorCode::FeeAlreadyWithdrawn)?;
+        let amount = self.accruals.remove(index).amount;
+        self.total_claimed += amount;
+        Ok(amount)
+    }
+}
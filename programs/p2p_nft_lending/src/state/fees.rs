@@ -1,19 +1,46 @@
 use anchor_lang::prelude::*;
 
+use crate::state::Multisig;
+
 #[account]
 pub struct PlatformFees {
     pub fee_percentage: u32,
     pub interest_rate: u32,
     // Loan-to-Value (LTV) Ratio
     pub ltv: u32,
-    pub uncollected_fees: Vec<Pubkey>,
+    // fields below replace the first deployment's uncollected_fees list, see migrate_state
+    /// share of the interest collected taken when the lender claims, 0 to disable
+    pub interest_fee_percentage: u32,
+    /// slots after maturity a borrower can still repay before the loan defaults
+    pub grace_slots: u64,
+    /// APR charged on top of the contract rate during the grace period
+    pub penalty_rate: u32,
+    /// flat fee added to the debt once a loan is past maturity, in the loan currency's units
+    pub late_fee: u64,
+    /// highest cut of the platform fee, in bps, a frontend can ask for as referrer
+    pub max_referral_bps: u16,
+    /// no new loans while set
+    pub paused: bool,
+    /// highest spread over the benchmark rate a floating rate loan can carry
+    pub max_spread: u32,
+    /// account layout version, bumped by migrate_state
+    pub version: u8,
 }
 
 impl PlatformFees {
     pub const MAX_SIZE: usize = 4 // fee_percentage
     + 4  //interest
     + 4 //ltv
-    + 4 + (100 * 32 ); //100 uncollected_fees at a time
+    + 4 //interest_fee_percentage
+    + 8 //grace_slots
+    + 4 //penalty_rate
+    + 8 //late_fee
+    + 2 //max_referral_bps
+    + 1 //paused
+    + 4 //max_spread
+    + 1; //version
+
+    pub const VERSION: u8 = 1;
 
     pub fn init(&mut self, fee: u32, interest: u32, ltv: u32) {
         self.fee_percentage = fee;
@@ -22,6 +49,19 @@ impl PlatformFees {
     }
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
+/// frontend credited with a cut of the platform fee on a loan it brought in
+pub struct Referral {
+    pub referrer: Pubkey,
+    /// share of the platform fee, at most PlatformFees::max_referral_bps
+    pub bps: u16,
+}
+
+impl Referral {
+    pub const MAX_SIZE: usize = 32 //referrer
+    + 2; //bps
+}
+
 #[account]
 ///Account to record single Loan fee
 pub struct LoanFee {
@@ -31,21 +71,33 @@ pub struct LoanFee {
     pub escrow: Pubkey,
     ///owner acct that has not withdrawn its portion of fee
     pub owners: Vec<Pubkey>,
+    ///basis point share of owners[i], snapshotted from the multisig when the fee was taken
+    pub shares: Vec<u16>,
 }
 impl LoanFee {
-    pub const MAX_SIZE: usize = 8 + 32 + 32 + 32 + 4 + (100 * 32);
+    pub const MAX_SIZE: usize = 8 + 32 + 32 + 32
+    + 4 + (104 * 32) //100 owners + treasury + insurance fund + 2 referrers
+    + 4 + (104 * 2); //shares
     pub fn init(
         &mut self,
         amount: u64,
         token_mint: Pubkey,
         loan: Pubkey,
         escrow: Pubkey,
-        owners: Vec<Pubkey>,
+        shares: Vec<(Pubkey, u16)>,
     ) {
         self.amount = amount;
         self.token_mint = token_mint;
         self.loan = loan;
         self.escrow = escrow;
-        self.owners = owners;
+        (self.owners, self.shares) = shares.into_iter().unzip();
+    }
+
+    /// removes `owner` from the fee and returns its share of `amount`
+    pub fn take_share(&mut self, owner: &Pubkey, amount: u64) -> Option<u64> {
+        let index = self.owners.iter().position(|a| a == owner)?;
+        self.owners.remove(index);
+        let bps = self.shares.remove(index);
+        Some((amount as u128 * bps as u128 / Multisig::TOTAL_BPS as u128) as u64)
     }
 }
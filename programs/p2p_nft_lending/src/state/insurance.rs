@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// insurance payout to the lender of a loan whose liquidation came in below principal
+pub struct InsuranceClaim {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub mint: Pubkey,
+    /// debt (principal, interest and late charges) the sale price didn't cover, in the mint's
+    /// base units (lamports for SOL)
+    pub shortfall: u64,
+    pub payout: u64,
+    pub reason: ClaimReason,
+    pub slot: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
+/// why the payout is what it is
+pub enum ClaimReason {
+    ShortfallCovered,
+    CappedPerLoan,
+    FundDepleted,
+}
+
+impl InsuranceClaim {
+    pub const MAX_SIZE: usize = 32 //loan
+    + 32 //lender
+    + 32 //mint
+    + 8 //shortfall
+    + 8 //payout
+    + 1 //reason
+    + 8; //slot
+
+    /// payout towards `shortfall`, bounded by the per-loan cap and the fund's balance
+    pub fn payout_for(shortfall: u64, cap: u64, balance: u64) -> (u64, ClaimReason) {
+        if shortfall <= cap && shortfall <= balance {
+            (shortfall, ClaimReason::ShortfallCovered)
+        } else if cap <= balance {
+            (cap, ClaimReason::CappedPerLoan)
+        } else {
+            (balance, ClaimReason::FundDepleted)
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::errors::ErrorCode;
+use crate::state::Referral;
 use anchor_lang::prelude::*;
 
 #[account]
@@ -10,6 +11,18 @@ pub struct LoanRequest {
     pub slot_duration: u64,
     pub loan: Option<Pubkey>,
     pub borrow_nft_mint: Pubkey,
+    /// open-term request when non zero, the loan runs until called plus this notice
+    pub notice_slots: u64,
+    /// terms priced at request time, copied onto the loan when granted
+    pub ltv: u32,
+    pub interest_rate: u32,
+    pub fee_percentage: u32,
+    /// verified collection whose CollectionConfig applied, Pubkey::default() if none
+    pub collection: Pubkey,
+    /// frontend the borrower came through, if any
+    pub referral: Option<Referral>,
+    /// highest spread over the benchmark rate the borrower accepts, None for fixed rate only
+    pub max_spread: Option<u32>,
 }
 
 impl LoanRequest {
@@ -19,7 +32,14 @@ impl LoanRequest {
     +  32 //requested_token_mint
     + 8 //slot_duration
     + 1 + 32 //loan
-    + 32; //borrow_nft_mint
+    + 32 //borrow_nft_mint
+    + 8 //notice_slots
+    + 4 //ltv
+    + 4 //interest_rate
+    + 4 //fee_percentage
+    + 32 //collection
+    + 1 + Referral::MAX_SIZE //referral
+    + 1 + 4; //max_spread
 
     pub fn init(
         &mut self,
@@ -100,6 +120,26 @@ pub struct Loan {
     pub slot_duration: u64,
     pub start_slot: u64,
     pub last_updated_slot: u64,
+    //open-term loans (notice_slots > 0) ignore slot_duration
+    pub notice_slots: u64,
+    pub call_slot: Option<u64>,
+    //floating rate loans accrue at benchmark + spread instead of interest_rate
+    pub spread: Option<u32>,
+    //principal counted against the collection's exposure until released
+    pub collection_exposure: u64,
+    //principal counted against the currency's debt ceiling until released
+    pub currency_debt: u64,
+    //share of the interest collected booked as a platform fee when the lender claims
+    pub interest_fee_percentage: u32,
+    //late payment terms at grant time, the loan defaults grace_slots after end_slot
+    pub grace_slots: u64,
+    pub penalty_rate: u32,
+    pub late_fee: u64,
+    //late charges added to outstanding_debt so far
+    pub penalty_interest: u64,
+    pub late_fee_charged: u64,
+    //frontends sharing the platform fees of the loan, borrower side first
+    pub referrals: Vec<Referral>,
 }
 
 impl Loan {
@@ -119,7 +159,19 @@ impl Loan {
     + 1 //status
     + 8 //slot_duration
     + 8 //start_slot
-    + 8; //last_updated_slot
+    + 8 //last_updated_slot
+    + 8 //notice_slots
+    + 1 + 8 //call_slot
+    + 1 + 4 //spread
+    + 8 //collection_exposure
+    + 8 //currency_debt
+    + 4 //interest_fee_percentage
+    + 8 //grace_slots
+    + 4 //penalty_rate
+    + 8 //late_fee
+    + 8 //penalty_interest
+    + 8 //late_fee_charged
+    + 4 + (2 * Referral::MAX_SIZE); //referrals
 
     pub fn init(
         &mut self,
@@ -153,8 +205,53 @@ impl Loan {
 
         self.start_slot = start_slot;
         self.last_updated_slot = start_slot;
+        self.notice_slots = 0;
+        self.call_slot = None;
+        self.spread = None;
+        self.collection_exposure = 0;
+        self.currency_debt = 0;
+        self.interest_fee_percentage = 0;
+        self.grace_slots = 0;
+        self.penalty_rate = 0;
+        self.late_fee = 0;
+        self.penalty_interest = 0;
+        self.late_fee_charged = 0;
+        self.referrals = vec![];
         Ok(())
     }
+
+    pub fn set_late_terms(&mut self, grace_slots: u64, penalty_rate: u32, late_fee: u64) {
+        self.grace_slots = grace_slots;
+        self.penalty_rate = penalty_rate;
+        self.late_fee = late_fee;
+    }
+
+    /// adds penalty interest and the late fee to the debt, tracked apart from contract interest
+    pub fn add_late_charges(&mut self, penalty_interest: u64, late_fee: u64) {
+        self.outstanding_debt += penalty_interest + late_fee;
+        self.penalty_interest += penalty_interest;
+        self.late_fee_charged += late_fee;
+    }
+
+    pub fn is_open_term(&self) -> bool {
+        self.notice_slots > 0
+    }
+
+    /// slot after which the loan defaults, open-term loans only mature once called
+    pub fn end_slot(&self) -> u64 {
+        if !self.is_open_term() {
+            return self.start_slot + self.slot_duration;
+        }
+        match self.call_slot {
+            Some(call_slot) => call_slot + self.notice_slots,
+            None => u64::MAX,
+        }
+    }
+
+    /// slot after which the loan can no longer be repaid, end of the grace period
+    pub fn default_slot(&self) -> u64 {
+        self.end_slot().saturating_add(self.grace_slots)
+    }
 }
 
 pub enum LoanStatus {
@@ -174,6 +271,8 @@ pub enum LoanStatus {
     Sell,
     //nft purchased by a third party
     Sold,
+    //nothing left to withdraw: pool loan proceeds returned to the pool, or the borrower refunded
+    Settled,
 }
 
 impl LoanStatus {
@@ -187,6 +286,7 @@ impl LoanStatus {
             LoanStatus::Completed => 5,
             LoanStatus::Sell => 6,
             LoanStatus::Sold => 7,
+            LoanStatus::Settled => 8,
         }
     }
 
@@ -200,6 +300,7 @@ impl LoanStatus {
             5 => Ok(LoanStatus::Completed),
             6 => Ok(LoanStatus::Sell),
             7 => Ok(LoanStatus::Sold),
+            8 => Ok(LoanStatus::Settled),
             _ => Err(ErrorCode::InvalidStatus.into()),
         }
     }
@@ -1,9 +1,29 @@
+pub mod benchmark;
+pub mod collection_config;
+pub mod currency_config;
+pub mod fee_treasury;
 pub mod fees;
+pub mod insurance;
 pub mod multisig;
 pub mod loan;
 pub mod platform_listing;
+pub mod pool;
+pub mod position_listing;
+pub mod pricing;
+pub mod spending_limit;
+pub mod syndicate;
 
+pub use benchmark::*;
+pub use collection_config::*;
+pub use currency_config::*;
+pub use fee_treasury::*;
 pub use fees::*;
+pub use insurance::*;
 pub use multisig::*;
 pub use loan::*;
 pub use platform_listing::*;
+pub use pool::*;
+pub use position_listing::*;
+pub use pricing::*;
+pub use spending_limit::*;
+pub use syndicate::*;
@@ -1,21 +1,61 @@
 use crate::errors::ErrorCode;
+use crate::state::{CollectionParams, CurrencyParams};
+use crate::utils::{
+    insurance_fund_address, COLLECTION_CONFIG_STR, CURRENCY_CONFIG_STR, MULTISIG_SEED_STR,
+    PLATFORM_FEES_SEED_STR,
+};
 use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, InstructionData};
 use solana_program::{self, instruction::Instruction};
 
 #[account]
 pub struct Multisig {
+    ///total vote weight needed to approve a transaction
     pub threshold: u64,
-    ///sequence number to track transactions
-    ///only increase when a transaction gets executed
-    /// meaning a transaction can get replaced before execution
+    ///owner set sequence number, increases when the owners change
+    /// so transactions proposed to the previous owners can't be approved or executed
     pub seqno: u32,
     pub owners: Vec<Pubkey>,
+    // fields below were added after the first deployment, see migrate_state
+    ///index of the next transaction, each proposal gets its own account
+    pub proposal_count: u32,
+    ///slots an approved transaction waits before it can be executed, owners can veto meanwhile
+    pub execution_delay: u64,
+    ///longest a proposal stays open, in slots, 0 lets proposals stay open until executed
+    pub max_proposal_slots: u64,
+    ///vote weight of each owner, empty gives every owner a weight of 1
+    pub vote_weights: Vec<u16>,
+    /// basis point fee share of each owner, empty splits the owners' part equally
+    pub weights: Vec<u16>,
+    /// receives treasury_bps of every fee
+    pub treasury: Pubkey,
+    pub treasury_bps: u16,
+    /// share of every fee credited to the insurance fund
+    pub insurance_bps: u16,
+    /// owner subsets with their own threshold governing some admin instructions
+    pub roles: Vec<RoleConfig>,
+    /// account layout version, bumped by migrate_state
+    pub version: u8,
 }
 
 
 impl Multisig {
     pub const MAX_SIZE: usize = 8 + 4  //threshold, seqno
-    + 4 + (32 * 100); //100 owners max
+    + 4 + (32 * 100) //100 owners max
+    + 4 //proposal_count
+    + 8 //execution_delay
+    + 8 //max_proposal_slots
+    + 4 + (2 * 100) //vote_weights
+    + 4 + (2 * 100) //weights
+    + 32 //treasury
+    + 2 //treasury_bps
+    + 2 //insurance_bps
+    + 4 + (4 * RoleConfig::MAX_SIZE) //one config per role
+    + 1; //version
+
+    pub const VERSION: u8 = 1;
+
+    pub const TOTAL_BPS: u16 = 10_000;
 
     pub fn init(&mut self, owners: Vec<Pubkey>, threshold: u64) -> Result<()> {
         assert_unique_owners(&owners)?;
@@ -33,17 +73,352 @@ impl Multisig {
         self.owners = owners;
         self.threshold = threshold;
         self.seqno = 0;
+        self.proposal_count = 0;
+        self.execution_delay = 0;
+        self.max_proposal_slots = 0;
+        self.vote_weights = vec![];
+        self.weights = vec![];
+        self.treasury = Pubkey::default();
+        self.treasury_bps = 0;
+        self.insurance_bps = 0;
+        self.roles = vec![];
+        self.version = Self::VERSION;
+
+        Ok(())
+    }
 
+    pub fn set_fee_shares(
+        &mut self,
+        weights: Vec<u16>,
+        treasury: Pubkey,
+        treasury_bps: u16,
+        insurance_bps: u16,
+    ) -> Result<()> {
+        let total = weights
+            .iter()
+            .try_fold(treasury_bps, |total, weight| total.checked_add(*weight))
+            .and_then(|total| total.checked_add(insurance_bps));
+        require!(
+            (weights.is_empty() || weights.len() == self.owners.len())
+                && matches!(total, Some(total) if total == Self::TOTAL_BPS
+                    || (weights.is_empty() && total <= Self::TOTAL_BPS))
+                && (treasury_bps == 0 || !self.owners.contains(&treasury)),
+            ErrorCode::InvalidFeeShares
+        );
+        self.weights = weights;
+        self.treasury = treasury;
+        self.treasury_bps = treasury_bps;
+        self.insurance_bps = insurance_bps;
         Ok(())
     }
 
-    pub fn update_owners(&mut self, owners: Vec<Pubkey>) {
-        if (owners.len() as u64) < self.threshold {
-            self.threshold = owners.len() as u64;
+    /// current (recipient, basis points) split of a fee, snapshotted by each LoanFee
+    pub fn fee_shares(&self) -> Vec<(Pubkey, u16)> {
+        let owners_bps = Self::TOTAL_BPS - self.treasury_bps - self.insurance_bps;
+        let mut shares: Vec<(Pubkey, u16)> = if self.owners.is_empty() {
+            vec![]
+        } else if self.weights.is_empty() {
+            // equal split, the rounding remainder goes to the first owner
+            let count = self.owners.len() as u16;
+            self.owners
+                .iter()
+                .enumerate()
+                .map(|(i, owner)| {
+                    let remainder = if i == 0 { owners_bps % count } else { 0 };
+                    (*owner, owners_bps / count + remainder)
+                })
+                .collect()
+        } else {
+            self.owners
+                .iter()
+                .copied()
+                .zip(self.weights.iter().copied())
+                .collect()
+        };
+        if self.treasury_bps > 0 {
+            shares.push((self.treasury, self.treasury_bps));
+        }
+        if self.insurance_bps > 0 {
+            shares.push((insurance_fund_address(), self.insurance_bps));
+        }
+        shares.retain(|(_, bps)| *bps > 0);
+        shares
+    }
+
+    /// replaces the owners, retained owners keep their vote weight and fee share. New owners
+    /// get no fee share and, once votes are weighted, need theirs set through
+    /// update_owners_and_vote_weights
+    pub fn update_owners(&mut self, owners: Vec<Pubkey>) -> Result<()> {
+        let vote_weights = if self.vote_weights.is_empty() {
+            vec![]
+        } else {
+            self.retained_weights(&self.vote_weights, &owners)
+                .into_iter()
+                .collect::<Option<Vec<u16>>>()
+                .ok_or(ErrorCode::InvalidVoteWeights)?
+        };
+        self.replace_owners(owners, vote_weights)
+    }
+
+    /// replaces the owners along with their vote weights (in owners order, empty for one each)
+    /// and the weight threshold
+    pub fn update_owners_and_vote_weights(
+        &mut self,
+        owners: Vec<Pubkey>,
+        vote_weights: Vec<u16>,
+        threshold: u64,
+    ) -> Result<()> {
+        self.replace_owners(owners, vec![])?;
+        self.set_vote_weights(vote_weights, threshold)
+    }
+
+    // weight of each of `owners` among the current owners, None for new owners
+    fn retained_weights(&self, weights: &[u16], owners: &[Pubkey]) -> Vec<Option<u16>> {
+        owners
+            .iter()
+            .map(|owner| {
+                self.owners
+                    .iter()
+                    .position(|current| current == owner)
+                    .and_then(|i| weights.get(i).copied())
+            })
+            .collect()
+    }
+
+    fn replace_owners(&mut self, owners: Vec<Pubkey>, vote_weights: Vec<u16>) -> Result<()> {
+        // a removed owner's fee share has to be handed out through set_fee_shares first
+        require!(
+            self.owners
+                .iter()
+                .zip(self.weights.iter())
+                .all(|(owner, bps)| *bps == 0 || owners.contains(owner)),
+            ErrorCode::InvalidFeeShares
+        );
+        if !self.weights.is_empty() {
+            self.weights = self
+                .retained_weights(&self.weights, &owners)
+                .into_iter()
+                .map(|bps| bps.unwrap_or(0))
+                .collect();
         }
         self.owners = owners.clone();
+        self.vote_weights = vote_weights;
+        // removed owners lose their roles, a role left without members falls back to the owners
+        for config in self.roles.iter_mut() {
+            config.members.retain(|member| owners.contains(member));
+        }
+        self.roles.retain(|config| !config.members.is_empty());
+        self.clamp_thresholds();
         self.seqno += 1;
+        Ok(())
+    }
+
+    /// sets each owner's vote weight (in owners order, empty for one each) and the weight
+    /// threshold together so the threshold is always reachable
+    pub fn set_vote_weights(&mut self, vote_weights: Vec<u16>, threshold: u64) -> Result<()> {
+        require!(
+            (vote_weights.is_empty() || vote_weights.len() == self.owners.len())
+                && vote_weights.iter().all(|weight| *weight > 0),
+            ErrorCode::InvalidVoteWeights
+        );
+        self.vote_weights = vote_weights;
+        require!(
+            threshold > 0 && threshold <= self.weight_of(&self.owners),
+            ErrorCode::InvalidThreshold
+        );
+        self.threshold = threshold;
+        self.clamp_thresholds();
+        // pending transactions were tallied with the previous weights
+        self.seqno += 1;
+        Ok(())
+    }
+
+    /// vote weight of owners[i]
+    pub fn vote_weight(&self, owner_index: usize) -> u64 {
+        self.vote_weights.get(owner_index).map_or(1, |weight| *weight as u64)
+    }
+
+    /// total vote weight of `members`
+    pub fn weight_of(&self, members: &[Pubkey]) -> u64 {
+        self.owners
+            .iter()
+            .enumerate()
+            .filter(|(_, owner)| members.contains(owner))
+            .map(|(i, _)| self.vote_weight(i))
+            .sum()
+    }
+
+    // keeps the owners' and roles' thresholds within reach of their total weight
+    fn clamp_thresholds(&mut self) {
+        self.threshold = std::cmp::min(self.threshold, self.weight_of(&self.owners));
+        let weights: Vec<u64> = self
+            .roles
+            .iter()
+            .map(|config| self.weight_of(&config.members))
+            .collect();
+        for (config, weight) in self.roles.iter_mut().zip(weights) {
+            config.threshold = std::cmp::min(config.threshold, weight);
+        }
+    }
+
+    pub fn set_roles(&mut self, roles: Vec<RoleConfig>) -> Result<()> {
+        for (i, config) in roles.iter().enumerate() {
+            assert_unique_owners(&config.members)?;
+            require!(
+                !roles.iter().skip(i + 1).any(|other| other.role == config.role)
+                    && config.members.len() <= RoleConfig::MAX_MEMBERS
+                    && config.members.iter().all(|member| self.owners.contains(member))
+                    && config.threshold > 0
+                    && config.threshold <= self.weight_of(&config.members),
+                ErrorCode::InvalidRoles
+            );
+        }
+        self.roles = roles;
+        // pending transactions were proposed under the previous roles
+        self.seqno += 1;
+        Ok(())
+    }
+
+    /// members and threshold whose approvals `instructions` need: those of each configured
+    /// role they require, the owners' for instructions without a configured role
+    pub fn quorums(&self, instructions: &[TransactionInstruction]) -> Vec<(&[Pubkey], u64)> {
+        let mut roles: Vec<&RoleConfig> = vec![];
+        // nothing to execute still takes the owners' threshold
+        let mut needs_owners = instructions.is_empty();
+        for instruction in instructions {
+            let config = Role::of(instruction)
+                .and_then(|role| self.roles.iter().find(|config| config.role == role));
+            match config {
+                Some(config) if !roles.iter().any(|other| other.role == config.role) => {
+                    roles.push(config)
+                }
+                Some(_) => {}
+                None => needs_owners = true,
+            }
+        }
+        let mut quorums: Vec<(&[Pubkey], u64)> = roles
+            .into_iter()
+            .map(|config| (&config.members[..], config.threshold))
+            .collect();
+        if needs_owners {
+            quorums.push((&self.owners[..], self.threshold));
+        }
+        quorums
+    }
+
+    /// the proposer must hold every role the instructions require
+    pub fn can_propose(&self, proposer: &Pubkey, instructions: &[TransactionInstruction]) -> bool {
+        self.owners.contains(proposer)
+            && self
+                .quorums(instructions)
+                .iter()
+                .all(|(members, _)| members.contains(proposer))
+    }
+
+    pub fn is_approved(&self, tx: &Transaction) -> bool {
+        self.quorums(&tx.instructions())
+            .iter()
+            .all(|(members, threshold)| self.votes(&tx.signers, members) >= *threshold)
+    }
+
+    /// total vote weight against `tx`
+    pub fn rejected_weight(&self, tx: &Transaction) -> u64 {
+        self.votes(&tx.rejections, &self.owners)
+    }
+
+    /// total vote weight of the owners vetoing `tx`
+    pub fn vetoed_weight(&self, tx: &Transaction) -> u64 {
+        self.votes(&tx.vetoes, &self.owners)
+    }
+
+    /// false once rejections leave some quorum unable to reach its threshold
+    pub fn can_pass(&self, tx: &Transaction) -> bool {
+        self.quorums(&tx.instructions()).iter().all(|(members, threshold)| {
+            self.weight_of(members) - self.votes(&tx.rejections, members) >= *threshold
+        })
     }
+
+    /// marks the transaction failed once it can't pass, starts the execution delay when it's
+    /// approved and resets it if approvals drop (revoked or turned into rejections)
+    pub fn tally(&self, tx: &mut Transaction, slot: u64) {
+        tx.failed = tx.failed || !self.can_pass(tx);
+        if !self.is_approved(tx) {
+            tx.approved_slot = None;
+        } else if tx.approved_slot.is_none() {
+            tx.approved_slot = Some(slot);
+        }
+    }
+
+    // vote weight of owners[i] with votes[i] set, counted for `members`
+    fn votes(&self, votes: &[bool], members: &[Pubkey]) -> u64 {
+        self.owners
+            .iter()
+            .zip(votes)
+            .enumerate()
+            .filter(|(_, (owner, vote))| **vote && members.contains(owner))
+            .map(|(i, _)| self.vote_weight(i))
+            .sum()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// platform fee parameters
+    FeeAdmin,
+    /// fee split and treasury withdrawals
+    Treasury,
+    /// pausing new loans
+    PauseGuardian,
+    /// owners, roles and the proposal rules
+    OwnerManager,
+}
+
+impl Role {
+    /// role governing `instruction`, None for instructions left to all owners
+    pub fn of(instruction: &TransactionInstruction) -> Option<Role> {
+        if instruction.program_id != crate::ID || instruction.data.len() < 8 {
+            return None;
+        }
+        use crate::instruction::*;
+        let roles = [
+            (SetPlatformFees::DISCRIMINATOR, Role::FeeAdmin),
+            (SetFeeModel::DISCRIMINATOR, Role::FeeAdmin),
+            (SetLatePaymentTerms::DISCRIMINATOR, Role::FeeAdmin),
+            (SetMaxReferralFee::DISCRIMINATOR, Role::FeeAdmin),
+            (SetMaxSpread::DISCRIMINATOR, Role::FeeAdmin),
+            (SetFeeShares::DISCRIMINATOR, Role::Treasury),
+            (WithdrawTreasury::DISCRIMINATOR, Role::Treasury),
+            (SetSpendingLimit::DISCRIMINATOR, Role::Treasury),
+            (SetPaused::DISCRIMINATOR, Role::PauseGuardian),
+            (SetOwners::DISCRIMINATOR, Role::OwnerManager),
+            (SetOwnersAndChangeThreshold::DISCRIMINATOR, Role::OwnerManager),
+            (SetOwnersAndWeights::DISCRIMINATOR, Role::OwnerManager),
+            (ChangeThreshold::DISCRIMINATOR, Role::OwnerManager),
+            (SetRoles::DISCRIMINATOR, Role::OwnerManager),
+            (SetExecutionDelay::DISCRIMINATOR, Role::OwnerManager),
+            (SetMaxProposalSlots::DISCRIMINATOR, Role::OwnerManager),
+        ];
+        roles
+            .iter()
+            .find(|(discriminator, _)| discriminator[..] == instruction.data[..8])
+            .map(|(_, role)| *role)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub struct RoleConfig {
+    pub role: Role,
+    /// multisig owners holding the role
+    pub members: Vec<Pubkey>,
+    /// member vote weight needed by the role's instructions
+    pub threshold: u64,
+}
+
+impl RoleConfig {
+    pub const MAX_MEMBERS: usize = 10;
+    pub const MAX_SIZE: usize = 1 //role
+    + 4 + (RoleConfig::MAX_MEMBERS * 32) //members
+    + 8; //threshold
 }
 
 fn assert_unique_owners(owners: &[Pubkey]) -> Result<()> {
@@ -60,36 +435,232 @@ pub struct Transaction {
     pub proposer: Pubkey,
     // The multisig account this transaction belongs to.
     pub multisig: Pubkey,
-    // Target program to execute against.
-    pub program_id: Pubkey,
     // Boolean ensuring one time execution.
     pub did_execute: bool,
     // Owner set sequence number.
     pub seqno: u32,
-    // Accounts required for the transaction.
-    pub accounts: Vec<TransactionAccount>,
+    // Proposal index, seeds the transaction account.
+    pub index: u32,
     // signers[index] is true iff multisig.owners[index] signed the transaction.
     pub signers: Vec<bool>,
-    // Instruction data for the transaction.
-    pub data: Vec<u8>,
+    // rejections[index] is true iff multisig.owners[index] voted against the transaction.
+    pub rejections: Vec<bool>,
+    // Set once rejections leave too little vote weight to reach the threshold.
+    pub failed: bool,
+    // Slot the transaction reached the threshold, starts the execution delay.
+    pub approved_slot: Option<u64>,
+    // Last slot the transaction can be approved or executed, None if it never expires.
+    pub expiry_slot: Option<u64>,
+    // Instructions executed in order, all or none of them take effect. Empty for typed proposals.
+    pub instructions: Vec<TransactionInstruction>,
+    // Admin instruction with decodable arguments, built when the transaction executes.
+    pub proposal: Option<Proposal>,
+    // Bumped whenever an owner withdraws an approval, voiding approvals signed off-chain before.
+    pub vote_nonce: u32,
+    // vetoes[index] is true iff multisig.owners[index] vetoed the approved transaction.
+    pub vetoes: Vec<bool>,
 }
 
 impl Transaction {
-    pub const MAX_SIZE: usize = 32 //proposer
+    // Everything but the instructions.
+    pub const BASE_SIZE: usize = 32 //proposer
     + 32 //multisig
-    + 32 //program_id
     + 1 //did_execute
     + 4  // seqno
-    + 4 + (2 * TransactionAccount::MAX_SIZE) // 20 accounts max
-    + 4 + (1 * 20) // signers max
+    + 4 // index
+    + 4 + 100 // signers, one per owner
+    + 4 + 100 // rejections, one per owner
+    + 1 // failed
+    + 1 + 8 // approved_slot
+    + 1 + 8 // expiry_slot
+    + 4 // instructions
+    + 1 // proposal
+    + 4 // vote_nonce
+    + 4 + 100; // vetoes, one per owner
+    // Single instruction transaction.
+    pub const MAX_SIZE: usize = Transaction::BASE_SIZE + TransactionInstruction::MAX_SIZE;
+
+    pub fn is_expired(&self, slot: u64) -> bool {
+        matches!(self.expiry_slot, Some(expiry_slot) if slot > expiry_slot)
+    }
+
+    pub fn rejection_count(&self) -> u64 {
+        self.rejections.iter().filter(|&did_reject| *did_reject).count() as u64
+    }
+
+    // Records owners[owner_index]'s vote, an owner either approves or rejects.
+    pub fn vote(&mut self, owner_index: usize, approve: bool) {
+        if !approve {
+            self.vote_nonce = self.vote_nonce.wrapping_add(1);
+        }
+        self.signers[owner_index] = approve;
+        self.rejections[owner_index] = !approve;
+    }
+
+    // Withdraws owners[owner_index]'s approval without voting against.
+    pub fn revoke(&mut self, owner_index: usize) {
+        self.signers[owner_index] = false;
+        self.vote_nonce = self.vote_nonce.wrapping_add(1);
+    }
+
+    // Instructions to execute: the stored ones, or the one built from the typed proposal.
+    pub fn instructions(&self) -> Vec<TransactionInstruction> {
+        match &self.proposal {
+            Some(proposal) => vec![proposal.instruction(self)],
+            None => self.instructions.clone(),
+        }
+    }
+
+    // Message owners sign off-chain to approve the transaction at `key` until `expiry_slot`:
+    // the key, the sha256 of the serialized instructions, the expiry slot and the vote nonce.
+    pub fn approval_message(&self, key: &Pubkey, expiry_slot: u64) -> Vec<u8> {
+        let instructions = self.instructions().try_to_vec().unwrap_or_default();
+        [
+            key.as_ref(),
+            solana_program::hash::hash(&instructions).as_ref(),
+            &expiry_slot.to_le_bytes(),
+            &self.vote_nonce.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    // Space for a transaction holding exactly `instructions`.
+    pub fn space(instructions: &[TransactionInstruction]) -> usize {
+        Self::BASE_SIZE + instructions.iter().map(TransactionInstruction::size).sum::<usize>()
+    }
+}
+
+// Typed proposal for one of the program's own admin instructions, so approvers can read what
+// they sign from the transaction account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum Proposal {
+    SetPlatformFees { fee_percentage: u32, interest_rate: u32, ltv: u32 },
+    SetFeeModel { origination_fee: u32, interest_fee: u32 },
+    SetOwners { owners: Vec<Pubkey> },
+    ChangeThreshold { threshold: u64 },
+    SetPaused { paused: bool },
+    // Registry updates create the config on first use, the proposer pays its rent and must
+    // sign the execute transaction.
+    SetCollectionConfig { collection: Pubkey, params: CollectionParams },
+    SetCurrencyConfig { mint: Pubkey, params: CurrencyParams },
+}
+
+impl Proposal {
+    pub fn size(&self) -> usize {
+        self.try_to_vec().map_or(0, |data| data.len())
+    }
+
+    // The admin instruction `tx` executes, signed by the multisig.
+    pub fn instruction(&self, tx: &Transaction) -> TransactionInstruction {
+        use crate::instruction;
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+        let multisig_signer = TransactionAccount {
+            pubkey: pda(&[MULTISIG_SEED_STR.as_bytes()]),
+            is_signer: true,
+            is_writable: false,
+        };
+        let writable = |pubkey| TransactionAccount {
+            pubkey,
+            is_signer: false,
+            is_writable: true,
+        };
+        let platform_fees = || writable(pda(&[PLATFORM_FEES_SEED_STR.as_bytes()]));
+        let registry = |config| {
+            vec![
+                writable(config),
+                multisig_signer.clone(),
+                TransactionAccount {
+                    pubkey: tx.proposer,
+                    is_signer: true,
+                    is_writable: true,
+                },
+                TransactionAccount {
+                    pubkey: solana_program::system_program::ID,
+                    is_signer: false,
+                    is_writable: false,
+                },
+            ]
+        };
+        let (accounts, data) = match self.clone() {
+            Proposal::SetPlatformFees {
+                fee_percentage,
+                interest_rate,
+                ltv,
+            } => (
+                vec![platform_fees(), multisig_signer.clone()],
+                instruction::SetPlatformFees {
+                    fee_percentage,
+                    interest_rate,
+                    ltv,
+                }
+                .data(),
+            ),
+            Proposal::SetFeeModel {
+                origination_fee,
+                interest_fee,
+            } => (
+                vec![platform_fees(), multisig_signer.clone()],
+                instruction::SetFeeModel {
+                    origination_fee,
+                    interest_fee,
+                }
+                .data(),
+            ),
+            Proposal::SetOwners { owners } => (
+                vec![writable(tx.multisig), multisig_signer.clone()],
+                instruction::SetOwners { owners }.data(),
+            ),
+            Proposal::ChangeThreshold { threshold } => (
+                vec![writable(tx.multisig), multisig_signer.clone()],
+                instruction::ChangeThreshold { threshold }.data(),
+            ),
+            Proposal::SetPaused { paused } => (
+                vec![platform_fees(), multisig_signer.clone()],
+                instruction::SetPaused { paused }.data(),
+            ),
+            Proposal::SetCollectionConfig { collection, params } => (
+                registry(pda(&[COLLECTION_CONFIG_STR.as_bytes(), collection.as_ref()])),
+                instruction::SetCollectionConfig { collection, params }.data(),
+            ),
+            Proposal::SetCurrencyConfig { mint, params } => (
+                registry(pda(&[CURRENCY_CONFIG_STR.as_bytes(), mint.as_ref()])),
+                instruction::SetCurrencyConfig { mint, params }.data(),
+            ),
+        };
+        TransactionInstruction {
+            program_id: crate::ID,
+            accounts,
+            data,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionInstruction {
+    // Target program to execute against.
+    pub program_id: Pubkey,
+    // Accounts required for the instruction.
+    pub accounts: Vec<TransactionAccount>,
+    // Instruction data.
+    pub data: Vec<u8>,
+}
+
+impl TransactionInstruction {
+    pub const MAX_SIZE: usize = 32 //program_id
+    + 4 + (20 * TransactionAccount::MAX_SIZE) // 20 accounts max
     + 4 + (1 * 1000); // data
+
+    pub fn size(&self) -> usize {
+        32 + 4 + (self.accounts.len() * TransactionAccount::MAX_SIZE) + 4 + self.data.len()
+    }
 }
-impl From<&Transaction> for Instruction {
-    fn from(tx: &Transaction) -> Instruction {
+
+impl From<&TransactionInstruction> for Instruction {
+    fn from(ix: &TransactionInstruction) -> Instruction {
         Instruction {
-            program_id: tx.program_id,
-            accounts: tx.accounts.iter().map(Into::into).collect(),
-            data: tx.data.clone(),
+            program_id: ix.program_id,
+            accounts: ix.accounts.iter().map(Into::into).collect(),
+            data: ix.data.clone(),
         }
     }
 }
@@ -0,0 +1,166 @@
+use crate::errors::ErrorCode;
+use crate::utils::SLOTS_PER_YEAR;
+use anchor_lang::prelude::*;
+
+#[account]
+/// peer-to-pool liquidity for a single (collection, currency) pair
+pub struct LendingPool {
+    /// verified metaplex collection the collateral must belong to
+    pub collection: Pubkey,
+    pub currency_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub vault: Pubkey,
+    /// tokens sitting in the vault
+    pub available_liquidity: u64,
+    /// principal currently lent out
+    pub total_borrowed: u64,
+    /// value the pool assigns to every nft of the collection
+    pub nft_valuation: u64,
+    pub ltv: u32,
+    pub max_slot_duration: u64,
+    //kinked utilization curve (all rates in the same 3dp percentage as PlatformFees)
+    pub base_rate: u32,
+    pub optimal_utilization: u32,
+    pub slope1: u32,
+    pub slope2: u32,
+    pub bump: u8,
+    /// sum of principal * interest_rate of the loans accruing interest
+    pub rate_weighted_principal: u128,
+    /// sum of principal * interest_rate * start_slot of the same loans
+    pub rate_weighted_start: u128,
+    /// defaulted loans whose collateral is listed but not settled yet, deposits wait for them
+    pub pending_sales: u32,
+}
+
+impl LendingPool {
+    pub const MAX_SIZE: usize = 32 //collection
+    + 32 //currency_mint
+    + 32 //share_mint
+    + 32 //vault
+    + 8 //available_liquidity
+    + 8 //total_borrowed
+    + 8 //nft_valuation
+    + 4 //ltv
+    + 8 //max_slot_duration
+    + 4 //base_rate
+    + 4 //optimal_utilization
+    + 4 //slope1
+    + 4 //slope2
+    + 1 //bump
+    + 16 //rate_weighted_principal
+    + 16 //rate_weighted_start
+    + 4; //pending_sales
+
+    pub fn init(
+        &mut self,
+        collection: Pubkey,
+        currency_mint: Pubkey,
+        share_mint: Pubkey,
+        vault: Pubkey,
+        bump: u8,
+        config: PoolConfig,
+    ) -> Result<()> {
+        self.collection = collection;
+        self.currency_mint = currency_mint;
+        self.share_mint = share_mint;
+        self.vault = vault;
+        self.available_liquidity = 0;
+        self.total_borrowed = 0;
+        self.bump = bump;
+        self.rate_weighted_principal = 0;
+        self.rate_weighted_start = 0;
+        self.pending_sales = 0;
+        self.set_config(config)
+    }
+
+    pub fn set_config(&mut self, config: PoolConfig) -> Result<()> {
+        require!(
+            config.ltv > 0
+                && config.ltv < 1000
+                && config.optimal_utilization > 0
+                && config.optimal_utilization < 1000,
+            ErrorCode::InvalidPoolConfig
+        );
+        self.nft_valuation = config.nft_valuation;
+        self.ltv = config.ltv;
+        self.max_slot_duration = config.max_slot_duration;
+        self.base_rate = config.base_rate;
+        self.optimal_utilization = config.optimal_utilization;
+        self.slope1 = config.slope1;
+        self.slope2 = config.slope2;
+        Ok(())
+    }
+
+    /// everything the share holders own: idle liquidity plus principal lent out and the
+    /// interest accrued on it
+    pub fn total_value(&self, slot: u64) -> Result<u64> {
+        let accrued_interest = self.accrued_interest(slot)?;
+        Ok(self
+            .available_liquidity
+            .checked_add(self.total_borrowed)
+            .and_then(|total| total.checked_add(accrued_interest))
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+
+    /// simple interest at their contract rate accrued on the outstanding loans by `slot`
+    pub fn accrued_interest(&self, slot: u64) -> Result<u64> {
+        let accrued = self
+            .rate_weighted_principal
+            .checked_mul(slot as u128)
+            .and_then(|total| total.checked_sub(self.rate_weighted_start))
+            .ok_or(ErrorCode::MathOverflow)?
+            / (1000 * SLOTS_PER_YEAR as u128);
+        Ok(u64::try_from(accrued).map_err(|_| ErrorCode::MathOverflow)?)
+    }
+
+    /// books a new loan's principal, its interest accrues from `start_slot`
+    pub fn add_loan(&mut self, principal: u64, interest_rate: u32, start_slot: u64) -> Result<()> {
+        let (weighted_principal, weighted_start) =
+            Self::rate_weights(principal, interest_rate, start_slot)?;
+        self.total_borrowed = self
+            .total_borrowed
+            .checked_add(principal)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.rate_weighted_principal = self
+            .rate_weighted_principal
+            .checked_add(weighted_principal)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.rate_weighted_start = self
+            .rate_weighted_start
+            .checked_add(weighted_start)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// takes a loan's principal and accrued interest off the books, once it is repaid or
+    /// written down on default
+    pub fn remove_loan(&mut self, principal: u64, interest_rate: u32, start_slot: u64) -> Result<()> {
+        let (weighted_principal, weighted_start) =
+            Self::rate_weights(principal, interest_rate, start_slot)?;
+        self.total_borrowed = self.total_borrowed.saturating_sub(principal);
+        self.rate_weighted_principal = self
+            .rate_weighted_principal
+            .saturating_sub(weighted_principal);
+        self.rate_weighted_start = self.rate_weighted_start.saturating_sub(weighted_start);
+        Ok(())
+    }
+
+    fn rate_weights(principal: u64, interest_rate: u32, start_slot: u64) -> Result<(u128, u128)> {
+        let weighted_principal = principal as u128 * interest_rate as u128;
+        let weighted_start = weighted_principal
+            .checked_mul(start_slot as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok((weighted_principal, weighted_start))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PoolConfig {
+    pub nft_valuation: u64,
+    pub ltv: u32,
+    pub max_slot_duration: u64,
+    pub base_rate: u32,
+    pub optimal_utilization: u32,
+    pub slope1: u32,
+    pub slope2: u32,
+}
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// lend / borrow nft escrowed for sale on the secondary market
+pub struct PositionListing {
+    pub seller: Pubkey,
+    pub position_mint: Pubkey,
+    pub loan: Pubkey,
+    pub price: u64,
+    /// loan currency, Pubkey::default() for SOL
+    pub price_mint: Pubkey,
+    pub bump: u8,
+}
+
+impl PositionListing {
+    pub const MAX_SIZE: usize = 32 //seller
+    + 32 //position_mint
+    + 32 //loan
+    + 8 //price
+    + 32 //price_mint
+    + 1; //bump
+
+    pub fn init(
+        &mut self,
+        seller: Pubkey,
+        position_mint: Pubkey,
+        loan: Pubkey,
+        price: u64,
+        price_mint: Pubkey,
+        bump: u8,
+    ) {
+        self.seller = seller;
+        self.position_mint = position_mint;
+        self.loan = loan;
+        self.price = price;
+        self.price_mint = price_mint;
+        self.bump = bump;
+    }
+}
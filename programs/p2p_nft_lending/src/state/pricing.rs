@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// multisig managed loan pricing, global under Pubkey::default() or per verified collection
+pub struct PricingTable {
+    pub collection: Pubkey,
+    /// looked up in order, the first tier covering the request wins
+    pub tiers: Vec<PricingTier>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PricingTier {
+    /// upper bound of the ltv band (requested_amount / nft_worth), 3dp
+    pub max_ltv: u32,
+    /// upper bound of the duration band, u64::MAX to cover open-term loans
+    pub max_slot_duration: u64,
+    pub interest_rate: u32,
+    pub fee_percentage: u32,
+}
+
+impl PricingTable {
+    pub const MAX_TIERS: usize = 16;
+    pub const MAX_SIZE: usize = 32 //collection
+    + 4 + (PricingTable::MAX_TIERS * (4 + 8 + 4 + 4)) //tiers
+    + 1; //bump
+
+    pub fn tier_for(&self, ltv: u32, slot_duration: u64) -> Option<PricingTier> {
+        self.tiers
+            .iter()
+            .find(|tier| ltv <= tier.max_ltv && slot_duration <= tier.max_slot_duration)
+            .copied()
+    }
+}
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::{Multisig, Role};
+
+#[account]
+/// amounts of a mint's fee treasury owners can withdraw each period without a proposal
+pub struct SpendingLimits {
+    pub mint: Pubkey,
+    pub limits: Vec<SpendingLimit>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Spender {
+    Owner(Pubkey),
+    /// budget shared by the role's members
+    Role(Role),
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct SpendingLimit {
+    pub spender: Spender,
+    /// most that can be withdrawn per period
+    pub amount: u64,
+    /// period length in slots, 0 for a one-off budget that never resets
+    pub period_slots: u64,
+    pub period_start: u64,
+    /// withdrawn since period_start
+    pub spent: u64,
+}
+
+impl SpendingLimits {
+    pub const MAX_LIMITS: usize = 16;
+    pub const MAX_SIZE: usize = 32 //mint
+    + 4 + (SpendingLimits::MAX_LIMITS * (1 + 32 + 8 + 8 + 8 + 8)) //limits
+    + 1; //bump
+
+    /// sets `spender`'s limit, starting a new period, an amount of 0 removes it
+    pub fn set(&mut self, spender: Spender, amount: u64, period_slots: u64, slot: u64) -> Result<()> {
+        self.limits.retain(|limit| limit.spender != spender);
+        if amount == 0 {
+            return Ok(());
+        }
+        require!(
+            self.limits.len() < Self::MAX_LIMITS,
+            ErrorCode::SpendingLimitsFull
+        );
+        self.limits.push(SpendingLimit {
+            spender,
+            amount,
+            period_slots,
+            period_start: slot,
+            spent: 0,
+        });
+        Ok(())
+    }
+
+    /// charges `amount` to the first limit of `owner`, or of a role they hold, with enough
+    /// left in its current period
+    pub fn spend(&mut self, multisig: &Multisig, owner: &Pubkey, amount: u64, slot: u64) -> Result<Spender> {
+        require!(multisig.owners.contains(owner), ErrorCode::InvalidOwner);
+        let holds = |spender: &Spender| match spender {
+            Spender::Owner(key) => key == owner,
+            Spender::Role(role) => multisig
+                .roles
+                .iter()
+                .any(|config| config.role == *role && config.members.contains(owner)),
+        };
+        for limit in self.limits.iter_mut().filter(|limit| holds(&limit.spender)) {
+            if limit.period_slots > 0 && slot >= limit.period_start.saturating_add(limit.period_slots) {
+                // a new period starts with the first withdrawal after the last one ended
+                limit.period_start = slot;
+                limit.spent = 0;
+            }
+            let spent = limit.spent.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+            if spent <= limit.amount {
+                limit.spent = spent;
+                return Ok(limit.spender);
+            }
+        }
+        Err(ErrorCode::SpendingLimitExceeded.into())
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+#[account]
+/// several lenders funding a single loan request, each holding lend notes pro rata
+pub struct Syndicate {
+    pub loan_request: Pubkey,
+    pub requested_token_mint: Pubkey,
+    /// fungible lend notes, minted 1:1 with the amount funded
+    pub note_mint: Pubkey,
+    /// holds contributions until the loan is drawn (or refunded)
+    pub vault: Pubkey,
+    pub funded_amount: u64,
+    /// last slot contributions are accepted, refunds open after it if still unfilled
+    pub deadline_slot: u64,
+    pub loan: Option<Pubkey>,
+    /// repayment / sale proceeds already paid out to note holders
+    pub claimed_amount: u64,
+    pub bump: u8,
+}
+
+impl Syndicate {
+    pub const MAX_SIZE: usize = 32 //loan_request
+    + 32 //requested_token_mint
+    + 32 //note_mint
+    + 32 //vault
+    + 8 //funded_amount
+    + 8 //deadline_slot
+    + 1 + 32 //loan
+    + 8 //claimed_amount
+    + 1; //bump
+
+    pub fn init(
+        &mut self,
+        loan_request: Pubkey,
+        requested_token_mint: Pubkey,
+        note_mint: Pubkey,
+        vault: Pubkey,
+        deadline_slot: u64,
+        bump: u8,
+    ) {
+        self.loan_request = loan_request;
+        self.requested_token_mint = requested_token_mint;
+        self.note_mint = note_mint;
+        self.vault = vault;
+        self.funded_amount = 0;
+        self.deadline_slot = deadline_slot;
+        self.loan = None;
+        self.claimed_amount = 0;
+        self.bump = bump;
+    }
+}
@@ -1,10 +1,24 @@
 use crate::{
+    errors::ErrorCode,
     instructions::RepayLoansContext,
-    math::{Decimal, Rate, TryAdd, TryDiv, TryMul},
-    state::loan::LoanStatus,
+    math::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub},
+    state::{
+        loan::LoanStatus, BenchmarkRate, CollectionConfig, Loan, LoanFee, Multisig, PricingTable,
+        PricingTier, RateSegment, Referral,
+    },
+};
+use anchor_lang::prelude::{
+    require, Account, AccountInfo, AccountsExit, Clock, Context, ProgramError, Pubkey,
+    SolanaSysvar,
+};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::metadata::MetadataAccount;
+use anchor_spl::token_interface::spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
 };
-use anchor_lang::prelude::{Context, ProgramError};
 use solana_program::clock::{DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT, SECONDS_PER_DAY};
+use solana_program::{ed25519_program, instruction::Instruction};
 
 // platform Seeds
 pub const MULTISIG_SEED_STR: &str = "multisig";
@@ -18,6 +32,23 @@ pub const BORROW_NFT_MINT: &str = "borrow_nft_mint";
 pub const GRANT_LOAN_STR: &str = "grant_loan";
 pub const LOAN_STR: &str = "loan";
 pub const LOAN_FEE_STR: &str = "loan_fee";
+pub const LENDING_POOL_STR: &str = "lending_pool";
+pub const POOL_SHARE_MINT_STR: &str = "pool_share_mint";
+pub const POOL_VAULT_STR: &str = "pool_vault";
+pub const SYNDICATE_STR: &str = "syndicate";
+pub const SYNDICATE_NOTE_MINT_STR: &str = "syndicate_note_mint";
+pub const SYNDICATE_VAULT_STR: &str = "syndicate_vault";
+pub const POSITION_LISTING_STR: &str = "position_listing";
+pub const POSITION_ESCROW_STR: &str = "position_escrow";
+pub const BENCHMARK_RATE_STR: &str = "benchmark_rate";
+pub const PRICING_TABLE_STR: &str = "pricing_table";
+pub const COLLECTION_CONFIG_STR: &str = "collection_config";
+pub const CURRENCY_CONFIG_STR: &str = "currency_config";
+pub const INTEREST_FEE_STR: &str = "interest_fee";
+pub const FEE_TREASURY_STR: &str = "fee_treasury";
+pub const INSURANCE_FUND_STR: &str = "insurance_fund";
+pub const INSURANCE_CLAIM_STR: &str = "insurance_claim";
+pub const SPENDING_LIMITS_STR: &str = "spending_limits";
 
 /// Number of slots per year
 pub const SLOTS_PER_YEAR: u64 =
@@ -48,6 +79,374 @@ pub fn compound_interest(
     compounded_interest_rate.try_mul(borrow_amount)
 }
 
+/// interest accrued on a floating rate loan between `from_slot` and `to_slot`,
+/// every benchmark segment (plus `spread`) is applied for the slots it was in force
+///
+/// slots before the first recorded segment accrue at the first segment's rate
+pub fn floating_interest(
+    borrow_amount: u64,
+    history: &[RateSegment],
+    spread: u32,
+    from_slot: u64,
+    to_slot: u64,
+) -> Result<Decimal, ProgramError> {
+    let mut interest = Decimal::zero();
+    for (i, segment) in history.iter().enumerate() {
+        let segment_start = if i == 0 {
+            from_slot
+        } else {
+            std::cmp::max(from_slot, segment.start_slot)
+        };
+        let segment_end = match history.get(i + 1) {
+            Some(next) => std::cmp::min(to_slot, next.start_slot),
+            None => to_slot,
+        };
+        if segment_end <= segment_start {
+            continue;
+        }
+        let rate = segment
+            .rate
+            .checked_add(spread)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let compounded = compound_interest(borrow_amount, rate, segment_end - segment_start)?;
+        interest = interest.try_add(compounded.try_sub(Decimal::from(borrow_amount))?)?;
+    }
+    Ok(interest)
+}
+
+/// remaining account with the given key, optional accounts are passed in any order
+pub fn find_remaining_account<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Option<&'a AccountInfo<'info>> {
+    remaining_accounts.iter().find(|info| info.key == key)
+}
+
+/// floating rate loans pass the benchmark rate account in remaining accounts
+pub fn load_benchmark_rate<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> anchor_lang::Result<Account<'info, BenchmarkRate>> {
+    let (benchmark_rate, _) =
+        Pubkey::find_program_address(&[BENCHMARK_RATE_STR.as_bytes()], program_id);
+    let info = find_remaining_account(remaining_accounts, &benchmark_rate)
+        .ok_or(ErrorCode::MissingBenchmarkRate)?;
+    Account::try_from(info)
+}
+
+/// interest accrued on `loan` since it was last updated
+pub fn accrued_interest<'info>(
+    loan: &Loan,
+    current_slot: u64,
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> anchor_lang::Result<u64> {
+    let slots_elapsed = current_slot - loan.last_updated_slot;
+    let interest = match loan.spread {
+        Some(spread) => {
+            let benchmark_rate = load_benchmark_rate(remaining_accounts, program_id)?;
+            floating_interest(
+                loan.requested_amount,
+                &benchmark_rate.history,
+                spread,
+                loan.last_updated_slot,
+                current_slot,
+            )?
+        }
+        None => compound_interest(loan.requested_amount, loan.interest_rate, slots_elapsed)?
+            .try_sub(Decimal::from(loan.requested_amount))?,
+    };
+    Ok(interest.try_round_u64()?)
+}
+
+/// penalty interest and late fee owed on `loan` since it was last updated, penalty interest
+/// accrues at penalty_rate on top of the contract rate between maturity and default
+pub fn late_charges(loan: &Loan, current_slot: u64) -> anchor_lang::Result<(u64, u64)> {
+    let end_slot = loan.end_slot();
+    if current_slot <= end_slot {
+        return Ok((0, 0));
+    }
+    let from = std::cmp::max(loan.last_updated_slot, end_slot);
+    let to = std::cmp::min(current_slot, loan.default_slot());
+    let penalty_interest = if to > from {
+        compound_interest(loan.requested_amount, loan.penalty_rate, to - from)?
+            .try_sub(Decimal::from(loan.requested_amount))?
+            .try_round_u64()?
+    } else {
+        0
+    };
+    let late_fee = if loan.late_fee_charged == 0 {
+        loan.late_fee
+    } else {
+        0
+    };
+    Ok((penalty_interest, late_fee))
+}
+
+/// requested_amount / nft_worth as a 3dp percentage, rounded up
+pub fn loan_to_value(requested_amount: u64, nft_worth: u64) -> Result<u32, ProgramError> {
+    if nft_worth == 0 {
+        return Err(ErrorCode::InvalidLoanState.into());
+    }
+    let ltv = (requested_amount as u128 * 1000).div_ceil(nft_worth as u128);
+    u32::try_from(ltv).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// verified collection of an nft from its metaplex metadata, `None` when the metadata has no
+/// verified collection or doesn't exist, token-2022 nfts can keep theirs in the mint instead
+pub fn verified_collection(metadata: &AccountInfo) -> anchor_lang::Result<Option<Pubkey>> {
+    if metadata.data_is_empty() {
+        return Ok(None);
+    }
+    let metadata: Account<MetadataAccount> = Account::try_from(metadata)?;
+    Ok(metadata
+        .collection
+        .as_ref()
+        .filter(|collection| collection.verified)
+        .map(|collection| collection.key))
+}
+
+/// pricing tier covering a loan request. The collection's pricing table is preferred over
+/// the global one, `None` when neither has tiers and the flat PlatformFees terms apply.
+/// The collection's table has to be passed whenever the collection is known
+pub fn load_pricing_tier(
+    global_pricing_table: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+    program_id: &Pubkey,
+    collection: Option<Pubkey>,
+    ltv: u32,
+    slot_duration: u64,
+) -> anchor_lang::Result<Option<PricingTier>> {
+    if let Some(collection) = collection {
+        let (pricing_table, _) = Pubkey::find_program_address(
+            &[PRICING_TABLE_STR.as_bytes(), collection.as_ref()],
+            program_id,
+        );
+        let info = find_remaining_account(remaining_accounts, &pricing_table)
+            .ok_or(ErrorCode::MissingPricingTable)?;
+        if let Some(tier) = pricing_table_tier(info, ltv, slot_duration)? {
+            return Ok(Some(tier));
+        }
+    }
+    pricing_table_tier(global_pricing_table, ltv, slot_duration)
+}
+
+// a table that doesn't exist or has no tiers leaves the pricing to the next one
+fn pricing_table_tier(
+    info: &AccountInfo,
+    ltv: u32,
+    slot_duration: u64,
+) -> anchor_lang::Result<Option<PricingTier>> {
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    let pricing_table: Account<PricingTable> = Account::try_from(info)?;
+    if pricing_table.tiers.is_empty() {
+        return Ok(None);
+    }
+    pricing_table
+        .tier_for(ltv, slot_duration)
+        .map(Some)
+        .ok_or(ErrorCode::NoPricingTier.into())
+}
+
+/// the collection's config, `None` if the collection has not been registered.
+/// The config account has to be passed whenever the collection is known
+pub fn load_collection_config<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+    collection: &Pubkey,
+) -> anchor_lang::Result<Option<Account<'info, CollectionConfig>>> {
+    let (collection_config, _) = Pubkey::find_program_address(
+        &[COLLECTION_CONFIG_STR.as_bytes(), collection.as_ref()],
+        program_id,
+    );
+    let info = find_remaining_account(remaining_accounts, &collection_config)
+        .ok_or(ErrorCode::MissingCollectionConfig)?;
+    if info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Account::try_from(info)?))
+}
+
+/// counts a granted loan against its collection's exposure cap
+pub fn record_collection_exposure(
+    remaining_accounts: &[AccountInfo],
+    program_id: &Pubkey,
+    collection: &Pubkey,
+    loan: &mut Loan,
+) -> anchor_lang::Result<()> {
+    if *collection == Pubkey::default() {
+        return Ok(());
+    }
+    let mut collection_config = load_collection_config(remaining_accounts, program_id, collection)?
+        .ok_or(ErrorCode::MissingCollectionConfig)?;
+    require!(collection_config.allowed, ErrorCode::CollectionNotAllowed);
+    collection_config.add_exposure(loan.requested_amount)?;
+    collection_config.exit(program_id)?;
+    loan.collection_exposure = loan.requested_amount;
+    Ok(())
+}
+
+/// creates the `[INTEREST_FEE_STR, loan]` LoanFee PDA only once there is an interest fee to book,
+/// paid by `payer`, lamports already sent to the address count towards its rent
+pub fn create_interest_fee<'info>(
+    interest_fee: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    loan: &Pubkey,
+    bump: u8,
+) -> anchor_lang::Result<Account<'info, LoanFee>> {
+    use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+    use anchor_lang::prelude::{CpiContext, Rent};
+
+    let space = 8 + LoanFee::MAX_SIZE;
+    let rent = Rent::get()?.minimum_balance(space);
+    let seeds = [INTEREST_FEE_STR.as_bytes(), loan.as_ref(), &[bump]];
+    let signer = [&seeds[..]];
+    if interest_fee.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: interest_fee.clone(),
+                },
+                &signer,
+            ),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(interest_fee.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    Transfer {
+                        from: payer.clone(),
+                        to: interest_fee.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Allocate {
+                    account_to_allocate: interest_fee.clone(),
+                },
+                &signer,
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Assign {
+                    account_to_assign: interest_fee.clone(),
+                },
+                &signer,
+            ),
+            &crate::ID,
+        )?;
+    }
+    Account::try_from_unchecked(interest_fee)
+}
+
+/// multisig fee escrow of `mint`: a system owned PDA for SOL, the multisig's token account otherwise
+pub fn fee_escrow_address(
+    multisig: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    program_id: &Pubkey,
+) -> Pubkey {
+    if *mint == Pubkey::default() {
+        Pubkey::find_program_address(
+            &[multisig.as_ref(), token_program.as_ref(), mint.as_ref()],
+            program_id,
+        )
+        .0
+    } else {
+        get_associated_token_address_with_program_id(multisig, mint, token_program)
+    }
+}
+
+/// recipient the insurance share of fees is credited to in every mint's FeeTreasury
+pub fn insurance_fund_address() -> Pubkey {
+    Pubkey::find_program_address(&[INSURANCE_FUND_STR.as_bytes()], &crate::ID).0
+}
+
+/// `shares` with the referrers' cut carved out of them, each recipient appears once
+pub fn referral_shares(shares: Vec<(Pubkey, u16)>, referrals: &[Referral]) -> Vec<(Pubkey, u16)> {
+    let referral_bps: u16 = referrals.iter().map(|referral| referral.bps).sum();
+    if referral_bps == 0 {
+        return shares;
+    }
+    let platform_bps = Multisig::TOTAL_BPS - referral_bps;
+    let mut shares: Vec<(Pubkey, u16)> = shares
+        .into_iter()
+        .map(|(owner, bps)| {
+            let scaled = bps as u32 * platform_bps as u32 / Multisig::TOTAL_BPS as u32;
+            (owner, scaled as u16)
+        })
+        .collect();
+    // the rounding remainder stays with the first recipient
+    let scaled_bps: u16 = shares.iter().map(|(_, bps)| *bps).sum();
+    if let Some((_, bps)) = shares.first_mut() {
+        *bps += platform_bps - scaled_bps;
+    }
+    for referral in referrals {
+        match shares.iter_mut().find(|(owner, _)| *owner == referral.referrer) {
+            Some((_, bps)) => *bps += referral.bps,
+            None => shares.push((referral.referrer, referral.bps)),
+        }
+    }
+    shares.retain(|(_, bps)| *bps > 0);
+    shares
+}
+
+/// public keys whose signature over `message` an Ed25519 program instruction carries, the
+/// precompile itself checked the signatures when the transaction was processed
+pub fn ed25519_signers(instruction: &Instruction, message: &[u8]) -> Result<Vec<Pubkey>, ProgramError> {
+    if instruction.program_id != ed25519_program::ID {
+        return Ok(vec![]);
+    }
+    let data = &instruction.data;
+    let read_u16 = |at: usize| -> Result<u16, ProgramError> {
+        data.get(at..at + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or_else(|| ErrorCode::InvalidSignatures.into())
+    };
+    let count = *data.first().ok_or(ErrorCode::InvalidSignatures)? as usize;
+    let mut signers = vec![];
+    for i in 0..count {
+        // signature, public key and message offsets, each with the index of the instruction
+        // holding it, u16::MAX for this one
+        let offsets = 2 + i * 14;
+        let indexes = [read_u16(offsets + 2)?, read_u16(offsets + 6)?, read_u16(offsets + 12)?];
+        if indexes.iter().any(|index| *index != u16::MAX) {
+            return Err(ErrorCode::InvalidSignatures.into());
+        }
+        let public_key = read_u16(offsets + 4)? as usize;
+        let message_offset = read_u16(offsets + 8)? as usize;
+        let message_size = read_u16(offsets + 10)? as usize;
+        let signed = data
+            .get(message_offset..message_offset + message_size)
+            .ok_or(ErrorCode::InvalidSignatures)?;
+        if signed != message {
+            continue;
+        }
+        let public_key = data
+            .get(public_key..public_key + 32)
+            .ok_or(ErrorCode::InvalidSignatures)?;
+        signers.push(Pubkey::try_from(public_key).map_err(|_| ErrorCode::InvalidSignatures)?);
+    }
+    Ok(signers)
+}
+
 pub fn calculate_fees(amount: u64, fee_percentage: u32) -> Result<Decimal, ProgramError> {
     let actual_rate = Decimal::from_percent_3dp(fee_percentage);
     actual_rate.try_mul(amount)
@@ -72,6 +471,103 @@ pub fn max_amount_allowed(nft_worth: u64, ltv: u32) -> Result<Decimal, ProgramEr
     Decimal::from(nft_worth).try_mul(actual_rate)
 }
 
+/// decimals of an spl-token or token-2022 mint
+pub fn mint_decimals(mint: &AccountInfo) -> Result<u8, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(mint.base.decimals)
+}
+
+/// amount withheld by the mint's `TransferFeeConfig` (token-2022) when `amount` is sent
+/// in the current epoch. Legacy spl-token mints and mints without the extension charge nothing.
+///
+/// every other token-2022 extension we support (e.g. interest bearing) only changes the
+/// ui amount, loans are always accounted for in raw token amounts
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(fee)
+}
+
+/// amount that has to be sent so that the receiving account ends up with exactly `net_amount`
+/// after the mint's transfer fee has been withheld
+pub fn gross_transfer_amount(mint: &AccountInfo, net_amount: u64) -> Result<u64, ProgramError> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(net_amount.checked_add(fee).ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// pool utilization (3dp percentage) = borrowed / (borrowed + available)
+pub fn pool_utilization(total_borrowed: u64, available_liquidity: u64) -> Result<u32, ProgramError> {
+    let total = total_borrowed as u128 + available_liquidity as u128;
+    if total == 0 {
+        return Ok(0);
+    }
+    Ok((total_borrowed as u128 * 1000 / total) as u32)
+}
+
+/// kinked utilization curve, rates are in the same 3dp percentage as `PlatformFees.interest_rate`
+///
+/// below `optimal_utilization` the rate climbs from `base_rate` by `slope1`,
+/// above it the remaining utilization adds `slope2` on top
+pub fn pool_interest_rate(
+    utilization: u32,
+    base_rate: u32,
+    optimal_utilization: u32,
+    slope1: u32,
+    slope2: u32,
+) -> Result<u32, ProgramError> {
+    let utilization = std::cmp::min(utilization, 1000) as u64;
+    let optimal = optimal_utilization as u64;
+
+    let rate = if utilization <= optimal {
+        base_rate as u64 + slope1 as u64 * utilization / optimal
+    } else {
+        base_rate as u64
+            + slope1 as u64
+            + slope2 as u64 * (utilization - optimal) / (1000 - optimal)
+    };
+    Ok(u32::try_from(rate).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
+/// shares minted for `amount` deposited into a pool worth `total_value`
+pub fn shares_for_deposit(
+    amount: u64,
+    total_shares: u64,
+    total_value: u64,
+) -> Result<u64, ProgramError> {
+    if total_shares == 0 || total_value == 0 {
+        return Ok(amount);
+    }
+    let shares = amount as u128 * total_shares as u128 / total_value as u128;
+    Ok(u64::try_from(shares).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
+/// tokens redeemed for burning `shares` of a pool worth `total_value`
+pub fn amount_for_shares(
+    shares: u64,
+    total_shares: u64,
+    total_value: u64,
+) -> Result<u64, ProgramError> {
+    if total_shares == 0 {
+        return Ok(0);
+    }
+    let amount = shares as u128 * total_value as u128 / total_shares as u128;
+    Ok(u64::try_from(amount).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
 pub fn complete_loan(ctx: Context<RepayLoansContext>) -> Result<(), ProgramError> {
     let loan_token_escrow_bump = ctx.bumps.get(PLATFORM_FEES_SEED_STR);
     let bump = &[*loan_token_escrow_bump.unwrap()][..];
@@ -79,13 +575,14 @@ pub fn complete_loan(ctx: Context<RepayLoansContext>) -> Result<(), ProgramError
     let outer = vec![inner.as_slice()];
 
     //burn borrow nft
-    anchor_spl::token::burn(ctx.accounts.burn_borrow_token_context(), 1)?;
+    anchor_spl::token_interface::burn(ctx.accounts.burn_borrow_token_context(), 1)?;
     // send back original nft
-    anchor_spl::token::transfer(
+    anchor_spl::token_interface::transfer_checked(
         ctx.accounts
             .transfer_nft_from_escrow_context()
             .with_signer(outer.as_slice()),
         1,
+        ctx.accounts.nft_mint.decimals,
     )?;
 
     // change status
@@ -129,9 +626,915 @@ mod test {
     }
 }
 
+#[test]
+fn test_pool_interest_rate() {
+    // 2% base, kink at 80%, +8% up to the kink, +100% above it
+    let rate = |utilization| pool_interest_rate(utilization, 20, 800, 80, 1000).unwrap();
+    assert_eq!(rate(0), 20);
+    assert_eq!(rate(400), 60);
+    assert_eq!(rate(800), 100);
+    assert_eq!(rate(900), 600);
+    assert_eq!(rate(1000), 1100);
+
+    assert_eq!(pool_utilization(0, 0).unwrap(), 0);
+    assert_eq!(pool_utilization(800, 200).unwrap(), 800);
+
+    let shares = shares_for_deposit(1000, 2000, 4000).unwrap();
+    assert_eq!(shares, 500);
+    assert_eq!(amount_for_shares(shares, 2500, 5000).unwrap(), 1000);
+}
+
+#[test]
+fn test_pool_value() {
+    use crate::state::{LendingPool, PoolConfig};
+
+    let mut pool = LendingPool {
+        collection: Pubkey::default(),
+        currency_mint: Pubkey::default(),
+        share_mint: Pubkey::default(),
+        vault: Pubkey::default(),
+        available_liquidity: 0,
+        total_borrowed: 0,
+        nft_valuation: 0,
+        ltv: 0,
+        max_slot_duration: 0,
+        base_rate: 0,
+        optimal_utilization: 0,
+        slope1: 0,
+        slope2: 0,
+        bump: 0,
+        rate_weighted_principal: 0,
+        rate_weighted_start: 0,
+        pending_sales: 0,
+    };
+    let config = PoolConfig {
+        nft_valuation: 10_000,
+        ltv: 500,
+        max_slot_duration: SLOTS_PER_YEAR,
+        base_rate: 20,
+        optimal_utilization: 800,
+        slope1: 80,
+        slope2: 1000,
+    };
+    pool.init(Pubkey::default(), Pubkey::default(), Pubkey::default(), Pubkey::default(), 0, config)
+        .unwrap();
+    pool.available_liquidity = 6_000;
+
+    // 10% on 4_000 for half a year, then 20% on 2_000 for a quarter
+    pool.add_loan(4_000, 100, 0).unwrap();
+    pool.add_loan(2_000, 200, SLOTS_PER_YEAR / 2).unwrap();
+    assert_eq!(pool.total_value(SLOTS_PER_YEAR / 2).unwrap(), 12_200);
+    let slot = SLOTS_PER_YEAR * 3 / 4;
+    assert_eq!(pool.accrued_interest(slot).unwrap(), 400);
+    assert_eq!(pool.total_value(slot).unwrap(), 12_400);
+
+    // a settled loan takes its accrued interest off the books along with its principal
+    pool.remove_loan(4_000, 100, 0).unwrap();
+    assert_eq!(pool.total_borrowed, 2_000);
+    assert_eq!(pool.accrued_interest(slot).unwrap(), 100);
+}
+
+#[test]
+fn test_pricing_tiers() {
+    assert_eq!(loan_to_value(4000, 10000).unwrap(), 400);
+    assert_eq!(loan_to_value(4001, 10000).unwrap(), 401);
+    assert!(loan_to_value(1, 0).is_err());
+
+    let tier = |max_ltv, max_slot_duration, interest_rate| PricingTier {
+        max_ltv,
+        max_slot_duration,
+        interest_rate,
+        fee_percentage: 10,
+    };
+    let table = PricingTable {
+        collection: Pubkey::default(),
+        tiers: vec![tier(300, 1_000, 50), tier(600, 10_000, 100), tier(600, u64::MAX, 150)],
+        bump: 0,
+    };
+    assert_eq!(table.tier_for(250, 500).unwrap().interest_rate, 50);
+    assert_eq!(table.tier_for(250, 5_000).unwrap().interest_rate, 100);
+    assert_eq!(table.tier_for(400, u64::MAX).unwrap().interest_rate, 150);
+    assert!(table.tier_for(700, 500).is_none());
+}
+
+#[test]
+fn test_collection_exposure() {
+    use crate::state::CollectionParams;
+
+    let params = CollectionParams {
+        allowed: true,
+        max_ltv: 500,
+        max_slot_duration: u64::MAX,
+        min_interest_rate: 50,
+        max_interest_rate: 200,
+        exposure_cap: 10_000,
+    };
+    let mut collection_config = CollectionConfig {
+        collection: Pubkey::default(),
+        allowed: false,
+        max_ltv: 0,
+        max_slot_duration: 0,
+        min_interest_rate: 0,
+        max_interest_rate: 0,
+        exposure_cap: 0,
+        exposure: 0,
+        bump: 0,
+    };
+    collection_config.set_params(params).unwrap();
+    collection_config.add_exposure(6_000).unwrap();
+    assert!(collection_config.add_exposure(5_000).is_err());
+    collection_config.add_exposure(4_000).unwrap();
+    assert_eq!(collection_config.exposure, 10_000);
+
+    assert!(collection_config
+        .set_params(CollectionParams {
+            min_interest_rate: 300,
+            ..params
+        })
+        .is_err());
+}
+
+#[test]
+fn test_currency_limits() {
+    use crate::state::{CurrencyConfig, CurrencyParams};
+
+    let params = CurrencyParams {
+        enabled: true,
+        min_loan_amount: 100,
+        max_loan_amount: 5_000,
+        debt_ceiling: 8_000,
+        fee_override: None,
+        insurance_cap: 0,
+    };
+    let mut currency_config = CurrencyConfig {
+        mint: Pubkey::default(),
+        enabled: false,
+        min_loan_amount: 0,
+        max_loan_amount: 0,
+        debt_ceiling: 0,
+        total_debt: 0,
+        fee_override: None,
+        insurance_cap: 0,
+        bump: 0,
+    };
+    assert!(currency_config.check_loan(1_000).is_err());
+
+    currency_config.set_params(params).unwrap();
+    assert!(currency_config.check_loan(50).is_err());
+    assert!(currency_config.check_loan(6_000).is_err());
+    currency_config.add_debt(5_000).unwrap();
+    assert!(currency_config.add_debt(4_000).is_err());
+    currency_config.add_debt(3_000).unwrap();
+    assert_eq!(currency_config.total_debt, 8_000);
+
+    assert!(currency_config
+        .set_params(CurrencyParams {
+            fee_override: Some(1000),
+            ..params
+        })
+        .is_err());
+}
+
 #[test]
 fn test_fee(){
     let fee = calculate_fees(10000, 50).unwrap().try_round_u64().unwrap();
     assert!(fee == 500);
 }
 
+
+#[test]
+fn test_floating_interest() {
+    let borrow_amount = 1_000_000;
+    let single = vec![RateSegment {
+        start_slot: 100,
+        rate: 50,
+    }];
+    // a flat benchmark accrues like a fixed loan at benchmark + spread
+    assert!(
+        floating_interest(borrow_amount, &single, 10, 200, 1200).unwrap()
+            == compound_interest(borrow_amount, 60, 1000)
+                .unwrap()
+                .try_sub(Decimal::from(borrow_amount))
+                .unwrap()
+    );
+
+    let history = vec![
+        RateSegment {
+            start_slot: 100,
+            rate: 50,
+        },
+        RateSegment {
+            start_slot: 700,
+            rate: 150,
+        },
+    ];
+    let low = compound_interest(borrow_amount, 60, 500)
+        .unwrap()
+        .try_sub(Decimal::from(borrow_amount))
+        .unwrap();
+    let high = compound_interest(borrow_amount, 160, 500)
+        .unwrap()
+        .try_sub(Decimal::from(borrow_amount))
+        .unwrap();
+    let floating = floating_interest(borrow_amount, &history, 10, 200, 1200).unwrap();
+    assert!(floating == low.try_add(high).unwrap());
+
+    // nothing accrues when nothing has elapsed
+    assert!(floating_interest(borrow_amount, &history, 10, 700, 700).unwrap() == Decimal::zero());
+}
+
+#[test]
+fn test_late_charges() {
+    use anchor_lang::AnchorDeserialize;
+
+    let mut loan = Loan::deserialize(&mut &[0u8; Loan::MAX_SIZE][..]).unwrap();
+    loan.init(
+        Pubkey::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        400,
+        10,
+        100,
+        10_000,
+        1_000_000,
+        1_000,
+        100,
+    )
+    .unwrap();
+    loan.set_late_terms(500, 200, 25);
+    assert_eq!(loan.default_slot(), 1_600);
+
+    // nothing is owed before maturity
+    assert_eq!(late_charges(&loan, 1_100).unwrap(), (0, 0));
+
+    // penalty accrues from maturity, the late fee is charged once
+    loan.last_updated_slot = 1_000;
+    let penalty = compound_interest(1_000_000, 200, 300)
+        .unwrap()
+        .try_sub(Decimal::from(1_000_000u64))
+        .unwrap()
+        .try_round_u64()
+        .unwrap();
+    assert_eq!(late_charges(&loan, 1_400).unwrap(), (penalty, 25));
+    loan.add_late_charges(penalty, 25);
+    loan.last_updated_slot = 1_400;
+    let (_, late_fee) = late_charges(&loan, 1_500).unwrap();
+    assert_eq!(late_fee, 0);
+
+    // penalty stops at the end of the grace period
+    let (rest, _) = late_charges(&loan, 5_000).unwrap();
+    assert_eq!(rest, late_charges(&loan, 1_600).unwrap().0);
+    assert_eq!(loan.late_fee_charged, 25);
+    assert_eq!(loan.outstanding_debt, 1_000_000 + penalty + 25);
+}
+
+#[test]
+fn test_fee_shares() {
+    use crate::state::{LoanFee, Multisig};
+
+    let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let treasury = Pubkey::new_unique();
+    let mut multisig = Multisig {
+        threshold: 2,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners: owners.clone(),
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    // equal split, the rounding remainder goes to the first owner
+    let shares = multisig.fee_shares();
+    assert_eq!(shares[0], (owners[0], 3_334));
+    assert_eq!(shares[2], (owners[2], 3_333));
+
+    assert!(multisig
+        .set_fee_shares(vec![5_000, 2_000], treasury, 3_000, 0)
+        .is_err());
+    assert!(multisig
+        .set_fee_shares(vec![5_000, 2_000, 2_000], treasury, 2_000, 0)
+        .is_err());
+    multisig
+        .set_fee_shares(vec![5_000, 0, 2_000], treasury, 3_000, 0)
+        .unwrap();
+
+    let mut loan_fee = LoanFee {
+        amount: 0,
+        token_mint: Pubkey::default(),
+        loan: Pubkey::default(),
+        escrow: Pubkey::default(),
+        owners: vec![],
+        shares: vec![],
+    };
+    loan_fee.init(
+        1_000,
+        Pubkey::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        multisig.fee_shares(),
+    );
+    // zero weight owners are left out of the snapshot
+    assert_eq!(loan_fee.owners, vec![owners[0], owners[2], treasury]);
+
+    // a removed owner's fee share has to be reassigned first
+    assert!(multisig.update_owners(vec![owners[1]]).is_err());
+    multisig
+        .set_fee_shares(vec![0, 7_000, 0], treasury, 3_000, 0)
+        .unwrap();
+    // a later owner change doesn't touch the snapshot
+    multisig.update_owners(vec![owners[1]]).unwrap();
+    assert_eq!(loan_fee.take_share(&owners[0], 1_000), Some(500));
+    assert_eq!(loan_fee.take_share(&owners[0], 1_000), None);
+    assert_eq!(loan_fee.take_share(&owners[1], 1_000), None);
+    assert_eq!(loan_fee.take_share(&treasury, 1_000), Some(300));
+    assert_eq!(multisig.fee_shares(), vec![(owners[1], 7_000), (treasury, 3_000)]);
+
+    // the insurance fund takes its share ahead of an equal split
+    multisig.set_fee_shares(vec![], treasury, 0, 500).unwrap();
+    assert_eq!(
+        multisig.fee_shares(),
+        vec![(owners[1], 9_500), (insurance_fund_address(), 500)]
+    );
+    assert!(multisig.set_fee_shares(vec![], treasury, 9_600, 500).is_err());
+}
+
+#[test]
+fn test_state_migration() {
+    use crate::instructions::migration::MultisigV0;
+    use crate::state::Multisig;
+    use anchor_lang::{AccountSerialize, AnchorDeserialize};
+
+    let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let multisig = Multisig {
+        threshold: 2,
+        seqno: 4,
+        owners: owners.clone(),
+        proposal_count: 7,
+        execution_delay: 10,
+        max_proposal_slots: 0,
+        vote_weights: vec![2, 1, 1],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    // later fields are appended, the first layout still reads the same values
+    let mut data = vec![];
+    multisig.try_serialize(&mut data).unwrap();
+    let legacy = MultisigV0::deserialize(&mut &data[8..]).unwrap();
+    assert_eq!((legacy.threshold, legacy.seqno), (2, 4));
+    assert_eq!(legacy.owners, owners);
+}
+
+#[test]
+fn test_fee_treasury() {
+    use crate::state::FeeTreasury;
+
+    let owner = Pubkey::new_unique();
+    let treasury = Pubkey::new_unique();
+    let mut fee_treasury = FeeTreasury {
+        mint: Pubkey::default(),
+        vault: Pubkey::default(),
+        accruals: vec![],
+        total_swept: 0,
+        total_claimed: 0,
+        bump: 0,
+    };
+    fee_treasury.accrue(owner, 700).unwrap();
+    fee_treasury.accrue(treasury, 300).unwrap();
+    fee_treasury.accrue(owner, 70).unwrap();
+    // nothing to record for a zero share
+    fee_treasury.accrue(Pubkey::new_unique(), 0).unwrap();
+    assert_eq!(fee_treasury.accruals.len(), 2);
+    assert_eq!(fee_treasury.total_swept, 1_070);
+
+    assert_eq!(fee_treasury.claim(&owner).unwrap(), 770);
+    assert!(fee_treasury.claim(&owner).is_err());
+    assert_eq!(fee_treasury.total_claimed, 770);
+
+    for _ in 1..FeeTreasury::MAX_ACCRUALS {
+        fee_treasury.accrue(Pubkey::new_unique(), 1).unwrap();
+    }
+    assert!(fee_treasury.accrue(Pubkey::new_unique(), 1).is_err());
+    fee_treasury.accrue(treasury, 1).unwrap();
+}
+
+#[test]
+fn test_insurance_payout() {
+    use crate::state::{ClaimReason, InsuranceClaim};
+
+    assert_eq!(
+        InsuranceClaim::payout_for(400, 500, 1_000),
+        (400, ClaimReason::ShortfallCovered)
+    );
+    assert_eq!(
+        InsuranceClaim::payout_for(800, 500, 1_000),
+        (500, ClaimReason::CappedPerLoan)
+    );
+    assert_eq!(
+        InsuranceClaim::payout_for(800, 500, 300),
+        (300, ClaimReason::FundDepleted)
+    );
+    assert_eq!(
+        InsuranceClaim::payout_for(0, 500, 300),
+        (0, ClaimReason::ShortfallCovered)
+    );
+}
+
+#[test]
+fn test_referral_shares() {
+    use crate::state::{Multisig, Referral};
+
+    let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let multisig = Multisig {
+        threshold: 2,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners: owners.clone(),
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    assert_eq!(referral_shares(multisig.fee_shares(), &[]), multisig.fee_shares());
+
+    let frontend = Pubkey::new_unique();
+    let shares = referral_shares(
+        multisig.fee_shares(),
+        &[
+            Referral { referrer: frontend, bps: 500 },
+            Referral { referrer: owners[1], bps: 1_000 },
+        ],
+    );
+    // 8_500 bps left to the owners, an owner acting as referrer keeps a single entry
+    assert_eq!(shares.len(), 4);
+    assert_eq!(shares[0], (owners[0], 2_834));
+    assert_eq!(shares[1], (owners[1], 2_833 + 1_000));
+    assert_eq!(shares[2], (owners[2], 2_833));
+    assert_eq!(shares[3], (frontend, 500));
+    assert_eq!(
+        shares.iter().map(|(_, bps)| *bps as u32).sum::<u32>(),
+        Multisig::TOTAL_BPS as u32
+    );
+}
+
+#[test]
+fn test_transaction_space() {
+    use crate::state::{Transaction, TransactionAccount, TransactionInstruction};
+    use anchor_lang::AnchorSerialize;
+
+    let instruction = |accounts: usize, data: usize| TransactionInstruction {
+        program_id: Pubkey::new_unique(),
+        accounts: (0..accounts)
+            .map(|_| TransactionAccount {
+                pubkey: Pubkey::new_unique(),
+                is_signer: false,
+                is_writable: true,
+            })
+            .collect(),
+        data: vec![1; data],
+    };
+    let instructions = vec![instruction(2, 20), instruction(4, 12)];
+    let tx = Transaction {
+        proposer: Pubkey::new_unique(),
+        multisig: Pubkey::new_unique(),
+        did_execute: false,
+        seqno: 0,
+        index: 0,
+        signers: vec![false; 100],
+        rejections: vec![false; 100],
+        failed: false,
+        approved_slot: Some(0),
+        expiry_slot: Some(0),
+        instructions: instructions.clone(),
+        proposal: None,
+        vote_nonce: 0,
+        vetoes: vec![false; 100],
+    };
+    assert_eq!(tx.try_to_vec().unwrap().len(), Transaction::space(&instructions));
+    assert_eq!(
+        Transaction::space(&[instruction(20, 1000)]),
+        Transaction::MAX_SIZE
+    );
+}
+
+#[test]
+fn test_transaction_votes() {
+    use crate::state::{Multisig, Transaction};
+
+    let owners: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let multisig = Multisig {
+        threshold: 3,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners,
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    let mut tx = Transaction {
+        proposer: Pubkey::new_unique(),
+        multisig: Pubkey::new_unique(),
+        did_execute: false,
+        seqno: 0,
+        index: 0,
+        signers: vec![false; 4],
+        rejections: vec![false; 4],
+        failed: false,
+        approved_slot: None,
+        expiry_slot: Some(20),
+        instructions: vec![],
+        proposal: None,
+        vote_nonce: 0,
+        vetoes: vec![false; 4],
+    };
+    assert!(!tx.is_expired(20) && tx.is_expired(21));
+    // 3 of 4
+    for (owner_index, slot) in [(0, 10), (1, 11), (2, 12)] {
+        tx.vote(owner_index, true);
+        multisig.tally(&mut tx, slot);
+    }
+    assert_eq!(tx.approved_slot, Some(12));
+
+    // dropping below the threshold restarts the execution delay
+    tx.revoke(2);
+    multisig.tally(&mut tx, 13);
+    assert_eq!(tx.approved_slot, None);
+    tx.vote(1, false);
+    multisig.tally(&mut tx, 14);
+    assert_eq!(tx.rejection_count(), 1);
+    assert!(!tx.failed);
+
+    // 2 rejections out of 4 owners leave the threshold out of reach
+    tx.vote(3, false);
+    multisig.tally(&mut tx, 15);
+    assert!(tx.failed);
+}
+
+#[test]
+fn test_roles() {
+    use crate::state::{
+        Multisig, Role, RoleConfig, Transaction, TransactionInstruction,
+    };
+    use anchor_lang::{Discriminator, InstructionData};
+
+    let owners: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let mut multisig = Multisig {
+        threshold: 3,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners: owners.clone(),
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    let fee_admin = RoleConfig {
+        role: Role::FeeAdmin,
+        members: vec![owners[0], owners[1]],
+        threshold: 1,
+    };
+    assert!(multisig
+        .set_roles(vec![RoleConfig { members: vec![Pubkey::new_unique()], ..fee_admin.clone() }])
+        .is_err());
+    assert!(multisig
+        .set_roles(vec![RoleConfig { threshold: 3, ..fee_admin.clone() }])
+        .is_err());
+    multisig.set_roles(vec![fee_admin]).unwrap();
+
+    let instruction = |data: Vec<u8>| TransactionInstruction {
+        program_id: crate::ID,
+        accounts: vec![],
+        data,
+    };
+    let set_fee_model = instruction(
+        crate::instruction::SetFeeModel {
+            origination_fee: 10,
+            interest_fee: 0,
+        }
+        .data(),
+    );
+    let set_benchmark_rate = instruction(crate::instruction::SetBenchmarkRate { rate: 10 }.data());
+    assert_eq!(Role::of(&set_fee_model), Some(Role::FeeAdmin));
+    assert_eq!(Role::of(&set_benchmark_rate), None);
+    assert_eq!(
+        Role::of(&instruction(crate::instruction::SetRoles::DISCRIMINATOR.to_vec())),
+        Some(Role::OwnerManager)
+    );
+
+    // fee admins alone, on their own threshold
+    assert!(multisig.can_propose(&owners[1], std::slice::from_ref(&set_fee_model)));
+    assert!(!multisig.can_propose(&owners[2], std::slice::from_ref(&set_fee_model)));
+    let mut tx = Transaction {
+        proposer: owners[0],
+        multisig: Pubkey::new_unique(),
+        did_execute: false,
+        seqno: 0,
+        index: 0,
+        signers: vec![true, false, false, false],
+        rejections: vec![false; 4],
+        failed: false,
+        approved_slot: None,
+        expiry_slot: None,
+        instructions: vec![set_fee_model],
+        proposal: None,
+        vote_nonce: 0,
+        vetoes: vec![false; 4],
+    };
+    assert!(multisig.is_approved(&tx));
+
+    // a batch with an unroled instruction also needs the owners' threshold
+    tx.instructions.push(set_benchmark_rate);
+    assert!(!multisig.is_approved(&tx));
+    tx.signers = vec![true, false, true, true];
+    assert!(multisig.is_approved(&tx));
+
+    // both fee admins against, the role's threshold is out of reach
+    tx.rejections = vec![false, true, false, false];
+    multisig.tally(&mut tx, 0);
+    assert!(!tx.failed);
+    tx.signers[0] = false;
+    tx.rejections[0] = true;
+    multisig.tally(&mut tx, 0);
+    assert!(tx.failed);
+
+    // removed owners leave their roles
+    multisig.update_owners(vec![owners[1], owners[2], owners[3]]).unwrap();
+    assert_eq!(multisig.roles[0].members, vec![owners[1]]);
+    multisig.update_owners(vec![owners[2], owners[3]]).unwrap();
+    assert!(multisig.roles.is_empty());
+}
+
+#[test]
+fn test_vote_weights() {
+    use crate::state::{Multisig, Role, RoleConfig, Transaction};
+
+    let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut multisig = Multisig {
+        threshold: 2,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners: owners.clone(),
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    assert!(multisig.set_vote_weights(vec![5, 3], 6).is_err());
+    assert!(multisig.set_vote_weights(vec![5, 0, 2], 6).is_err());
+    assert!(multisig.set_vote_weights(vec![5, 3, 2], 11).is_err());
+    multisig.set_vote_weights(vec![5, 3, 2], 6).unwrap();
+    assert_eq!(multisig.seqno, 1);
+    multisig
+        .set_roles(vec![RoleConfig {
+            role: Role::FeeAdmin,
+            members: vec![owners[0], owners[1]],
+            threshold: 8,
+        }])
+        .unwrap();
+
+    let mut tx = Transaction {
+        proposer: owners[0],
+        multisig: Pubkey::new_unique(),
+        did_execute: false,
+        seqno: 1,
+        index: 0,
+        signers: vec![true, false, false],
+        rejections: vec![false; 3],
+        failed: false,
+        approved_slot: None,
+        expiry_slot: None,
+        instructions: vec![],
+        proposal: None,
+        vote_nonce: 0,
+        vetoes: vec![false; 3],
+    };
+    // 5 of 6
+    multisig.tally(&mut tx, 0);
+    assert_eq!(tx.approved_slot, None);
+    tx.vote(2, true);
+    multisig.tally(&mut tx, 1);
+    assert_eq!(tx.approved_slot, Some(1));
+
+    // withdrawing an approval voids the approvals signed off-chain before it
+    let message = tx.approval_message(&tx.multisig, 10);
+    tx.revoke(2);
+    assert_ne!(tx.approval_message(&tx.multisig, 10), message);
+
+    // vetoes are weighed like votes, the two lightest owners fall short of the threshold
+    tx.vetoes = vec![false, true, true];
+    assert_eq!(multisig.vetoed_weight(&tx), 5);
+    tx.vetoes[0] = true;
+    assert!(multisig.vetoed_weight(&tx) >= multisig.threshold);
+
+    // the heaviest owner against leaves a weight of 5, short of 6
+    let mut tx = Transaction { signers: vec![false; 3], approved_slot: None, ..tx };
+    tx.vote(0, false);
+    multisig.tally(&mut tx, 2);
+    assert_eq!(multisig.rejected_weight(&tx), 5);
+    assert!(tx.failed);
+
+    // retained owners keep their weight, a new owner needs one set explicitly
+    let newcomer = Pubkey::new_unique();
+    assert!(multisig.update_owners(vec![owners[0], newcomer]).is_err());
+    multisig.update_owners(vec![owners[1], owners[2]]).unwrap();
+    assert_eq!(multisig.vote_weights, vec![3, 2]);
+    // thresholds stay reachable
+    assert_eq!(multisig.threshold, 5);
+    assert_eq!(multisig.roles[0].threshold, 3);
+    multisig
+        .update_owners_and_vote_weights(vec![owners[1], newcomer], vec![], 2)
+        .unwrap();
+    assert!(multisig.vote_weights.is_empty());
+    assert_eq!(multisig.threshold, 2);
+}
+
+#[test]
+fn test_typed_proposals() {
+    use crate::state::{Multisig, Proposal, Role, Transaction};
+    use anchor_lang::{AnchorSerialize, InstructionData};
+
+    let proposal = Proposal::SetPaused { paused: true };
+    let tx = Transaction {
+        proposer: Pubkey::new_unique(),
+        multisig: Pubkey::new_unique(),
+        did_execute: false,
+        seqno: 0,
+        index: 0,
+        signers: vec![false; 100],
+        rejections: vec![false; 100],
+        failed: false,
+        approved_slot: Some(0),
+        expiry_slot: Some(0),
+        instructions: vec![],
+        proposal: Some(proposal.clone()),
+        vote_nonce: 0,
+        vetoes: vec![false; 100],
+    };
+    assert_eq!(
+        tx.try_to_vec().unwrap().len(),
+        Transaction::BASE_SIZE + proposal.size()
+    );
+
+    // built like the raw proposal an approver would have to decode
+    let instructions = tx.instructions();
+    assert_eq!(instructions.len(), 1);
+    assert_eq!(instructions[0].program_id, crate::ID);
+    assert_eq!(
+        instructions[0].data,
+        crate::instruction::SetPaused { paused: true }.data()
+    );
+    let (platform_fees, _) =
+        Pubkey::find_program_address(&[PLATFORM_FEES_SEED_STR.as_bytes()], &crate::ID);
+    let (multisig_signer, _) =
+        Pubkey::find_program_address(&[MULTISIG_SEED_STR.as_bytes()], &crate::ID);
+    assert!(instructions[0].accounts[0].pubkey == platform_fees && instructions[0].accounts[0].is_writable);
+    assert!(instructions[0].accounts[1].pubkey == multisig_signer && instructions[0].accounts[1].is_signer);
+    assert_eq!(Role::of(&instructions[0]), Some(Role::PauseGuardian));
+
+    // owner management instructions target the multisig account
+    let tx = Transaction {
+        proposal: Some(Proposal::ChangeThreshold { threshold: 2 }),
+        ..tx
+    };
+    let instruction = &tx.instructions()[0];
+    assert_eq!(instruction.accounts[0].pubkey, tx.multisig);
+    assert_eq!(Role::of(instruction), Some(Role::OwnerManager));
+    let multisig = Multisig {
+        threshold: 1,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners: vec![tx.proposer],
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![],
+        version: Multisig::VERSION,
+    };
+    assert!(multisig.can_propose(&tx.proposer, &tx.instructions()));
+}
+
+#[test]
+fn test_ed25519_signers() {
+    // one signature: offsets, then public key, signature and message
+    let ed25519_instruction = |signer: &Pubkey, message: &[u8], instruction_index: u16| {
+        let mut data = vec![1, 0];
+        for offset in [48u16, instruction_index, 16, u16::MAX, 112, message.len() as u16, u16::MAX] {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[7; 64]);
+        data.extend_from_slice(message);
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: vec![],
+            data,
+        }
+    };
+    let owner = Pubkey::new_unique();
+    let message = [3u8; 72];
+
+    let instruction = ed25519_instruction(&owner, &message, u16::MAX);
+    assert_eq!(ed25519_signers(&instruction, &message).unwrap(), vec![owner]);
+    assert!(ed25519_signers(&instruction, &[4u8; 72]).unwrap().is_empty());
+
+    // signatures kept in another instruction aren't read
+    let instruction = ed25519_instruction(&owner, &message, 0);
+    assert!(ed25519_signers(&instruction, &message).is_err());
+
+    let mut instruction = ed25519_instruction(&owner, &message, u16::MAX);
+    instruction.data.truncate(120);
+    assert!(ed25519_signers(&instruction, &message).is_err());
+    instruction.program_id = crate::ID;
+    assert!(ed25519_signers(&instruction, &message).unwrap().is_empty());
+}
+
+#[test]
+fn test_spending_limits() {
+    use crate::state::{Multisig, Role, RoleConfig, Spender, SpendingLimits};
+
+    let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let multisig = Multisig {
+        threshold: 2,
+        seqno: 0,
+        proposal_count: 0,
+        execution_delay: 0,
+        max_proposal_slots: 0,
+        owners: owners.clone(),
+        vote_weights: vec![],
+        weights: vec![],
+        treasury: Pubkey::default(),
+        treasury_bps: 0,
+        insurance_bps: 0,
+        roles: vec![RoleConfig {
+            role: Role::Treasury,
+            members: vec![owners[1], owners[2]],
+            threshold: 1,
+        }],
+        version: Multisig::VERSION,
+    };
+    let mut spending_limits = SpendingLimits {
+        mint: Pubkey::default(),
+        limits: vec![],
+        bump: 0,
+    };
+    spending_limits.set(Spender::Owner(owners[0]), 100, 10, 0).unwrap();
+    spending_limits.set(Spender::Role(Role::Treasury), 50, 0, 0).unwrap();
+
+    // per owner, renewed each period
+    assert_eq!(
+        spending_limits.spend(&multisig, &owners[0], 60, 1).unwrap(),
+        Spender::Owner(owners[0])
+    );
+    assert!(spending_limits.spend(&multisig, &owners[0], 60, 9).is_err());
+    spending_limits.spend(&multisig, &owners[0], 60, 10).unwrap();
+    assert_eq!(spending_limits.limits[0].period_start, 10);
+
+    // role members share a one-off budget
+    spending_limits.spend(&multisig, &owners[1], 30, 1).unwrap();
+    assert!(spending_limits.spend(&multisig, &owners[2], 30, 1_000).is_err());
+    assert_eq!(
+        spending_limits.spend(&multisig, &owners[2], 20, 1_000).unwrap(),
+        Spender::Role(Role::Treasury)
+    );
+
+    // non owners and removed limits can't spend
+    assert!(spending_limits.spend(&multisig, &Pubkey::new_unique(), 1, 1).is_err());
+    spending_limits.set(Spender::Owner(owners[0]), 0, 10, 20).unwrap();
+    assert!(spending_limits.spend(&multisig, &owners[0], 1, 30).is_err());
+    assert_eq!(spending_limits.limits.len(), 1);
+}
@@ -17,3 +17,20 @@ export const BORROW_NFT_MINT = "borrow_nft_mint";
 export const LOAN_TOKEN_ESCROW = "loan_token_escrow";
 export const LOAN_STR = "loan";
 export const LOAN_FEE_STR = "loan_fee";
+export const LENDING_POOL_STR = "lending_pool";
+export const POOL_SHARE_MINT_STR = "pool_share_mint";
+export const POOL_VAULT_STR = "pool_vault";
+export const SYNDICATE_STR = "syndicate";
+export const SYNDICATE_NOTE_MINT_STR = "syndicate_note_mint";
+export const SYNDICATE_VAULT_STR = "syndicate_vault";
+export const POSITION_LISTING_STR = "position_listing";
+export const POSITION_ESCROW_STR = "position_escrow";
+export const BENCHMARK_RATE_STR = "benchmark_rate";
+export const PRICING_TABLE_STR = "pricing_table";
+export const COLLECTION_CONFIG_STR = "collection_config";
+export const CURRENCY_CONFIG_STR = "currency_config";
+export const INTEREST_FEE_STR = "interest_fee";
+export const FEE_TREASURY_STR = "fee_treasury";
+export const INSURANCE_FUND_STR = "insurance_fund";
+export const INSURANCE_CLAIM_STR = "insurance_claim";
+export const SPENDING_LIMITS_STR = "spending_limits";
@@ -14,13 +14,26 @@ import {
   RawMint,
   getOrCreateAssociatedTokenAccount,
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  getMintLen,
+  createInitializeTransferFeeConfigInstruction,
 } from "@solana/spl-token";
-import { MULTISIG_TRANSACTION_SEED_STR } from "./CONSTANTS";
+import { CURRENCY_CONFIG_STR, MULTISIG_SEED_STR, MULTISIG_TRANSACTION_SEED_STR, PRICING_TABLE_STR } from "./CONSTANTS";
+import {
+  createCreateMasterEditionV3Instruction,
+  createCreateMetadataAccountV3Instruction,
+  createVerifyCollectionInstruction,
+} from "@metaplex-foundation/mpl-token-metadata";
 
 const SPL_ASSOCIATED_TOKEN_ACCOUNT_PROGRAM_ID: PublicKey = new PublicKey(
   'ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL',
 );
 
+export const TOKEN_METADATA_PROGRAM_ID: PublicKey = new PublicKey(
+  'metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s',
+);
+
 export const writePrivateKey = (key: string, name: string) => {
   fs.writeFileSync(
     `test_utils/keys/${name}.json`,
@@ -100,6 +113,10 @@ export const getPdaParamsWithSeedAndPubkey = async (
   }
 }
 
+export const getGlobalPricingTable = async (program: anchor.Program): Promise<anchor.web3.PublicKey> => {
+  return (await getPdaParamsWithSeedAndPubkey(program, PRICING_TABLE_STR, PublicKey.default)).key;
+}
+
 export const getLoanPDA = async (
   program: anchor.Program,
   seed: string,
@@ -115,13 +132,13 @@ export const getLoanPDA = async (
 
 }
 
-export const getMultisigTransactionPdaParams = async (program: anchor.Program, seqno: number): Promise<PDAParameters> => {
+export const getMultisigTransactionPdaParams = async (program: anchor.Program, index: number): Promise<PDAParameters> => {
 
-  const seqnoBn = new anchor.BN(seqno);
-  const seqnoBuffer = seqnoBn.toBuffer('le', 4);
+  const indexBn = new anchor.BN(index);
+  const indexBuffer = indexBn.toBuffer('le', 4);
 
   let [key, bump] = await anchor.web3.PublicKey.findProgramAddress(
-    [Buffer.from(MULTISIG_TRANSACTION_SEED_STR), seqnoBuffer], program.programId,
+    [Buffer.from(MULTISIG_TRANSACTION_SEED_STR), indexBuffer], program.programId,
   );
 
 
@@ -131,6 +148,183 @@ export const getMultisigTransactionPdaParams = async (program: anchor.Program, s
   }
 }
 
+// allowlists `mint` (PublicKey.default for SOL) as a loan currency through the multisig
+// proposes `data` as owner1, approves as owner2 and executes it
+export const executeMultisigInstruction = async (
+  program: anchor.Program,
+  accounts: anchor.web3.AccountMeta[],
+  data: Buffer,
+) => {
+  const owner1 = getKeypair("owner1");
+  const owner2 = getKeypair("owner2");
+  const multisig = (await getPdaParams(program, MULTISIG_SEED_STR)).key;
+
+  const multisigData: any = await program.account.multisig.fetch(multisig);
+  const transaction = (await getMultisigTransactionPdaParams(program, multisigData.proposalCount)).key;
+
+  await program.methods.createTransaction(program.programId, accounts, data, null)
+    .accounts({ multisig, transaction, proposer: owner1.publicKey })
+    .signers([owner1])
+    .rpc();
+  await program.methods.approve()
+    .accounts({ multisig, transaction, owner: owner2.publicKey })
+    .signers([owner2])
+    .rpc();
+  await program.methods.executeTransaction()
+    .accounts({ multisig, multisigSigner: multisig, transaction, proposer: owner1.publicKey })
+    .remainingAccounts(
+      accounts
+        .map(account => account.pubkey.equals(multisig) ? { ...account, isSigner: false } : account)
+        .concat({ pubkey: program.programId, isWritable: false, isSigner: false })
+    )
+    .rpc();
+}
+
+export const registerCurrency = async (
+  program: anchor.Program,
+  mint: PublicKey,
+  params: any = {
+    enabled: true,
+    minLoanAmount: new anchor.BN(0),
+    maxLoanAmount: new anchor.BN("18446744073709551615"),
+    debtCeiling: new anchor.BN("18446744073709551615"),
+    feeOverride: null,
+    insuranceCap: new anchor.BN(0),
+  },
+): Promise<PublicKey> => {
+  const payer = (program.provider as anchor.AnchorProvider).wallet.publicKey;
+  const multisig = (await getPdaParams(program, MULTISIG_SEED_STR)).key;
+  const currencyConfig = (await getPdaParamsWithSeedAndPubkey(program, CURRENCY_CONFIG_STR, mint)).key;
+
+  const accounts = [
+    { pubkey: currencyConfig, isWritable: true, isSigner: false },
+    { pubkey: multisig, isWritable: false, isSigner: true },
+    { pubkey: payer, isWritable: true, isSigner: true },
+    { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+  ];
+  const data = program.coder.instruction.encode("set_currency_config", { mint, params });
+  await executeMultisigInstruction(program, accounts, data);
+
+  return currencyConfig;
+}
+
+export const getNftMetadataAddress = async (mint: anchor.web3.PublicKey): Promise<anchor.web3.PublicKey> => {
+  const [key] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID,
+  );
+  return key;
+}
+
+/**
+ * creates the metaplex metadata of an nft, in `collection` (unverified) if one is given
+ * @param mintAuthority authority of `mint`, also made the update authority
+ */
+export const createNftMetadata = async (
+  provider: anchor.AnchorProvider,
+  mint: anchor.web3.PublicKey,
+  mintAuthority: anchor.web3.Keypair,
+  collection: anchor.web3.PublicKey | null = null,
+): Promise<anchor.web3.PublicKey> => {
+  const metadata = await getNftMetadataAddress(mint);
+
+  const tx = new anchor.web3.Transaction().add(
+    createCreateMetadataAccountV3Instruction(
+      {
+        metadata,
+        mint,
+        mintAuthority: mintAuthority.publicKey,
+        payer: mintAuthority.publicKey,
+        updateAuthority: mintAuthority.publicKey,
+      },
+      {
+        createMetadataAccountArgsV3: {
+          data: {
+            name: "Main Nft",
+            symbol: "MAIN",
+            uri: "",
+            sellerFeeBasisPoints: 0,
+            creators: null,
+            collection: collection ? { verified: false, key: collection } : null,
+            uses: null,
+          },
+          isMutable: true,
+          collectionDetails: null,
+        },
+      },
+    )
+  );
+  await provider.sendAndConfirm(tx, [mintAuthority]);
+
+  return metadata;
+}
+
+const getMasterEditionAddress = async (mint: anchor.web3.PublicKey): Promise<anchor.web3.PublicKey> => {
+  const [key] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), Buffer.from("edition")],
+    TOKEN_METADATA_PROGRAM_ID,
+  );
+  return key;
+}
+
+/**
+ * creates a collection nft (mint, metadata and master edition) held by `authority`
+ * @returns the collection mint
+ */
+export const createCollection = async (
+  provider: anchor.AnchorProvider,
+  authority: anchor.web3.Keypair,
+): Promise<anchor.web3.PublicKey> => {
+  const collection = await createMint(provider, authority);
+  const collectionAccount = await createAssociatedTokenAccount(provider, collection, authority);
+  await mintTokens(provider, 1, collection, authority, collectionAccount);
+  const metadata = await createNftMetadata(provider, collection, authority);
+
+  const tx = new anchor.web3.Transaction().add(
+    createCreateMasterEditionV3Instruction(
+      {
+        edition: await getMasterEditionAddress(collection),
+        mint: collection,
+        updateAuthority: authority.publicKey,
+        mintAuthority: authority.publicKey,
+        payer: authority.publicKey,
+        metadata,
+      },
+      { createMasterEditionArgs: { maxSupply: 0 } },
+    )
+  );
+  await provider.sendAndConfirm(tx, [authority]);
+
+  return collection;
+}
+
+/**
+ * creates the metaplex metadata of `mint`, verified in `collection`
+ * @param authority mint authority of `mint` and the collection's update authority
+ */
+export const createVerifiedCollectionNft = async (
+  provider: anchor.AnchorProvider,
+  mint: anchor.web3.PublicKey,
+  authority: anchor.web3.Keypair,
+  collection: anchor.web3.PublicKey,
+): Promise<anchor.web3.PublicKey> => {
+  const metadata = await createNftMetadata(provider, mint, authority, collection);
+
+  const tx = new anchor.web3.Transaction().add(
+    createVerifyCollectionInstruction({
+      metadata,
+      collectionAuthority: authority.publicKey,
+      payer: authority.publicKey,
+      collectionMint: collection,
+      collection: await getNftMetadataAddress(collection),
+      collectionMasterEditionAccount: await getMasterEditionAddress(collection),
+    })
+  );
+  await provider.sendAndConfirm(tx, [authority]);
+
+  return metadata;
+}
+
 export const createMint = async (
   provider: anchor.AnchorProvider,
   user: anchor.web3.Keypair,
@@ -168,16 +362,74 @@ export const createMint = async (
   return tokenMint.publicKey;
 }
 
+/**
+ * creates a token-2022 mint, optionally with the TransferFee extension
+ * @param transferFeeBasisPoints fee withheld on every transfer (0 = no extension)
+ */
+export const createToken2022Mint = async (
+  provider: anchor.AnchorProvider,
+  user: anchor.web3.Keypair,
+  decimal: number = 0,
+  transferFeeBasisPoints: number = 0,
+  maxTransferFee: bigint = BigInt(1_000_000_000),
+): Promise<anchor.web3.PublicKey> => {
+  const tokenMint = new anchor.web3.Keypair();
+  const extensions = transferFeeBasisPoints > 0 ? [ExtensionType.TransferFeeConfig] : [];
+  const mintLen = getMintLen(extensions);
+  const lamportsForMint = await provider.connection.getMinimumBalanceForRentExemption(mintLen);
+
+  let tx = new anchor.web3.Transaction();
+  tx.add(
+    anchor.web3.SystemProgram.createAccount({
+      programId: TOKEN_2022_PROGRAM_ID,
+      space: mintLen,
+      fromPubkey: user.publicKey,
+      newAccountPubkey: tokenMint.publicKey,
+      lamports: lamportsForMint,
+    })
+  )
+  if (transferFeeBasisPoints > 0) {
+    tx.add(
+      createInitializeTransferFeeConfigInstruction(
+        tokenMint.publicKey,
+        user.publicKey,
+        user.publicKey,
+        transferFeeBasisPoints,
+        maxTransferFee,
+        TOKEN_2022_PROGRAM_ID,
+      )
+    )
+  }
+  tx.add(
+    createInitializeMintInstruction(
+      tokenMint.publicKey,
+      decimal,
+      user.publicKey,
+      user.publicKey,
+      TOKEN_2022_PROGRAM_ID,
+    )
+  );
+
+  await provider.sendAndConfirm(tx, [user, tokenMint],);
+
+  return tokenMint.publicKey;
+}
+
 export const createAssociatedTokenAccount = async (
   provider: anchor.AnchorProvider,
   mint: anchor.web3.PublicKey,
   user: anchor.web3.Keypair,
+  programId: anchor.web3.PublicKey = TOKEN_PROGRAM_ID,
 ): Promise<anchor.web3.PublicKey | undefined> => {
   let ata = await getOrCreateAssociatedTokenAccount(
     provider.connection, //connection
     user, //payer
     mint, //mint
     user.publicKey, //owner
+    false,
+    undefined,
+    undefined,
+    programId,
   )
   return ata.address
 }
@@ -237,7 +489,8 @@ export const mintTokens = async (
   amount: number,
   mint: anchor.web3.PublicKey,
   user: anchor.web3.Keypair,
-  userAssociatedTokenAccount: anchor.web3.PublicKey
+  userAssociatedTokenAccount: anchor.web3.PublicKey,
+  programId: anchor.web3.PublicKey = TOKEN_PROGRAM_ID,
 ) => {
   const txFundTokenAccount = new anchor.web3.Transaction();
   txFundTokenAccount.add(createMintToInstruction(
@@ -245,6 +498,8 @@ export const mintTokens = async (
     userAssociatedTokenAccount,
     user.publicKey,
     amount,
+    [],
+    programId,
   ));
   await provider.sendAndConfirm(txFundTokenAccount, [user]);
 
@@ -0,0 +1,214 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import {
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+} from "../test_utils/CONSTANTS";
+import { sleep } from "../test_utils/generalUtils";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let borrowerTokenAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let noticeSlots = new anchor.BN(5)
+
+
+describe("📞 open-term loan", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    const requestOpenTermLoan = (noticeSlots: anchor.BN) => program.methods
+        .requestOpenTermLoan(nftWorth, requestedAmount, noticeSlots)
+        .accounts({
+            nftMint: nftMint,
+            nftMetadata: nftMetadata,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            pricingTable: pricingTable,
+            nftTokenAccount: borrowerMainNftAccount,
+            borrowNftMint: borrowNftMint,
+            loanRequest: loanRequest.key,
+            borrowNftTokenAccount: borrowNftAccount,
+            requestedTokenMint: requestedTokenMint,
+            currencyConfig: currencyConfig,
+            platformFees: platformFees.key,
+            nftEscrow: nftEscrowTokenAccount.key,
+            borrower: borrower.publicKey,
+            nftTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([borrower])
+        .rpc()
+
+    it("rejects an open-term request without a notice period", async () => {
+        try {
+            await requestOpenTermLoan(new anchor.BN(0));
+            assert.fail()
+        } catch (e) {
+            assert.strictEqual(e.error.errorCode.code, 'InvalidNoticePeriod');
+        }
+    })
+
+    it("grants an open-term loan", async () => {
+        await requestOpenTermLoan(noticeSlots);
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+
+        await program.methods
+            .borrowerWithdrawTokens()
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                borrower: borrower.publicKey,
+                loanTokenEscrow: escrowTokenAccount,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.noticeSlots.toNumber(), noticeSlots.toNumber());
+        assert.equal(loanState.callSlot, null);
+    })
+
+    it("never defaults until called", async () => {
+        await sleep(3000);
+        await program.methods.refreshLoan().accounts({ loan: loan }).rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.status, 1); // TokensWithdrawn
+    })
+
+    it("defaults once the notice period after a call runs out", async () => {
+        await program.methods
+            .callLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                loan: loan,
+                lender: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+
+        const calledLoan = await program.account.loan.fetch(loan);
+        assert.ok(calledLoan.callSlot !== null);
+
+        await sleep(5000);
+        await program.methods.refreshLoan().accounts({ loan: loan }).rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.status, 3); // Defaulted
+    })
+});
@@ -0,0 +1,274 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import { slotsInAYear } from "../test_utils/calculations";
+import {
+    BENCHMARK_RATE_STR,
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+} from "../test_utils/CONSTANTS";
+import { sleep } from "../test_utils/generalUtils";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    getKeypair,
+    getLoanPDA,
+    getMultisigTransactionPdaParams,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    executeMultisigInstruction,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let owner1: anchor.web3.Keypair;
+let owner2: anchor.web3.Keypair;
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let benchmarkRate: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(slotsInAYear())
+const benchmark = 4 * 10;
+const spread = 3 * 10;
+// what the borrower accepts, under the platform's cap
+const maxSpread = 4 * 10;
+const platformMaxSpread = 5 * 10;
+
+
+describe("🌊 floating rate loan", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        owner1 = getKeypair("owner1");
+        owner2 = getKeypair("owner2");
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+        benchmarkRate = await getPdaParams(program as anchor.Program, BENCHMARK_RATE_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    it("sets the benchmark rate through the multisig", async () => {
+        const accounts = [
+            { pubkey: benchmarkRate.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+            { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+        ];
+        const data = program.coder.instruction.encode("set_benchmark_rate", { rate: benchmark });
+
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+
+        await program.methods.createTransaction(program.programId, accounts, data, null)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        await program.methods
+            .approve()
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                owner: owner2.publicKey
+            })
+            .signers([owner2])
+            .rpc()
+
+        await program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts(
+                accounts.map(
+                    account => account.pubkey.equals(multisigPda.key) ?
+                        { ...account, isSigner: false } : account
+                )
+                    .concat({
+                        pubkey: program.programId,
+                        isWritable: false,
+                        isSigner: false,
+                    }))
+            .rpc()
+
+        const benchmarkState = await program.account.benchmarkRate.fetch(benchmarkRate.key);
+        assert.equal(benchmarkState.history[benchmarkState.history.length - 1].rate, benchmark);
+    })
+
+    it("caps the spread through the multisig", async () => {
+        const accounts = [
+            { pubkey: platformFees.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_max_spread", { maxSpread: platformMaxSpread });
+        await executeMultisigInstruction(program as anchor.Program, accounts, data);
+
+        const platformFeesState = await program.account.platformFees.fetch(platformFees.key);
+        assert.equal(platformFeesState.maxSpread, platformMaxSpread);
+    })
+
+    it("grants a loan at benchmark + spread", async () => {
+        const requestAccounts = {
+            nftMint: nftMint,
+            nftMetadata: nftMetadata,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            pricingTable: pricingTable,
+            nftTokenAccount: borrowerMainNftAccount,
+            borrowNftMint: borrowNftMint,
+            loanRequest: loanRequest.key,
+            borrowNftTokenAccount: borrowNftAccount,
+            requestedTokenMint: requestedTokenMint,
+            currencyConfig: currencyConfig,
+            platformFees: platformFees.key,
+            nftEscrow: nftEscrowTokenAccount.key,
+            borrower: borrower.publicKey,
+            nftTokenProgram: TOKEN_PROGRAM_ID,
+        };
+        await assert.rejects(
+            program.methods
+                .requestFloatingLoan(nftWorth, requestedAmount, duration, platformMaxSpread + 1)
+                .accounts(requestAccounts)
+                .signers([borrower])
+                .rpc()
+        );
+
+        await program.methods
+            .requestFloatingLoan(nftWorth, requestedAmount, duration, maxSpread)
+            .accounts(requestAccounts)
+            .signers([borrower])
+            .rpc()
+
+        const grantFloatingLoan = (spread: number) => program.methods
+            .grantFloatingLoan(spread)
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .remainingAccounts([{ pubkey: benchmarkRate.key, isWritable: false, isSigner: false }])
+            .signers([lender])
+            .rpc()
+
+        // the lender can't go past the spread the borrower agreed to
+        try {
+            await grantFloatingLoan(maxSpread + 1);
+            assert.fail()
+        } catch (e) {
+            assert.strictEqual(e.error.errorCode.code, 'SpreadTooHigh');
+        }
+        await grantFloatingLoan(spread);
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.spread, spread);
+        assert.equal(loanState.interestRate, benchmark + spread);
+    })
+
+    it("needs the benchmark rate account to accrue", async () => {
+        await sleep(1000);
+        await assert.rejects(
+            program.methods.refreshLoan().accounts({ loan: loan }).rpc(),
+        );
+
+        await program.methods
+            .refreshLoan()
+            .accounts({ loan: loan })
+            .remainingAccounts([{ pubkey: benchmarkRate.key, isWritable: false, isSigner: false }])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.ok(loanState.outstandingDebt.gte(requestedAmount));
+    })
+});
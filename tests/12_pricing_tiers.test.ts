@@ -0,0 +1,286 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import { slotsInAYear } from "../test_utils/calculations";
+import {
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+    PRICING_TABLE_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    getKeypair,
+    getLoanPDA,
+    getMultisigTransactionPdaParams,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    executeMultisigInstruction,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+} from "../test_utils/walletUtils";
+
+
+let owner1: anchor.web3.Keypair;
+let owner2: anchor.web3.Keypair;
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let pricingTable: anchor.web3.PublicKey;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(slotsInAYear())
+// cheap short low-ltv loans, everything else up to 60% ltv costs more
+const tiers = [
+    { maxLtv: 50 * 10, maxSlotDuration: new anchor.BN(slotsInAYear()), interestRate: 5 * 10, feePercentage: 1 * 10 },
+    { maxLtv: 60 * 10, maxSlotDuration: new anchor.BN("18446744073709551615"), interestRate: 12 * 10, feePercentage: 3 * 10 },
+]
+
+
+describe("🪜 pricing tiers", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        owner1 = getKeypair("owner1");
+        owner2 = getKeypair("owner2");
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+        [pricingTable] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from(PRICING_TABLE_STR), anchor.web3.PublicKey.default.toBuffer()],
+            program.programId,
+        );
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    it("sets the global pricing tiers through the multisig", async () => {
+        const accounts = [
+            { pubkey: pricingTable, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+            { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+        ];
+        const data = program.coder.instruction.encode("set_pricing_tiers", {
+            collection: anchor.web3.PublicKey.default,
+            tiers,
+        });
+
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+
+        await program.methods.createTransaction(program.programId, accounts, data, null)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        await program.methods
+            .approve()
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                owner: owner2.publicKey
+            })
+            .signers([owner2])
+            .rpc()
+
+        await program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts(
+                accounts.map(
+                    account => account.pubkey.equals(multisigPda.key) ?
+                        { ...account, isSigner: false } : account
+                )
+                    .concat({
+                        pubkey: program.programId,
+                        isWritable: false,
+                        isSigner: false,
+                    }))
+            .rpc()
+
+        const pricingState = await program.account.pricingTable.fetch(pricingTable);
+        assert.equal(pricingState.tiers.length, tiers.length);
+    })
+
+    after(async () => {
+        // later suites request loans on the flat PlatformFees terms
+        const data = program.coder.instruction.encode("set_pricing_tiers", {
+            collection: anchor.web3.PublicKey.default,
+            tiers: [],
+        });
+        await executeMultisigInstruction(program as anchor.Program, [
+            { pubkey: pricingTable, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+            { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+        ], data);
+    });
+
+    it("rejects a request that leaves the pricing table out", async () => {
+        const accounts = {
+            nftMint: nftMint,
+            nftMetadata: nftMetadata,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            nftTokenAccount: borrowerMainNftAccount,
+            borrowNftMint: borrowNftMint,
+            loanRequest: loanRequest.key,
+            borrowNftTokenAccount: borrowNftAccount,
+            requestedTokenMint: requestedTokenMint,
+            currencyConfig: currencyConfig,
+            platformFees: platformFees.key,
+            nftEscrow: nftEscrowTokenAccount.key,
+            borrower: borrower.publicKey,
+            nftTokenProgram: TOKEN_PROGRAM_ID,
+        };
+
+        await assert.rejects(
+            program.methods
+                .requestForLoan(nftWorth, requestedAmount, duration)
+                .accounts(accounts)
+                .signers([borrower])
+                .rpc()
+        );
+
+        // nor can another account stand in for it to get the flat terms
+        try {
+            await program.methods
+                .requestForLoan(nftWorth, requestedAmount, duration)
+                .accounts({ ...accounts, pricingTable: anchor.web3.Keypair.generate().publicKey })
+                .signers([borrower])
+                .rpc()
+            assert.fail()
+        } catch (e) {
+            assert.strictEqual(e.error.errorCode.code, 'ConstraintSeeds');
+        }
+    })
+
+    it("prices the request with the matching tier and snapshots it onto the loan", async () => {
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const requestState = await program.account.loanRequest.fetch(loanRequest.key);
+        assert.equal(requestState.interestRate, tiers[0].interestRate);
+        assert.equal(requestState.feePercentage, tiers[0].feePercentage);
+        assert.equal(requestState.ltv, tiers[0].maxLtv);
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.interestRate, tiers[0].interestRate);
+        assert.equal(loanState.feePercentage, tiers[0].feePercentage);
+    })
+});
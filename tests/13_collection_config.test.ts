@@ -0,0 +1,115 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import {
+    COLLECTION_CONFIG_STR,
+    MULTISIG_SEED_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createMint,
+    getKeypair,
+    getMultisigTransactionPdaParams,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+} from "../test_utils/walletUtils";
+
+
+let owner1: anchor.web3.Keypair;
+let owner2: anchor.web3.Keypair;
+
+let collectionMint: anchor.web3.PublicKey;
+
+// PDAs
+let multisigPda: PDAParameters;
+let collectionConfig: PDAParameters;
+
+const params = {
+    allowed: true,
+    maxLtv: 40 * 10,
+    maxSlotDuration: new anchor.BN(1_000_000),
+    minInterestRate: 5 * 10,
+    maxInterestRate: 30 * 10,
+    exposureCap: new anchor.BN(1_000_000),
+}
+
+
+describe("🖼️ collection risk parameters", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        owner1 = getKeypair("owner1");
+        owner2 = getKeypair("owner2");
+
+        collectionMint = await createMint(provider, owner1);
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        collectionConfig = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, COLLECTION_CONFIG_STR, collectionMint);
+    });
+
+    it("registers a collection through the multisig", async () => {
+        const accounts = [
+            { pubkey: collectionConfig.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+            { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+        ];
+        const data = program.coder.instruction.encode("set_collection_config", {
+            collection: collectionMint,
+            params,
+        });
+
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+
+        await program.methods.createTransaction(program.programId, accounts, data, null)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        await program.methods
+            .approve()
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                owner: owner2.publicKey
+            })
+            .signers([owner2])
+            .rpc()
+
+        await program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts(
+                accounts.map(
+                    account => account.pubkey.equals(multisigPda.key) ?
+                        { ...account, isSigner: false } : account
+                )
+                    .concat({
+                        pubkey: program.programId,
+                        isWritable: false,
+                        isSigner: false,
+                    }))
+            .rpc()
+
+        const configState = await program.account.collectionConfig.fetch(collectionConfig.key);
+        assert.ok(configState.collection.equals(collectionMint));
+        assert.equal(configState.allowed, true);
+        assert.equal(configState.maxLtv, params.maxLtv);
+        assert.equal(configState.exposureCap.toNumber(), params.exposureCap.toNumber());
+        assert.equal(configState.exposure.toNumber(), 0);
+    })
+});
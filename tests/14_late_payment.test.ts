@@ -0,0 +1,236 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import {
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+} from "../test_utils/CONSTANTS";
+import { sleep } from "../test_utils/generalUtils";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    executeMultisigInstruction,
+    createMint,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let borrowerTokenAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(5)
+const graceSlots = new anchor.BN(1_000_000)
+const penaltyRate = 50 * 10;
+const lateFee = new anchor.BN(25)
+
+
+describe("⏰ late payment penalty", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+        // covers the late charges on top of the principal
+        await mintTokens(provider, 1_000, requestedTokenMint, lender, borrowerTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    const setLatePaymentTerms = async (graceSlots: anchor.BN, penaltyRate: number, lateFee: anchor.BN) => {
+        const accounts = [
+            { pubkey: platformFees.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_late_payment_terms", { graceSlots, penaltyRate, lateFee });
+        await executeMultisigInstruction(program as anchor.Program, accounts, data);
+    }
+
+    it("sets the late payment terms through the multisig", async () => {
+        await setLatePaymentTerms(graceSlots, penaltyRate, lateFee);
+
+        const platformFeesState = await program.account.platformFees.fetch(platformFees.key);
+        assert.equal(platformFeesState.graceSlots.toNumber(), graceSlots.toNumber());
+        assert.equal(platformFeesState.penaltyRate, penaltyRate);
+    })
+
+    it("snapshots the late payment terms at grant", async () => {
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+
+        await program.methods
+            .borrowerWithdrawTokens()
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                borrower: borrower.publicKey,
+                loanTokenEscrow: escrowTokenAccount,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.graceSlots.toNumber(), graceSlots.toNumber());
+        assert.equal(loanState.penaltyRate, penaltyRate);
+        assert.equal(loanState.lateFee.toNumber(), lateFee.toNumber());
+    })
+
+    it("charges penalty interest and the late fee during the grace period", async () => {
+        await sleep(3000);
+        await program.methods.refreshLoan().accounts({ loan: loan }).rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.status, 1); // TokensWithdrawn, not Defaulted
+        assert.equal(loanState.lateFeeCharged.toNumber(), lateFee.toNumber());
+        assert.ok(loanState.penaltyInterest.gtn(0));
+        assert.ok(loanState.outstandingDebt.gte(requestedAmount.add(lateFee).add(loanState.penaltyInterest)));
+    })
+
+    it("lets the borrower repay late", async () => {
+        await program.methods
+            .repayLoan(requestedAmount.muln(2))
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loanTokenEscrow: escrowTokenAccount,
+                loan: loan,
+                borrowNftMint: borrowNftMint,
+                borrowNftTokenAccount: borrowNftAccount,
+                nftMint: nftMint,
+                nftTokenAccount: borrowerMainNftAccount,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            }).signers([borrower]).rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.status, 2); // Repaid
+        assert.equal(loanState.lateFeeCharged.toNumber(), lateFee.toNumber());
+
+        // later tests expect loans to default right at maturity
+        await setLatePaymentTerms(new anchor.BN(0), 0, new anchor.BN(0));
+    })
+});
@@ -0,0 +1,520 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import {
+    FEE_TREASURY_STR,
+    COLLECTION_CONFIG_STR,
+    GRANT_LOAN_STR,
+    INSURANCE_CLAIM_STR,
+    INSURANCE_FUND_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+    PRICING_TABLE_STR,
+    SPENDING_LIMITS_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    executeMultisigInstruction,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+    createCollection,
+    createVerifiedCollectionNft,
+} from "../test_utils/walletUtils";
+import { sleep } from "../test_utils/generalUtils";
+
+
+let owner1: anchor.web3.Keypair;
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+let feeTreasury: PDAParameters;
+let insuranceFund: PDAParameters;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(5000)
+
+
+describe("🏦 fee treasury", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        owner1 = getKeypair("owner1");
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+        feeTreasury = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, FEE_TREASURY_STR, requestedTokenMint);
+        insuranceFund = await getPdaParams(program as anchor.Program, INSURANCE_FUND_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    const setFeeShares = async (insuranceBps: number) => {
+        const accounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_fee_shares", {
+            weights: [],
+            treasury: anchor.web3.PublicKey.default,
+            treasuryBps: 0,
+            insuranceBps,
+        });
+        await executeMultisigInstruction(program as anchor.Program, accounts, data);
+    }
+
+    it("takes an origination fee on grant", async () => {
+        // a tenth of every fee goes to the insurance fund
+        await setFeeShares(1_000);
+
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+
+        const loanFeeState = await program.account.loanFee.fetch(loanFee);
+        assert.ok(loanFeeState.owners.some(owner => owner.equals(insuranceFund.key)));
+        await setFeeShares(0);
+    })
+
+    it("sweeps the loan fee into the mint's treasury", async () => {
+        const loanFeeState = await program.account.loanFee.fetch(loanFee);
+
+        await program.methods
+            .sweepFees()
+            .accounts({
+                tokenMint: requestedTokenMint,
+                feeTreasury: feeTreasury.key,
+                multisig: multisigPda.key,
+                sweeper: owner1.publicKey,
+            })
+            .remainingAccounts([{ pubkey: loanFee, isWritable: true, isSigner: false }])
+            .signers([owner1])
+            .rpc()
+
+        const treasuryState = await program.account.feeTreasury.fetch(feeTreasury.key);
+        assert.ok(treasuryState.vault.equals(feeEscrowTokenAccount));
+        assert.ok(treasuryState.accruals.some(accrual => accrual.owner.equals(insuranceFund.key)));
+        assert.equal(treasuryState.accruals.length, loanFeeState.owners.length);
+        assert.ok(treasuryState.totalSwept.lte(loanFeeState.amount));
+
+        // drained fee accounts are closed
+        assert.equal(await provider.connection.getAccountInfo(loanFee), null);
+    })
+
+    it("lets an owner claim its accrued share", async () => {
+        const ownerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, owner1);
+        const treasuryState = await program.account.feeTreasury.fetch(feeTreasury.key);
+        const accrued = treasuryState.accruals.find(accrual => accrual.owner.equals(owner1.publicKey)).amount;
+
+        await program.methods
+            .claimFees()
+            .accounts({
+                tokenMint: requestedTokenMint,
+                feeTreasury: feeTreasury.key,
+                feeEscrow: feeEscrowTokenAccount,
+                ownerTokenAccount: ownerTokenAccount,
+                multisig: multisigPda.key,
+                owner: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        const balance = await provider.connection.getTokenAccountBalance(ownerTokenAccount);
+        assert.equal(Number(balance.value.amount), accrued.toNumber());
+
+        await assert.rejects(
+            program.methods
+                .claimFees()
+                .accounts({
+                    tokenMint: requestedTokenMint,
+                    feeTreasury: feeTreasury.key,
+                    feeEscrow: feeEscrowTokenAccount,
+                    ownerTokenAccount: ownerTokenAccount,
+                    multisig: multisigPda.key,
+                    owner: owner1.publicKey,
+                })
+                .signers([owner1])
+                .rpc(),
+        );
+    })
+
+    it("lets owners spend the treasury within their limit without a proposal", async () => {
+        const spendingLimits = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SPENDING_LIMITS_STR, requestedTokenMint);
+        const accounts = [
+            { pubkey: spendingLimits.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+            { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+        ];
+        const data = program.coder.instruction.encode("set_spending_limit", {
+            mint: requestedTokenMint,
+            spender: { owner: { "0": owner1.publicKey } },
+            amount: new anchor.BN(10),
+            periodSlots: new anchor.BN(1_000),
+        });
+        await executeMultisigInstruction(program as anchor.Program, accounts, data);
+
+        const spendingLimitsState = await program.account.spendingLimits.fetch(spendingLimits.key);
+        assert.equal(spendingLimitsState.limits.length, 1);
+        assert.ok(spendingLimitsState.limits[0].amount.eqn(10));
+
+        const ownerTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, owner1.publicKey);
+        const spend = (owner: anchor.web3.Keypair, amount: number) => program.methods
+            .spendTreasury(new anchor.BN(amount))
+            .accounts({
+                tokenMint: requestedTokenMint,
+                feeTreasury: feeTreasury.key,
+                spendingLimits: spendingLimits.key,
+                feeEscrow: feeEscrowTokenAccount,
+                destination: ownerTokenAccount,
+                multisig: multisigPda.key,
+                owner: owner.publicKey,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([owner])
+            .rpc()
+
+        // more than the limit, or without one, needs a proposal
+        await assert.rejects(spend(owner1, 11));
+        await assert.rejects(spend(lender, 1));
+    })
+
+    it("only insures sold loans whose sale fell short of their debt", async () => {
+        const insuranceClaim = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, INSURANCE_CLAIM_STR, loan)).key;
+        // the loan wasn't priced under a collection config and was never sold
+        const collectionConfig = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, COLLECTION_CONFIG_STR, anchor.web3.PublicKey.default)).key;
+
+        await assert.rejects(
+            program.methods
+                .fileInsuranceClaim()
+                .accounts({
+                    lendNftMint: lendNftMint,
+                    lendNftAccount: lendNftAccount,
+                    loan: loan,
+                    loanRequest: loanRequest.key,
+                    collectionConfig: collectionConfig,
+                    currencyConfig: currencyConfig,
+                    tokenMint: requestedTokenMint,
+                    feeTreasury: feeTreasury.key,
+                    feeEscrow: feeEscrowTokenAccount,
+                    insuranceClaim: insuranceClaim,
+                    lenderTokenAccount: lenderTokenAccount,
+                    multisig: multisigPda.key,
+                    lender: lender.publicKey,
+                })
+                .signers([lender])
+                .rpc(),
+        );
+    })
+
+    it("pays the lender of a sold loan the debt the sale didn't cover", async () => {
+        const insuranceCap = new anchor.BN(5);
+        await registerCurrency(program as anchor.Program, requestedTokenMint, {
+            enabled: true,
+            minLoanAmount: new anchor.BN(0),
+            maxLoanAmount: new anchor.BN("18446744073709551615"),
+            debtCeiling: new anchor.BN("18446744073709551615"),
+            feeOverride: null,
+            insuranceCap,
+        });
+
+        // collateral verified in an allowlisted collection
+        const collateralMint = await createMint(provider, borrower);
+        const collateralAccount = await createAssociatedTokenAccount(provider, collateralMint, borrower);
+        await mintTokens(provider, 1, collateralMint, borrower, collateralAccount);
+        const collection = await createCollection(provider, borrower);
+        const metadata = await createVerifiedCollectionNft(provider, collateralMint, borrower, collection);
+        const collectionConfig = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, COLLECTION_CONFIG_STR, collection)).key;
+        const collectionPricingTable = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, PRICING_TABLE_STR, collection)).key;
+        await executeMultisigInstruction(
+            program as anchor.Program,
+            [
+                { pubkey: collectionConfig, isWritable: true, isSigner: false },
+                { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+                { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+                { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+            ],
+            program.coder.instruction.encode("set_collection_config", {
+                collection,
+                params: {
+                    allowed: true,
+                    maxLtv: 50 * 10,
+                    maxSlotDuration: new anchor.BN(1_000_000),
+                    minInterestRate: 0,
+                    maxInterestRate: 100 * 10,
+                    exposureCap: new anchor.BN(1_000_000),
+                },
+            }),
+        );
+        const collectionAccounts = [
+            { pubkey: collectionConfig, isWritable: true, isSigner: false },
+            { pubkey: collectionPricingTable, isWritable: false, isSigner: false },
+        ];
+
+        // a late fee large enough that the debt outgrows the nft's worth
+        const setLateFee = async (lateFee: anchor.BN) => executeMultisigInstruction(
+            program as anchor.Program,
+            [
+                { pubkey: platformFees.key, isWritable: true, isSigner: false },
+                { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            ],
+            program.coder.instruction.encode("set_late_payment_terms", {
+                graceSlots: new anchor.BN(0),
+                penaltyRate: 0,
+                lateFee,
+            }),
+        );
+        await setLateFee(nftWorth);
+
+        const claimBorrowNftMint = await createMint(provider, borrower);
+        const claimBorrowNftAccount = await createAssociatedTokenAccount(provider, claimBorrowNftMint, borrower);
+        const claimLendNftMint = await createMint(provider, lender);
+        const claimLendNftAccount = await createAssociatedTokenAccount(provider, claimLendNftMint, lender);
+        const claimLoanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, claimBorrowNftMint);
+        const claimGrantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, claimLendNftMint);
+        const claimLoan = await getLoanPDA(program as anchor.Program, LOAN_STR, claimLoanRequest.key, claimGrantLoan.key);
+        const claimNftEscrow = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, claimLoanRequest.key);
+        const insuranceClaim = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, INSURANCE_CLAIM_STR, claimLoan)).key;
+
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, new anchor.BN(5))
+            .accounts({
+                nftMint: collateralMint,
+                nftMetadata: metadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: collateralAccount,
+                borrowNftMint: claimBorrowNftMint,
+                loanRequest: claimLoanRequest.key,
+                borrowNftTokenAccount: claimBorrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: claimNftEscrow.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .remainingAccounts(collectionAccounts)
+            .signers([borrower])
+            .rpc()
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: claimLendNftMint,
+                lendNftAccount: claimLendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: claimLoanRequest.key,
+                grantLoanReq: claimGrantLoan.key,
+                platformFees: platformFees.key,
+                loan: claimLoan,
+                loanFee: (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, claimLoan)).key,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .remainingAccounts(collectionAccounts)
+            .signers([lender])
+            .rpc()
+        await setLateFee(new anchor.BN(0));
+
+        const borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower);
+        await program.methods
+            .borrowerWithdrawTokens()
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: claimLoanRequest.key,
+                platformFees: platformFees.key,
+                loan: claimLoan,
+                borrower: borrower.publicKey,
+                loanTokenEscrow: escrowTokenAccount,
+            })
+            .signers([borrower])
+            .rpc()
+
+        // past maturity, with no grace period the loan defaults and the late fee is charged
+        await sleep(3000);
+        await program.methods.refreshLoan().accounts({ loan: claimLoan }).rpc();
+        await program.methods
+            .sellNft()
+            .accounts({
+                lender: lender.publicKey,
+                lendNftMint: claimLendNftMint,
+                lendNftAccount: claimLendNftAccount,
+                loan: claimLoan,
+            })
+            .signers([lender])
+            .rpc()
+
+        const buyer = owner1;
+        const buyerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, buyer);
+        await mintTokens(provider, nftWorth.toNumber(), requestedTokenMint, lender, buyerTokenAccount);
+        await program.methods
+            .buyNft()
+            .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                nftMint: collateralMint,
+                buyerNftAccount: await createAssociatedTokenAccount(provider, collateralMint, buyer),
+                loan: claimLoan,
+                nftEscrow: claimNftEscrow.key,
+                loanTokenEscrow: escrowTokenAccount,
+                platformFees: platformFees.key,
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: buyerTokenAccount,
+                buyer: buyer.publicKey,
+            })
+            .signers([buyer])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(claimLoan);
+        assert.equal(loanState.status, 7);
+        const shortfall = loanState.paidAmount.add(loanState.outstandingDebt).sub(loanState.nftWorth);
+        assert.ok(shortfall.gt(insuranceCap));
+
+        const oldLenderBal = Number((await provider.connection.getTokenAccountBalance(lenderTokenAccount)).value.amount);
+        await program.methods
+            .fileInsuranceClaim()
+            .accounts({
+                lendNftMint: claimLendNftMint,
+                lendNftAccount: claimLendNftAccount,
+                loan: claimLoan,
+                loanRequest: claimLoanRequest.key,
+                collectionConfig: collectionConfig,
+                currencyConfig: currencyConfig,
+                tokenMint: requestedTokenMint,
+                feeTreasury: feeTreasury.key,
+                feeEscrow: feeEscrowTokenAccount,
+                insuranceClaim: insuranceClaim,
+                lenderTokenAccount: lenderTokenAccount,
+                multisig: multisigPda.key,
+                lender: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+
+        // the shortfall is above the per-loan cap, so the cap is paid out
+        const claimState = await program.account.insuranceClaim.fetch(insuranceClaim);
+        assert.ok(claimState.loan.equals(claimLoan));
+        assert.ok(claimState.shortfall.eq(shortfall));
+        assert.ok(claimState.payout.eq(insuranceCap));
+        assert.deepEqual(claimState.reason, { cappedPerLoan: {} });
+        const newLenderBal = Number((await provider.connection.getTokenAccountBalance(lenderTokenAccount)).value.amount);
+        assert.equal(newLenderBal, oldLenderBal + insuranceCap.toNumber());
+    })
+});
@@ -0,0 +1,308 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import {
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+    SYNDICATE_NOTE_MINT_STR,
+    SYNDICATE_STR,
+    SYNDICATE_VAULT_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    executeMultisigInstruction,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//frontends
+let borrowerFrontend: anchor.web3.Keypair;
+let lenderFrontend: anchor.web3.Keypair;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(5000)
+const maxReferralBps = 2_000;
+
+
+describe("🤝 referral fees", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        borrowerFrontend = anchor.web3.Keypair.generate();
+        lenderFrontend = anchor.web3.Keypair.generate();
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    const setMaxReferralFee = async (maxReferralBps: number) => {
+        const accounts = [
+            { pubkey: platformFees.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_max_referral_fee", { maxReferralBps });
+        await executeMultisigInstruction(program as anchor.Program, accounts, data);
+    }
+
+    const requestForLoan = (bps: number) => program.methods
+        .requestForLoanWithReferral(nftWorth, requestedAmount, duration, {
+            referrer: borrowerFrontend.publicKey,
+            bps,
+        })
+        .accounts({
+            nftMint: nftMint,
+            nftMetadata: nftMetadata,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            pricingTable: pricingTable,
+            nftTokenAccount: borrowerMainNftAccount,
+            borrowNftMint: borrowNftMint,
+            loanRequest: loanRequest.key,
+            borrowNftTokenAccount: borrowNftAccount,
+            requestedTokenMint: requestedTokenMint,
+            currencyConfig: currencyConfig,
+            platformFees: platformFees.key,
+            nftEscrow: nftEscrowTokenAccount.key,
+            borrower: borrower.publicKey,
+            nftTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([borrower])
+        .rpc()
+
+    it("caps the referral fee at the multisig's limit", async () => {
+        // referrals are off until the multisig sets a cap
+        await assert.rejects(requestForLoan(500));
+
+        await setMaxReferralFee(maxReferralBps);
+        const platformFeesState = await program.account.platformFees.fetch(platformFees.key);
+        assert.equal(platformFeesState.maxReferralBps, maxReferralBps);
+
+        await assert.rejects(requestForLoan(maxReferralBps + 1));
+    })
+
+    it("splits the platform fee with the borrower's and lender's frontends", async () => {
+        await requestForLoan(500);
+        const loanRequestState = await program.account.loanRequest.fetch(loanRequest.key);
+        assert.ok(loanRequestState.referral.referrer.equals(borrowerFrontend.publicKey));
+
+        await program.methods
+            .grantLoanWithReferral({ referrer: lenderFrontend.publicKey, bps: 1_000 })
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.referrals.length, 2);
+
+        // referrers are fee recipients of their own, claimable like any owner's share
+        const loanFeeState = await program.account.loanFee.fetch(loanFee);
+        const shareOf = (referrer: anchor.web3.PublicKey) =>
+            loanFeeState.shares[loanFeeState.owners.findIndex(owner => owner.equals(referrer))];
+        assert.equal(shareOf(borrowerFrontend.publicKey), 500);
+        assert.equal(shareOf(lenderFrontend.publicKey), 1_000);
+        assert.equal(loanFeeState.shares.reduce((total, bps) => total + bps, 0), 10_000);
+
+        await setMaxReferralFee(0);
+    })
+
+    it("shares the fee on a syndicated loan with the borrower's frontend", async () => {
+        await setMaxReferralFee(maxReferralBps);
+
+        const syndicatedNftMint = await createMint(provider, borrower);
+        const syndicatedNftMetadata = await createNftMetadata(provider, syndicatedNftMint, borrower);
+        const syndicatedNftAccount = await createAssociatedTokenAccount(provider, syndicatedNftMint, borrower);
+        await mintTokens(provider, 1, syndicatedNftMint, borrower, syndicatedNftAccount);
+        const syndicatedBorrowNftMint = await createMint(provider, borrower);
+        const syndicatedBorrowNftAccount = await createAssociatedTokenAccount(provider, syndicatedBorrowNftMint, borrower);
+        const borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower);
+
+        const syndicatedLoanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, syndicatedBorrowNftMint);
+        const syndicate = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SYNDICATE_STR, syndicatedLoanRequest.key);
+        const noteMint = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SYNDICATE_NOTE_MINT_STR, syndicate.key);
+        const vault = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SYNDICATE_VAULT_STR, syndicate.key);
+        const syndicatedLoan = await getLoanPDA(program as anchor.Program, LOAN_STR, syndicatedLoanRequest.key, syndicate.key);
+        const syndicatedLoanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, syndicatedLoan)).key;
+        const syndicatedNftEscrow = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, syndicatedLoanRequest.key);
+
+        await program.methods
+            .requestForLoanWithReferral(nftWorth, requestedAmount, duration, {
+                referrer: borrowerFrontend.publicKey,
+                bps: 500,
+            })
+            .accounts({
+                nftMint: syndicatedNftMint,
+                nftMetadata: syndicatedNftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: syndicatedNftAccount,
+                borrowNftMint: syndicatedBorrowNftMint,
+                loanRequest: syndicatedLoanRequest.key,
+                borrowNftTokenAccount: syndicatedBorrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: syndicatedNftEscrow.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const slot = await provider.connection.getSlot();
+        await program.methods
+            .openSyndicate(new anchor.BN(slot + 10_000))
+            .accounts({
+                loanRequest: syndicatedLoanRequest.key,
+                borrowNftTokenAccount: syndicatedBorrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                syndicate: syndicate.key,
+                noteMint: noteMint.key,
+                vault: vault.key,
+                borrower: borrower.publicKey,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const lenderNoteAccount = await createAssociatedTokenAccount(provider, noteMint.key, lender);
+        await program.methods
+            .fundSyndicate(requestedAmount)
+            .accounts({
+                syndicate: syndicate.key,
+                loanRequest: syndicatedLoanRequest.key,
+                requestedTokenMint: requestedTokenMint,
+                noteMint: noteMint.key,
+                vault: vault.key,
+                lenderTokenAccount: lenderTokenAccount,
+                lenderNoteAccount: lenderNoteAccount,
+                lender: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+
+        await program.methods
+            .drawSyndicatedLoan()
+            .accounts({
+                syndicate: syndicate.key,
+                loanRequest: syndicatedLoanRequest.key,
+                borrowNftTokenAccount: syndicatedBorrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                vault: vault.key,
+                borrowerTokenAccount: borrowerTokenAccount,
+                loan: syndicatedLoan,
+                loanFee: syndicatedLoanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                loanTokenEscrow: escrowTokenAccount,
+                platformFees: platformFees.key,
+                multisig: multisigPda.key,
+                borrower: borrower.publicKey,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const loanState = await program.account.loan.fetch(syndicatedLoan);
+        assert.equal(loanState.referrals.length, 1);
+        const loanFeeState = await program.account.loanFee.fetch(syndicatedLoanFee);
+        const referrerIndex = loanFeeState.owners.findIndex(owner => owner.equals(borrowerFrontend.publicKey));
+        assert.equal(loanFeeState.shares[referrerIndex], 500);
+        assert.equal(loanFeeState.shares.reduce((total, bps) => total + bps, 0), 10_000);
+
+        await setMaxReferralFee(0);
+    })
+});
@@ -1,14 +1,17 @@
 import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
 import assert from "assert";
+import { createHash } from "crypto";
 import { P2pNftLending } from "../target/types/p2p_nft_lending";
 import {
     MULTISIG_SEED_STR,
     PLATFORM_FEES_SEED_STR,
     PLATFORM_LISTING_STR
 } from "../test_utils/CONSTANTS";
+import { sleep } from "../test_utils/generalUtils";
 import { PDAParameters } from "../test_utils/types";
 import {
+    executeMultisigInstruction,
     getKeypair,
     getMultisigTransactionPdaParams,
     getPdaParams,
@@ -184,9 +187,9 @@ describe("🚀 Multisig", () => {
         })
 
 
-        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.seqno);
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
 
-        await program.methods.createTransaction(program.programId, accounts, data)
+        await program.methods.createTransaction(program.programId, accounts, data, null)
             .accounts({
                 multisig: multisigPda.key,
 
@@ -262,9 +265,9 @@ describe("🚀 Multisig", () => {
         //     threshold: 2
         // })
 
-        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.seqno);
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
 
-        await program.methods.createTransaction(program.programId, accounts, data)
+        await program.methods.createTransaction(program.programId, accounts, data, null)
             .accounts({
                 multisig: multisigPda.key,
 
@@ -279,17 +282,20 @@ describe("🚀 Multisig", () => {
         )
 
 
-        assert.ok(txAccount.programId.equals(pid));
-        assert.deepEqual(txAccount.accounts, accounts);
-        assert.deepEqual(txAccount.data, data);
+        assert.equal(txAccount.instructions.length, 1);
+        assert.ok(txAccount.instructions[0].programId.equals(pid));
+        assert.deepEqual(txAccount.instructions[0].accounts, accounts);
+        assert.deepEqual(txAccount.instructions[0].data, data);
         assert.ok(txAccount.multisig.equals(multisigPda.key));
         assert.deepEqual(txAccount.didExecute, false);
         assert.ok(txAccount.seqno === 0);
+        assert.ok(txAccount.index === multisigData.proposalCount);
     })
     it("approve and execute the multisig transaction", async () => {
         const multisigData = await program.account.multisig.fetch(multisigPda.key)
 
-        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.seqno);
+        // the transaction created in the previous test
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount - 1);
 
         await program.methods
             .approve()
@@ -333,4 +339,633 @@ describe("🚀 Multisig", () => {
 
 
     })
+
+    it("keeps several proposals pending at once", async () => {
+        const accounts = [
+            { pubkey: platformFees.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_platform_fees", {
+            feePercentage: fee.toNumber(),
+            interestRate: interest.toNumber(),
+            ltv: ltv.toNumber(),
+        })
+
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const first = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+        const second = await getMultisigTransactionPdaParams(program, multisigData.proposalCount + 1);
+
+        for (const proposal of [first, second]) {
+            await program.methods.createTransaction(program.programId, accounts, data, null)
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: proposal.key,
+                    proposer: owner1.publicKey,
+                })
+                .signers([owner1])
+                .rpc()
+        }
+
+        // executed out of order, each proposal only needs its own approvals
+        for (const proposal of [second, first]) {
+            await program.methods
+                .approve()
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: proposal.key,
+                    owner: owner2.publicKey
+                })
+                .signers([owner2])
+                .rpc()
+
+            await program.methods
+                .executeTransaction()
+                .accounts({
+                    multisig: multisigPda.key,
+                    multisigSigner: multisigPda.key,
+                    transaction: proposal.key,
+                    proposer: owner1.publicKey,
+                })
+                .remainingAccounts(
+                    accounts.map(
+                        account => account.pubkey.equals(multisigPda.key) ?
+                            { ...account, isSigner: false } : account
+                    )
+                        .concat({
+                            pubkey: program.programId,
+                            isWritable: false,
+                            isSigner: false,
+                        }))
+                .rpc()
+
+            // executed proposals are closed
+            assert.equal(await provider.connection.getAccountInfo(proposal.key), null);
+        }
+
+        const multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.equal(multisigAccount.proposalCount, multisigData.proposalCount + 2);
+    })
+
+    it("executes a batch of instructions atomically", async () => {
+        const accounts = [
+            { pubkey: platformFees.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const batch = (graceSlots: number, originationFee: number) => [
+            {
+                programId: program.programId,
+                accounts,
+                data: program.coder.instruction.encode("set_late_payment_terms", {
+                    graceSlots: new anchor.BN(graceSlots),
+                    penaltyRate: 0,
+                    lateFee: new anchor.BN(0),
+                }),
+            },
+            {
+                programId: program.programId,
+                accounts,
+                data: program.coder.instruction.encode("set_fee_model", {
+                    originationFee,
+                    interestFee: 0,
+                }),
+            },
+        ];
+        const proposeAndExecute = async (instructions: any[]) => {
+            const multisigData = await program.account.multisig.fetch(multisigPda.key)
+            const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+
+            await program.methods.createBatchTransaction(instructions, null)
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: owner1.publicKey,
+                })
+                .signers([owner1])
+                .rpc()
+
+            await program.methods
+                .approve()
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    owner: owner2.publicKey
+                })
+                .signers([owner2])
+                .rpc()
+
+            await program.methods
+                .executeTransaction()
+                .accounts({
+                    multisig: multisigPda.key,
+                    multisigSigner: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: owner1.publicKey,
+                })
+                .remainingAccounts(
+                    accounts.map(
+                        account => account.pubkey.equals(multisigPda.key) ?
+                            { ...account, isSigner: false } : account
+                    )
+                        .concat({
+                            pubkey: program.programId,
+                            isWritable: false,
+                            isSigner: false,
+                        }))
+                .rpc()
+        }
+
+        // the invalid fee model fails the whole batch, the grace period isn't set either
+        await assert.rejects(proposeAndExecute(batch(10, 1000)));
+        let platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.ok(platformFeesAccount.graceSlots.eqn(0));
+
+        await proposeAndExecute(batch(0, fee.toNumber()));
+        platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.equal(platformFeesAccount.feePercentage, fee.toNumber());
+        assert.equal(platformFeesAccount.interestFeePercentage, 0);
+    })
+
+    it("sets weighted fee shares and a treasury split", async () => {
+        const setFeeShares = async (weights: number[], treasury: anchor.web3.PublicKey, treasuryBps: number, insuranceBps = 0) => {
+            const accounts = [
+                { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+                { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            ];
+            const data = program.coder.instruction.encode("set_fee_shares", { weights, treasury, treasuryBps, insuranceBps });
+            await executeMultisigInstruction(program as anchor.Program, accounts, data);
+        }
+        const treasury = anchor.web3.Keypair.generate().publicKey;
+        const owners = (await program.account.multisig.fetch(multisigPda.key)).owners;
+        const weights = owners.map((_, i) => i == 0 ? 9_000 - 100 * (owners.length - 1) : 100);
+
+        await setFeeShares(weights, treasury, 1_000);
+
+        let multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.deepEqual(multisigAccount.weights, weights);
+        assert.ok(multisigAccount.treasury.equals(treasury));
+        assert.equal(multisigAccount.treasuryBps, 1_000);
+
+        // back to an equal split for the loan tests
+        await setFeeShares([], anchor.web3.PublicKey.default, 0);
+        multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.deepEqual(multisigAccount.weights, []);
+    })
+
+    it("timelocks approved transactions and lets owners veto them", async () => {
+        const executionDelay = 10;
+        const accounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const setExecutionDelay = (executionDelay: number) =>
+            program.coder.instruction.encode("set_execution_delay", {
+                executionDelay: new anchor.BN(executionDelay),
+            });
+        await executeMultisigInstruction(program as anchor.Program, accounts, setExecutionDelay(executionDelay));
+
+        const propose = async () => {
+            const multisigData = await program.account.multisig.fetch(multisigPda.key)
+            const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+            await program.methods.createTransaction(program.programId, accounts, setExecutionDelay(0), null)
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: owner1.publicKey,
+                })
+                .signers([owner1])
+                .rpc()
+            await program.methods
+                .approve()
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    owner: owner2.publicKey
+                })
+                .signers([owner2])
+                .rpc()
+            return multisigTxPda.key;
+        }
+        const execute = (transaction: anchor.web3.PublicKey) => program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts([
+                { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+                { pubkey: program.programId, isWritable: false, isSigner: false },
+            ])
+            .rpc()
+
+        // owners veto during the delay, the proposer gets the rent back once they reach the
+        // threshold, a single owner's veto isn't enough
+        const vetoed = await propose();
+        assert.ok((await program.account.transaction.fetch(vetoed)).approvedSlot);
+        await assert.rejects(execute(vetoed));
+        const veto = (owner: anchor.web3.Keypair) => program.methods
+            .veto()
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: vetoed,
+                proposer: owner1.publicKey,
+                owner: owner.publicKey,
+            })
+            .signers([owner])
+            .rpc()
+        await veto(owner5);
+        const vetoedAccount = await program.account.transaction.fetch(vetoed);
+        assert.deepEqual(vetoedAccount.vetoes, [false, false, true]);
+        await veto(owner2);
+        assert.equal(await provider.connection.getAccountInfo(vetoed), null);
+
+        // executable once the delay has passed
+        const transaction = await propose();
+        await assert.rejects(execute(transaction));
+        await sleep(executionDelay * 500);
+        await execute(transaction);
+
+        const multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.ok(multisigAccount.executionDelay.eqn(0));
+    })
+
+    it("rejects, revokes and cancels proposals", async () => {
+        const accounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_execution_delay", { executionDelay: new anchor.BN(0) });
+        const propose = async () => {
+            const multisigData = await program.account.multisig.fetch(multisigPda.key)
+            const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+            await program.methods.createTransaction(program.programId, accounts, data, null)
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: owner1.publicKey,
+                })
+                .signers([owner1])
+                .rpc()
+            return multisigTxPda.key;
+        }
+        const vote = (method: string, transaction: anchor.web3.PublicKey, owner: anchor.web3.Keypair) =>
+            program.methods[method]()
+                .accounts({ multisig: multisigPda.key, transaction, owner: owner.publicKey })
+                .signers([owner])
+                .rpc()
+        const cancel = (transaction: anchor.web3.PublicKey, canceller: anchor.web3.Keypair) =>
+            program.methods
+                .cancelTransaction()
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction,
+                    proposer: owner1.publicKey,
+                    canceller: canceller.publicKey,
+                })
+                .signers([canceller])
+                .rpc()
+
+        const votedDown = await propose();
+        await vote("approve", votedDown, owner2);
+        await vote("revokeApproval", votedDown, owner2);
+        let txAccount = await program.account.transaction.fetch(votedDown);
+        assert.deepEqual(txAccount.signers, [true, false, false]);
+
+        // 2 of the 3 owners against, a 2 of 3 threshold can't be reached anymore
+        await vote("reject", votedDown, owner2);
+        await vote("reject", votedDown, owner5);
+        txAccount = await program.account.transaction.fetch(votedDown);
+        assert.ok(txAccount.failed);
+        await assert.rejects(vote("approve", votedDown, owner2));
+
+        // any owner can close a failed proposal, the proposer gets the rent back
+        await cancel(votedDown, owner5);
+        assert.equal(await provider.connection.getAccountInfo(votedDown), null);
+
+        // otherwise only the proposer can cancel
+        const withdrawn = await propose();
+        await assert.rejects(cancel(withdrawn, owner2));
+        await cancel(withdrawn, owner1);
+        assert.equal(await provider.connection.getAccountInfo(withdrawn), null);
+    })
+
+    it("expires stale proposals", async () => {
+        const maxProposalSlots = 20;
+        const accounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const setMaxProposalSlots = (maxProposalSlots: number) =>
+            program.coder.instruction.encode("set_max_proposal_slots", {
+                maxProposalSlots: new anchor.BN(maxProposalSlots),
+            });
+        await executeMultisigInstruction(program as anchor.Program, accounts, setMaxProposalSlots(maxProposalSlots));
+
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+        const propose = (expiresIn: number) => program.methods
+            .createTransaction(program.programId, accounts, setMaxProposalSlots(0), new anchor.BN(expiresIn))
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        await assert.rejects(propose(maxProposalSlots + 1));
+        await propose(2);
+        const txAccount = await program.account.transaction.fetch(multisigTxPda.key);
+        assert.ok(txAccount.expirySlot);
+
+        // nobody can approve it once expired, anyone can close it
+        await sleep(2000);
+        await assert.rejects(
+            program.methods
+                .approve()
+                .accounts({ multisig: multisigPda.key, transaction: multisigTxPda.key, owner: owner2.publicKey })
+                .signers([owner2])
+                .rpc()
+        );
+        await program.methods
+            .closeExpiredTransaction()
+            .accounts({ transaction: multisigTxPda.key, proposer: owner1.publicKey })
+            .rpc()
+        assert.equal(await provider.connection.getAccountInfo(multisigTxPda.key), null);
+
+        await executeMultisigInstruction(program as anchor.Program, accounts, setMaxProposalSlots(0));
+    })
+
+    it("lets a pause guardian role pause new loans on its own threshold", async () => {
+        const multisigAccounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const setRoles = (roles: any[]) => program.coder.instruction.encode("set_roles", { roles });
+        await executeMultisigInstruction(program as anchor.Program, multisigAccounts, setRoles([
+            { role: { pauseGuardian: {} }, members: [owner1.publicKey, owner2.publicKey], threshold: 1 },
+        ]));
+        let multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.equal(multisigAccount.roles.length, 1);
+
+        const accounts = [
+            { pubkey: platformFees.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const setPaused = async (paused: boolean, proposer: anchor.web3.Keypair) => {
+            const multisigData = await program.account.multisig.fetch(multisigPda.key)
+            const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+            const data = program.coder.instruction.encode("set_paused", { paused });
+            await program.methods.createTransaction(program.programId, accounts, data, null)
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: proposer.publicKey,
+                })
+                .signers([proposer])
+                .rpc()
+            // the proposer's approval alone meets the role's threshold
+            await program.methods
+                .executeTransaction()
+                .accounts({
+                    multisig: multisigPda.key,
+                    multisigSigner: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: proposer.publicKey,
+                })
+                .remainingAccounts([
+                    { pubkey: platformFees.key, isWritable: true, isSigner: false },
+                    { pubkey: multisigPda.key, isWritable: false, isSigner: false },
+                    { pubkey: program.programId, isWritable: false, isSigner: false },
+                ])
+                .rpc()
+        }
+
+        // owner5 isn't a pause guardian
+        await assert.rejects(setPaused(true, owner5));
+
+        await setPaused(true, owner1);
+        let platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.ok(platformFeesAccount.paused);
+
+        await setPaused(false, owner2);
+        platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.ok(!platformFeesAccount.paused);
+
+        // back to the owners' threshold for the loan tests
+        await executeMultisigInstruction(program as anchor.Program, multisigAccounts, setRoles([]));
+        multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.deepEqual(multisigAccount.roles, []);
+    })
+
+    it("weighs owner votes", async () => {
+        const accounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const owners = [owner1.publicKey, owner2.publicKey, owner5.publicKey];
+        const setOwnersAndWeights = (voteWeights: number[], threshold: number) =>
+            program.coder.instruction.encode("set_owners_and_weights", {
+                owners,
+                voteWeights,
+                threshold: new anchor.BN(threshold),
+            });
+        await assert.rejects(
+            executeMultisigInstruction(program as anchor.Program, accounts, setOwnersAndWeights([2, 1, 1], 5))
+        );
+        await executeMultisigInstruction(program as anchor.Program, accounts, setOwnersAndWeights([2, 1, 1], 2));
+        let multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.deepEqual(multisigAccount.voteWeights, [2, 1, 1]);
+
+        // owners keep their weight through an owner change, a new owner needs one set explicitly
+        const setOwners = (owners: anchor.web3.PublicKey[]) =>
+            program.coder.instruction.encode("set_owners", { owners });
+        await assert.rejects(
+            executeMultisigInstruction(program as anchor.Program, accounts, setOwners([...owners, owner3.publicKey]))
+        );
+        await executeMultisigInstruction(
+            program as anchor.Program,
+            accounts,
+            setOwners([owner5.publicKey, owner1.publicKey, owner2.publicKey])
+        );
+        multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.deepEqual(multisigAccount.voteWeights, [1, 2, 1]);
+
+        // owner1 carries the threshold on their own
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigAccount.proposalCount);
+        await program.methods.createTransaction(program.programId, accounts, setOwnersAndWeights([], 2), null)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+        await program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts([
+                { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+                { pubkey: program.programId, isWritable: false, isSigner: false },
+            ])
+            .rpc()
+
+        // back to one vote each for the loan tests
+        multisigAccount = await program.account.multisig.fetch(multisigPda.key);
+        assert.deepEqual(multisigAccount.voteWeights, []);
+        assert.ok(multisigAccount.threshold.eqn(2));
+    })
+
+    it("proposes typed admin instructions approvers can decode", async () => {
+        const proposeAndExecute = async (proposal: any) => {
+            const multisigData = await program.account.multisig.fetch(multisigPda.key)
+            const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+            await program.methods.createProposal(proposal, null)
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: owner1.publicKey,
+                })
+                .signers([owner1])
+                .rpc()
+
+            // the arguments read straight from the transaction account
+            const txAccount = await program.account.transaction.fetch(multisigTxPda.key);
+            assert.deepEqual(txAccount.proposal, proposal);
+            assert.deepEqual(txAccount.instructions, []);
+
+            await program.methods
+                .approve()
+                .accounts({
+                    multisig: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    owner: owner2.publicKey
+                })
+                .signers([owner2])
+                .rpc()
+            await program.methods
+                .executeTransaction()
+                .accounts({
+                    multisig: multisigPda.key,
+                    multisigSigner: multisigPda.key,
+                    transaction: multisigTxPda.key,
+                    proposer: owner1.publicKey,
+                })
+                .remainingAccounts([
+                    { pubkey: platformFees.key, isWritable: true, isSigner: false },
+                    { pubkey: multisigPda.key, isWritable: false, isSigner: false },
+                    { pubkey: program.programId, isWritable: false, isSigner: false },
+                ])
+                .rpc()
+        }
+
+        await proposeAndExecute({ setPaused: { paused: true } });
+        let platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.ok(platformFeesAccount.paused);
+
+        await proposeAndExecute({ setPaused: { paused: false } });
+        platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.ok(!platformFeesAccount.paused);
+    })
+
+    it("approves with owner signatures made off-chain", async () => {
+        const accounts = [
+            { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+        ];
+        const data = program.coder.instruction.encode("set_execution_delay", { executionDelay: new anchor.BN(0) });
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+        await program.methods.createTransaction(program.programId, accounts, data, null)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        // transaction key, sha256 of the borsh encoded instructions, expiry slot, vote nonce
+        const txAccount = await program.account.transaction.fetch(multisigTxPda.key);
+        const instructions = txAccount.instructions as any[];
+        const count = Buffer.alloc(4);
+        count.writeUInt32LE(instructions.length);
+        const encoded = Buffer.concat([
+            count,
+            ...instructions.map(ix => program.coder.types.encode("TransactionInstruction", ix)),
+        ]);
+        const expirySlot = new anchor.BN(await provider.connection.getSlot()).addn(100);
+        const approvalMessage = (voteNonce: number) => {
+            const nonce = Buffer.alloc(4);
+            nonce.writeUInt32LE(voteNonce);
+            return Buffer.concat([
+                multisigTxPda.key.toBuffer(),
+                createHash("sha256").update(encoded).digest(),
+                expirySlot.toArrayLike(Buffer, "le", 8),
+                nonce,
+            ]);
+        };
+        const message = approvalMessage(0);
+        const approveWithSignatures = (signer: anchor.web3.Keypair, signed: Buffer) => program.methods
+            .approveWithSignatures(expirySlot)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            })
+            .preInstructions([
+                anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+                    privateKey: signer.secretKey,
+                    message: signed,
+                }),
+            ])
+            .rpc()
+
+        // a non owner's signature or one over another message approves nothing
+        await assert.rejects(approveWithSignatures(anchor.web3.Keypair.generate(), message));
+        await assert.rejects(approveWithSignatures(owner2, Buffer.concat([message, Buffer.from([0])])));
+
+        // submitted by the provider wallet, owner2 never sends a transaction
+        await approveWithSignatures(owner2, message);
+        const approved = await program.account.transaction.fetch(multisigTxPda.key);
+        assert.deepEqual(approved.signers, [true, true, false]);
+        assert.ok(approved.approvedSlot);
+
+        // once owner2 withdraws the approval, the old signature can't be replayed
+        await program.methods
+            .revokeApproval()
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                owner: owner2.publicKey,
+            })
+            .signers([owner2])
+            .rpc()
+        await assert.rejects(approveWithSignatures(owner2, message));
+        const revoked = await program.account.transaction.fetch(multisigTxPda.key);
+        assert.deepEqual(revoked.signers, [true, false, false]);
+        assert.equal(revoked.voteNonce, 1);
+
+        await approveWithSignatures(owner2, approvalMessage(revoked.voteNonce));
+
+        await program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts([
+                { pubkey: multisigPda.key, isWritable: true, isSigner: false },
+                { pubkey: program.programId, isWritable: false, isSigner: false },
+            ])
+            .rpc()
+    })
 });
\ No newline at end of file
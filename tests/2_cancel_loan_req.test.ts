@@ -2,6 +2,7 @@ import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
 import { LAMPORTS_PER_SOL, } from "@solana/web3.js";
 import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { P2pNftLending } from "../target/types/p2p_nft_lending";
 import { maxAllowedAmount, compoundInterest, slotsInAYear, calculateFees } from "../test_utils/calculations";
 import {
@@ -29,6 +30,10 @@ import {
     mintTokens,
     tokenAccountInfo,
     writePublicKey,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
 } from "../test_utils/walletUtils";
 
 
@@ -43,6 +48,8 @@ let newOwners: anchor.web3.PublicKey[];
 
 // Borrower
 let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
 let borrower: anchor.web3.Keypair;
 let borrowerMainNftAccount: anchor.web3.PublicKey;
 let borrowNftMint: anchor.web3.PublicKey;
@@ -52,6 +59,7 @@ let borrowerTokenAccount: anchor.web3.PublicKey;
 // Lender
 let lender: anchor.web3.Keypair;
 let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
 let lenderTokenAccount: anchor.web3.PublicKey;
 let lendNftMint: anchor.web3.PublicKey;
 let lendNftAccount: anchor.web3.PublicKey;
@@ -137,6 +145,7 @@ describe("🚀 cancel loan req", () => {
 
 
         requestedTokenMint = anchor.web3.PublicKey.default;
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
 
         newOwners = [owner1.publicKey, owner2.publicKey, owner5.publicKey];
 
@@ -148,6 +157,8 @@ describe("🚀 cancel loan req", () => {
             borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower);
         }
         nftMint = await createMint(provider, owner2,); //mint main nft
+        nftMetadata = await createNftMetadata(provider, nftMint, owner2);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
 
         writePublicKey(nftMint,"nftMint");
         
@@ -205,12 +216,17 @@ describe("🚀 cancel loan req", () => {
             await program.methods
                 .requestForLoan(nftWorth, requested, duration)
                 .accounts({
+                    nftTokenProgram: TOKEN_PROGRAM_ID,
                     nftMint: nftMint,
+                    nftMetadata: nftMetadata,
+                    tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                    pricingTable: pricingTable,
                     nftTokenAccount: borrowerMainNftAccount,
                     borrowNftMint: borrowNftMint,
                     loanRequest: loanRequest.key,
                     borrowNftTokenAccount: borrowNftAccount,
                     requestedTokenMint: requestedTokenMint,
+                    currencyConfig: currencyConfig,
                     platformFees: platformFees.key,
                     nftEscrow: nftEscrowTokenAccount.key,
                     borrower: borrower.publicKey,
@@ -224,6 +240,46 @@ describe("🚀 cancel loan req", () => {
             assert.strictEqual(error.errorMessage, 'Maximum borrow amount exceeded')
         }
     })
+    it("rejects a loan request without the collateral's own metadata", async () => {
+        const accounts = {
+            nftTokenProgram: TOKEN_PROGRAM_ID,
+            nftMint: nftMint,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            nftTokenAccount: borrowerMainNftAccount,
+            borrowNftMint: borrowNftMint,
+            loanRequest: loanRequest.key,
+            borrowNftTokenAccount: borrowNftAccount,
+            requestedTokenMint: requestedTokenMint,
+            currencyConfig: currencyConfig,
+            platformFees: platformFees.key,
+            pricingTable: pricingTable,
+            nftEscrow: nftEscrowTokenAccount.key,
+            borrower: borrower.publicKey,
+        };
+
+        // leaving the metadata out would skip the collection's risk parameters
+        await assert.rejects(
+            program.methods
+                .requestForLoan(nftWorth, requestedAmount, duration)
+                .accounts(accounts)
+                .signers([borrower])
+                .rpc()
+        );
+
+        const otherNftMint = await createMint(provider, borrower,);
+        const otherNftMetadata = await createNftMetadata(provider, otherNftMint, borrower);
+        try {
+            await program.methods
+                .requestForLoan(nftWorth, requestedAmount, duration)
+                .accounts({ ...accounts, nftMetadata: otherNftMetadata })
+                .signers([borrower])
+                .rpc()
+            assert.fail()
+        } catch (e) {
+            const error = e.error
+            assert.strictEqual(error.errorCode.code, 'ConstraintSeeds');
+        }
+    })
     it(" requests for loan (deposits collateral)", async () => {
 
         const initialMainNftBal = await provider.connection.getTokenAccountBalance(borrowerMainNftAccount,);
@@ -236,12 +292,17 @@ describe("🚀 cancel loan req", () => {
         await program.methods
             .requestForLoan(nftWorth, requestedAmount, duration)
             .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
                 nftTokenAccount: borrowerMainNftAccount,
                 borrowNftMint: borrowNftMint,
                 loanRequest: loanRequest.key,
                 borrowNftTokenAccount: borrowNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 platformFees: platformFees.key,
                 nftEscrow: nftEscrowTokenAccount.key,
                 borrower: borrower.publicKey,
@@ -328,6 +389,7 @@ describe("🚀 cancel loan req", () => {
                     platformFees: platformFees.key,
                     nftEscrow: nftEscrowTokenAccount.key,
                     borrower: borrower.publicKey,
+                    nftTokenProgram: TOKEN_PROGRAM_ID,
                 }
             ).signers([borrower]).rpc();
 
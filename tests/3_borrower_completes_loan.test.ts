@@ -2,10 +2,12 @@ import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
 import { LAMPORTS_PER_SOL, } from "@solana/web3.js";
 import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { P2pNftLending } from "../target/types/p2p_nft_lending";
 import { maxAllowedAmount, compoundInterest, slotsInAYear, calculateFees } from "../test_utils/calculations";
 import {
     GRANT_LOAN_STR,
+    INTEREST_FEE_STR,
     LOAN_FEE_STR,
     LOAN_REQUEST_STR,
     LOAN_STR,
@@ -31,6 +33,10 @@ import {
     mintTokens,
     tokenAccountInfo,
     writePublicKey,
+    registerCurrency,
+    getNftMetadataAddress,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
 } from "../test_utils/walletUtils";
 
 
@@ -45,6 +51,8 @@ let newOwners: anchor.web3.PublicKey[];
 
 // Borrower
 let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
 let borrower: anchor.web3.Keypair;
 let borrowerMainNftAccount: anchor.web3.PublicKey;
 let borrowNftMint: anchor.web3.PublicKey;
@@ -54,6 +62,7 @@ let borrowerTokenAccount: anchor.web3.PublicKey;
 // Lender
 let lender: anchor.web3.Keypair;
 let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
 let lenderTokenAccount: anchor.web3.PublicKey;
 let lendNftMint: anchor.web3.PublicKey;
 let lendNftAccount: anchor.web3.PublicKey;
@@ -67,6 +76,7 @@ let loanRequest: PDAParameters;
 let grantLoan: PDAParameters;
 let loan: anchor.web3.PublicKey;
 let loanFee: anchor.web3.PublicKey;
+let interestFee: anchor.web3.PublicKey;
 
 //escrow
 let nftEscrowTokenAccount: PDAParameters;
@@ -155,6 +165,7 @@ describe("🚀 borrower completes loan", () => {
         //         anchor.web3.LAMPORTS_PER_SOL * 1000,
         //     );
         requestedTokenMint = anchor.web3.PublicKey.default;
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
 
         newOwners = [owner1.publicKey, owner2.publicKey, owner5.publicKey];
 
@@ -166,6 +177,8 @@ describe("🚀 borrower completes loan", () => {
             borrowerTokenAccount = await getAssociatedTokenAddressOnly(requestedTokenMint, borrower.publicKey);
         }
         nftMint = getPublicKey("nftMint"); //mint main nft
+        nftMetadata = await getNftMetadataAddress(nftMint);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
         borrowerMainNftAccount = await getAssociatedTokenAddressOnly(nftMint, borrower.publicKey); // borrower is owner2 
         //set new borrow nft as the previous ownership has been transferred to program 
         // in previous test
@@ -196,6 +209,7 @@ describe("🚀 borrower completes loan", () => {
         grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
         loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
         loanFee = await (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+        interestFee = await (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, INTEREST_FEE_STR, loan)).key;
 
         multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
         platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
@@ -226,12 +240,17 @@ describe("🚀 borrower completes loan", () => {
         await program.methods
             .requestForLoan(nftWorth, requestedAmount, duration)
             .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
                 nftTokenAccount: borrowerMainNftAccount,
                 borrowNftMint: borrowNftMint,
                 loanRequest: loanRequest.key,
                 borrowNftTokenAccount: borrowNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 platformFees: platformFees.key,
                 nftEscrow: nftEscrowTokenAccount.key,
                 borrower: borrower.publicKey,
@@ -245,6 +264,7 @@ describe("🚀 borrower completes loan", () => {
                 lendNftMint: lendNftMint,
                 lendNftAccount: lendNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 requestedTokenAccount: lenderTokenAccount,
                 loanRequest: loanRequest.key,
                 grantLoanReq: grantLoan.key,
@@ -255,6 +275,7 @@ describe("🚀 borrower completes loan", () => {
                 multisig: multisigPda.key,
                 loanTokenEscrow: escrowTokenAccount,
                 lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).
             signers([lender])
             .rpc().catch(err => {
@@ -303,6 +324,7 @@ describe("🚀 borrower completes loan", () => {
         await program.methods
             .borrowerWithdrawTokens()
             .accounts({
+                requestedTokenMint: requestedTokenMint,
                 requestedTokenAccount: borrowerTokenAccount,
                 loanRequest: loanRequest.key,
                 platformFees: platformFees.key,
@@ -356,6 +378,7 @@ describe("🚀 borrower completes loan", () => {
                 loan: loan,
                 loanFee: loanFee,
                 loanFeeEscrow: feeEscrowTokenAccount,
+                tokenMint: requestedTokenMint,
                 multisig: multisigPda.key,
                 adminTokenAccount,
                 admin: owner1.publicKey,
@@ -391,6 +414,7 @@ describe("🚀 borrower completes loan", () => {
                     loan: loan,
                     loanFee: loanFee,
                     loanFeeEscrow: feeEscrowTokenAccount,
+                    tokenMint: requestedTokenMint,
                     multisig: multisigPda.key,
                     adminTokenAccount,
                     admin: owner1.publicKey,
@@ -425,6 +449,8 @@ describe("🚀 borrower completes loan", () => {
         await program.methods
             .repayLoan(requestedAmount.div(new anchor.BN(2)))
             .accounts({
+                requestedTokenMint: requestedTokenMint,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 requestedTokenAccount: borrowerTokenAccount,
                 loanRequest: loanRequest.key,
                 platformFees: platformFees.key,
@@ -435,7 +461,8 @@ describe("🚀 borrower completes loan", () => {
                 nftMint: nftMint,
                 nftTokenAccount: borrowerMainNftAccount,
                 nftEscrow: nftEscrowTokenAccount.key,
-                borrower: borrower.publicKey
+                borrower: borrower.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).signers([borrower]).rpc();
 
         let finalLoanState = await program.account.loan.fetch(loan)
@@ -458,6 +485,8 @@ describe("🚀 borrower completes loan", () => {
         await program.methods
             .repayLoan(requestedAmount)
             .accounts({
+                requestedTokenMint: requestedTokenMint,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 requestedTokenAccount: borrowerTokenAccount,
                 loanRequest: loanRequest.key,
                 platformFees: platformFees.key,
@@ -468,7 +497,8 @@ describe("🚀 borrower completes loan", () => {
                 nftMint: nftMint,
                 nftTokenAccount: borrowerMainNftAccount,
                 nftEscrow: nftEscrowTokenAccount.key,
-                borrower: borrower.publicKey
+                borrower: borrower.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).signers([borrower]).rpc();
         let finalLoanState = await program.account.loan.fetch(loan)
 
@@ -508,6 +538,9 @@ describe("🚀 borrower completes loan", () => {
                 requestedTokenMint: requestedTokenMint,
                 grantLoanReq: grantLoan.key,
                 multisig: multisigPda.key,
+                interestFee: interestFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).
             signers([lender])
             .rpc()
@@ -516,6 +549,8 @@ describe("🚀 borrower completes loan", () => {
 
         const finalLendNftBal = await provider.connection.getTokenAccountBalance(lendNftAccount,);
         assert.equal(parseInt(finalLendNftBal.value.amount), 0);
+        // no interest fee is taken, so none is booked
+        assert.equal(await provider.connection.getAccountInfo(interestFee), null);
 
 
 
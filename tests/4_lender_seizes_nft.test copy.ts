@@ -2,6 +2,7 @@ import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
 import { LAMPORTS_PER_SOL, } from "@solana/web3.js";
 import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { P2pNftLending } from "../target/types/p2p_nft_lending";
 import { maxAllowedAmount, compoundInterest, slotsInAYear, calculateFees, slotsInDuration } from "../test_utils/calculations";
 import {
@@ -31,6 +32,10 @@ import {
     mintTokens,
     tokenAccountInfo,
     writePublicKey,
+    registerCurrency,
+    getNftMetadataAddress,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
 } from "../test_utils/walletUtils";
 
 
@@ -45,6 +50,8 @@ let newOwners: anchor.web3.PublicKey[];
 
 // Borrower
 let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
 let borrower: anchor.web3.Keypair;
 let borrowerMainNftAccount: anchor.web3.PublicKey;
 let borrowNftMint: anchor.web3.PublicKey;
@@ -54,6 +61,7 @@ let borrowerTokenAccount: anchor.web3.PublicKey;
 // Lender
 let lender: anchor.web3.Keypair;
 let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
 let lenderTokenAccount: anchor.web3.PublicKey;
 let lendNftMint: anchor.web3.PublicKey;
 let lendNftAccount: anchor.web3.PublicKey;
@@ -142,6 +150,7 @@ describe("🚀 Lender Seizes NFT", () => {
         await program.provider.sendAndConfirm(tx)
 
         requestedTokenMint = anchor.web3.PublicKey.default;
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
 
         newOwners = [owner1.publicKey, owner2.publicKey, owner5.publicKey];
 
@@ -155,6 +164,8 @@ describe("🚀 Lender Seizes NFT", () => {
         }
 
         nftMint = getPublicKey("nftMint"); //mint main nft
+        nftMetadata = await getNftMetadataAddress(nftMint);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
         borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower); // borrower is owner2 
         //set new borrow nft as the previous ownership has been transferred to program 
         // in previous test
@@ -218,12 +229,17 @@ describe("🚀 Lender Seizes NFT", () => {
         await program.methods
             .requestForLoan(nftWorth, requestedAmount, duration)
             .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
                 nftTokenAccount: borrowerMainNftAccount,
                 borrowNftMint: borrowNftMint,
                 loanRequest: loanRequest.key,
                 borrowNftTokenAccount: borrowNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 platformFees: platformFees.key,
                 nftEscrow: nftEscrowTokenAccount.key,
                 borrower: borrower.publicKey,
@@ -239,6 +255,7 @@ describe("🚀 Lender Seizes NFT", () => {
                 lendNftMint: lendNftMint,
                 lendNftAccount: lendNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 requestedTokenAccount: lenderTokenAccount,
                 loanRequest: loanRequest.key,
                 grantLoanReq: grantLoan.key,
@@ -249,6 +266,7 @@ describe("🚀 Lender Seizes NFT", () => {
                 multisig: multisigPda.key,
                 loanTokenEscrow: escrowTokenAccount,
                 lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).
             signers([lender])
             .rpc().catch(err => {
@@ -298,6 +316,7 @@ describe("🚀 Lender Seizes NFT", () => {
         await program.methods
             .borrowerWithdrawTokens()
             .accounts({
+                requestedTokenMint: requestedTokenMint,
                 requestedTokenAccount: borrowerTokenAccount,
                 loanRequest: loanRequest.key,
                 platformFees: platformFees.key,
@@ -355,6 +374,7 @@ describe("🚀 Lender Seizes NFT", () => {
         await program.methods
             .seizeNft()
             .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 nftMint: nftMint,
                 lenderNftAccount: lenderMainNftAccount,
                 lender: lender.publicKey,
@@ -2,10 +2,12 @@ import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
 import { LAMPORTS_PER_SOL, } from "@solana/web3.js";
 import assert from "assert";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { P2pNftLending } from "../target/types/p2p_nft_lending";
 import { maxAllowedAmount, compoundInterest, slotsInAYear, calculateFees, slotsInDuration } from "../test_utils/calculations";
 import {
     GRANT_LOAN_STR,
+    INTEREST_FEE_STR,
     LOAN_FEE_STR,
     LOAN_REQUEST_STR,
     LOAN_STR,
@@ -31,6 +33,10 @@ import {
     mintTokens,
     tokenAccountInfo,
     writePublicKey,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
 } from "../test_utils/walletUtils";
 
 
@@ -45,6 +51,8 @@ let newOwners: anchor.web3.PublicKey[];
 
 // Borrower
 let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
 let borrower: anchor.web3.Keypair;
 let borrowerMainNftAccount: anchor.web3.PublicKey;
 let borrowNftMint: anchor.web3.PublicKey;
@@ -54,6 +62,7 @@ let borrowerTokenAccount: anchor.web3.PublicKey;
 // Lender
 let lender: anchor.web3.Keypair;
 let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
 let lenderTokenAccount: anchor.web3.PublicKey;
 let lendNftMint: anchor.web3.PublicKey;
 let lendNftAccount: anchor.web3.PublicKey;
@@ -68,6 +77,7 @@ let loanRequest: PDAParameters;
 let grantLoan: PDAParameters;
 let loan: anchor.web3.PublicKey;
 let loanFee: anchor.web3.PublicKey;
+let interestFee: anchor.web3.PublicKey;
 
 //escrow
 let nftEscrowTokenAccount: PDAParameters;
@@ -142,6 +152,7 @@ describe("🚀 Lender Sells NFT", () => {
         await program.provider.sendAndConfirm(tx)
 
         requestedTokenMint = anchor.web3.PublicKey.default;
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
 
         newOwners = [owner1.publicKey, owner2.publicKey, owner5.publicKey];
 
@@ -157,6 +168,8 @@ describe("🚀 Lender Sells NFT", () => {
         //create NFT to 
         //as prev nft has been seized by lender
         nftMint = await createMint(provider, borrower,); //mint main nft
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
         [borrower, borrowerMainNftAccount] = await createAssociatedTokenAccountAndMintTo(provider, 1, nftMint, owner2);
 
         //set new borrow nft as the previous ownership has been transferred to program 
@@ -191,6 +204,7 @@ describe("🚀 Lender Sells NFT", () => {
         grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
         loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
         loanFee = await (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+        interestFee = await (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, INTEREST_FEE_STR, loan)).key;
 
         multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
         platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
@@ -221,12 +235,17 @@ describe("🚀 Lender Sells NFT", () => {
         await program.methods
             .requestForLoan(nftWorth, requestedAmount, duration)
             .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
                 nftTokenAccount: borrowerMainNftAccount,
                 borrowNftMint: borrowNftMint,
                 loanRequest: loanRequest.key,
                 borrowNftTokenAccount: borrowNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 platformFees: platformFees.key,
                 nftEscrow: nftEscrowTokenAccount.key,
                 borrower: borrower.publicKey,
@@ -242,6 +261,7 @@ describe("🚀 Lender Sells NFT", () => {
                 lendNftMint: lendNftMint,
                 lendNftAccount: lendNftAccount,
                 requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
                 requestedTokenAccount: lenderTokenAccount,
                 loanRequest: loanRequest.key,
                 grantLoanReq: grantLoan.key,
@@ -252,6 +272,7 @@ describe("🚀 Lender Sells NFT", () => {
                 multisig: multisigPda.key,
                 loanTokenEscrow: escrowTokenAccount,
                 lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).
             signers([lender])
             .rpc().catch(err => {
@@ -301,6 +322,7 @@ describe("🚀 Lender Sells NFT", () => {
         await program.methods
             .borrowerWithdrawTokens()
             .accounts({
+                requestedTokenMint: requestedTokenMint,
                 requestedTokenAccount: borrowerTokenAccount,
                 loanRequest: loanRequest.key,
                 platformFees: platformFees.key,
@@ -384,6 +406,7 @@ describe("🚀 Lender Sells NFT", () => {
         await program.methods
             .buyNft()
             .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
                 nftMint,
                 buyerNftAccount: buyerNftAccount,
                 loan,
@@ -426,6 +449,9 @@ describe("🚀 Lender Sells NFT", () => {
                 requestedTokenMint: requestedTokenMint,
                 grantLoanReq: grantLoan.key,
                 multisig: multisigPda.key,
+                interestFee: interestFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
             }).
             signers([lender])
             .rpc()
@@ -456,6 +482,7 @@ describe("🚀 Lender Sells NFT", () => {
         await program.methods
             .borrowerWithdrawTokens()
             .accounts({
+                requestedTokenMint: requestedTokenMint,
                 requestedTokenAccount: borrowerTokenAccount,
                 loanRequest: loanRequest.key,
                 platformFees: platformFees.key,
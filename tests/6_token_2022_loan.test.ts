@@ -0,0 +1,359 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import { calculateFees, slotsInAYear } from "../test_utils/calculations";
+import {
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+} from "../test_utils/CONSTANTS";
+import { sleep } from "../test_utils/generalUtils";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    createToken2022Mint,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    getNftMetadataAddress,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+
+// token-2022 mints
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let borrowerTokenAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+// 1% withheld on every transfer of the loan currency
+const transferFeeBasisPoints = 100;
+const transferFee = (amount: number) => Math.ceil(amount * transferFeeBasisPoints / 10_000);
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(7000)
+let duration = new anchor.BN(slotsInAYear())
+
+const tokenBalance = async (provider: anchor.AnchorProvider, account: anchor.web3.PublicKey) =>
+    parseInt((await provider.connection.getTokenAccountBalance(account)).value.amount);
+
+
+describe("🚀 token-2022 loan", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+
+        // collateral and position nfts are token-2022 mints as well
+        nftMint = await createToken2022Mint(provider, borrower);
+        // no metaplex metadata, token-2022 nfts can keep theirs in the mint
+        nftMetadata = await getNftMetadataAddress(nftMint);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower, TOKEN_2022_PROGRAM_ID);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount, TOKEN_2022_PROGRAM_ID);
+
+        borrowNftMint = await createToken2022Mint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower, TOKEN_2022_PROGRAM_ID);
+
+        lendNftMint = await createToken2022Mint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender, TOKEN_2022_PROGRAM_ID);
+
+        requestedTokenMint = await createToken2022Mint(provider, lender, 0, transferFeeBasisPoints);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender, TOKEN_2022_PROGRAM_ID);
+        await mintTokens(provider, 100_000, requestedTokenMint, lender, lenderTokenAccount, TOKEN_2022_PROGRAM_ID);
+        borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower, TOKEN_2022_PROGRAM_ID);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true, TOKEN_2022_PROGRAM_ID);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true, TOKEN_2022_PROGRAM_ID);
+
+        await sleep(100);
+    });
+
+    it("requests a loan with a token-2022 nft as collateral", async () => {
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        assert.equal(await tokenBalance(provider, borrowerMainNftAccount), 0);
+        assert.equal(await tokenBalance(provider, nftEscrowTokenAccount.key), 1);
+        assert.equal(await tokenBalance(provider, borrowNftAccount), 1);
+
+        // priced on the global terms, no collection to apply
+        const loanRequestState = await program.account.loanRequest.fetch(loanRequest.key);
+        assert.ok(loanRequestState.collection.equals(anchor.web3.PublicKey.default));
+    })
+
+    it("lender covers the transfer fee so escrows hold the exact loan amounts", async () => {
+        const initialLenderBal = await tokenBalance(provider, lenderTokenAccount);
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+
+        const platformFeesState = await program.account.platformFees.fetch(platformFees.key);
+        const expectedFee = Math.round(calculateFees(requestedAmount.toNumber(), platformFeesState.feePercentage));
+        const expectedEscrowBalance = requestedAmount.toNumber() - expectedFee;
+
+        assert.equal(await tokenBalance(provider, escrowTokenAccount), expectedEscrowBalance);
+        assert.equal(await tokenBalance(provider, feeEscrowTokenAccount), expectedFee);
+        assert.ok(initialLenderBal - await tokenBalance(provider, lenderTokenAccount) > requestedAmount.toNumber());
+        assert.equal(await tokenBalance(provider, lendNftAccount), 1);
+    })
+
+    it("borrower withdraws the granted amount net of the transfer fee", async () => {
+        const escrowBalance = await tokenBalance(provider, escrowTokenAccount);
+
+        await program.methods
+            .borrowerWithdrawTokens()
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                borrower: borrower.publicKey,
+                loanTokenEscrow: escrowTokenAccount,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        assert.equal(await tokenBalance(provider, escrowTokenAccount), 0);
+        assert.equal(await tokenBalance(provider, borrowerTokenAccount), escrowBalance - transferFee(escrowBalance));
+    })
+
+    it("repayments are credited with the exact amount received by the escrow", async () => {
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, borrowerTokenAccount, TOKEN_2022_PROGRAM_ID);
+
+        await program.methods
+            .repayLoan(requestedAmount.muln(2))
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loanTokenEscrow: escrowTokenAccount,
+                loan: loan,
+                borrowNftMint: borrowNftMint,
+                borrowNftTokenAccount: borrowNftAccount,
+                nftMint: nftMint,
+                nftTokenAccount: borrowerMainNftAccount,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                nftTokenProgram: TOKEN_2022_PROGRAM_ID,
+                positionTokenProgram: TOKEN_2022_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.outstandingDebt.toNumber(), 0);
+        assert.equal(await tokenBalance(provider, escrowTokenAccount), loanState.paidAmount.toNumber());
+        assert.equal(await tokenBalance(provider, borrowerMainNftAccount), 1);
+        assert.equal(await tokenBalance(provider, borrowNftAccount), 0);
+    })
+});
+
+describe("🚀 token-2022 currency with legacy position nfts", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    let nftMint: anchor.web3.PublicKey;
+    let borrowNftMint: anchor.web3.PublicKey;
+    let lendNftMint: anchor.web3.PublicKey;
+    let borrowerMainNftAccount: anchor.web3.PublicKey;
+    let borrowNftAccount: anchor.web3.PublicKey;
+    let lendNftAccount: anchor.web3.PublicKey;
+    let loanRequest: PDAParameters;
+    let grantLoan: PDAParameters;
+    let loan: anchor.web3.PublicKey;
+    let nftEscrowTokenAccount: PDAParameters;
+
+    before(async () => {
+        // the currency stays the token-2022 mint of the previous suite, everything else is spl-token
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+    });
+
+    it("mints and burns the positions with their own token program", async () => {
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+        assert.equal(await tokenBalance(provider, lendNftAccount), 1);
+
+        await program.methods
+            .repayLoan(requestedAmount.muln(2))
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loanTokenEscrow: escrowTokenAccount,
+                loan: loan,
+                borrowNftMint: borrowNftMint,
+                borrowNftTokenAccount: borrowNftAccount,
+                nftMint: nftMint,
+                nftTokenAccount: borrowerMainNftAccount,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        assert.equal(loanState.outstandingDebt.toNumber(), 0);
+        assert.equal(await tokenBalance(provider, borrowerMainNftAccount), 1);
+        assert.equal(await tokenBalance(provider, borrowNftAccount), 0);
+    })
+});
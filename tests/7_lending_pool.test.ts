@@ -0,0 +1,508 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getOrCreateAssociatedTokenAccount, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import {
+    COLLECTION_CONFIG_STR,
+    LENDING_POOL_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+    POOL_SHARE_MINT_STR,
+    POOL_VAULT_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createCollection,
+    createMint,
+    createVerifiedCollectionNft,
+    executeMultisigInstruction,
+    getKeypair,
+    getLoanPDA,
+    getMultisigTransactionPdaParams,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    TOKEN_METADATA_PROGRAM_ID,
+} from "../test_utils/walletUtils";
+import { sleep } from "../test_utils/generalUtils";
+
+
+let owner1: anchor.web3.Keypair;
+let owner2: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+let borrower: anchor.web3.Keypair;
+
+let collectionMint: anchor.web3.PublicKey;
+let currencyMint: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+let lenderShareAccount: anchor.web3.PublicKey;
+let borrowerTokenAccount: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+// PDAs
+let multisigPda: PDAParameters;
+let pool: anchor.web3.PublicKey;
+let shareMint: PDAParameters;
+let vault: PDAParameters;
+let platformFees: PDAParameters;
+let collectionConfig: anchor.web3.PublicKey;
+let loanTokenEscrow: anchor.web3.PublicKey;
+
+//pool config, large enough amounts for interest to accrue within a few slots
+const poolConfig = {
+    nftValuation: new anchor.BN(1_000_000_000_000),
+    ltv: 50 * 10,
+    maxSlotDuration: new anchor.BN(1_000_000),
+    baseRate: 2 * 10,
+    optimalUtilization: 80 * 10,
+    slope1: 8 * 10,
+    slope2: 100 * 10,
+}
+const depositAmount = new anchor.BN(5_000_000_000_000);
+
+const tokenBalance = async (provider: anchor.AnchorProvider, account: anchor.web3.PublicKey) =>
+    parseInt((await provider.connection.getTokenAccountBalance(account)).value.amount);
+
+// the pool's kinked rate curve at `utilization` (3dp percentage)
+const curveRate = (utilization: number) => utilization <= poolConfig.optimalUtilization ?
+    poolConfig.baseRate + Math.floor(poolConfig.slope1 * utilization / poolConfig.optimalUtilization) :
+    poolConfig.baseRate + poolConfig.slope1 +
+    Math.floor(poolConfig.slope2 * (utilization - poolConfig.optimalUtilization) / (1000 - poolConfig.optimalUtilization));
+
+type PoolLoan = {
+    nftMint: anchor.web3.PublicKey,
+    nftAccount: anchor.web3.PublicKey,
+    borrowNftMint: anchor.web3.PublicKey,
+    borrowNftAccount: anchor.web3.PublicKey,
+    loanRequest: anchor.web3.PublicKey,
+    loan: anchor.web3.PublicKey,
+    nftEscrow: anchor.web3.PublicKey,
+};
+
+
+describe("🏦 lending pool", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        owner1 = getKeypair("owner1");
+        owner2 = getKeypair("owner2");
+        lender = getKeypair("owner3");
+        borrower = getKeypair("owner4");
+
+        collectionMint = await createCollection(provider, owner1);
+        currencyMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, currencyMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, currencyMint, lender);
+        await mintTokens(provider, 10_000_000_000_000, currencyMint, lender, lenderTokenAccount);
+        borrowerTokenAccount = await createAssociatedTokenAccount(provider, currencyMint, borrower);
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+        // the collection isn't registered, the pool's own ltv applies
+        collectionConfig = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, COLLECTION_CONFIG_STR, collectionMint)).key;
+        loanTokenEscrow = (await getOrCreateAssociatedTokenAccount(
+            provider.connection, lender, currencyMint, platformFees.key, true,
+        )).address;
+        [pool] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from(LENDING_POOL_STR), collectionMint.toBuffer(), currencyMint.toBuffer()],
+            program.programId,
+        );
+        shareMint = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, POOL_SHARE_MINT_STR, pool);
+        vault = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, POOL_VAULT_STR, pool);
+    });
+
+    it("creates a pool through the multisig", async () => {
+        const accounts = [
+            { pubkey: collectionMint, isWritable: false, isSigner: false },
+            { pubkey: currencyMint, isWritable: false, isSigner: false },
+            { pubkey: pool, isWritable: true, isSigner: false },
+            { pubkey: shareMint.key, isWritable: true, isSigner: false },
+            { pubkey: vault.key, isWritable: true, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: provider.wallet.publicKey, isWritable: true, isSigner: true },
+            { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+            { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
+            { pubkey: anchor.web3.SYSVAR_RENT_PUBKEY, isWritable: false, isSigner: false },
+        ];
+        const data = program.coder.instruction.encode("create_pool", { config: poolConfig });
+
+        const multisigData = await program.account.multisig.fetch(multisigPda.key)
+        const multisigTxPda = await getMultisigTransactionPdaParams(program, multisigData.proposalCount);
+
+        await program.methods.createTransaction(program.programId, accounts, data, null)
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .signers([owner1])
+            .rpc()
+
+        await program.methods
+            .approve()
+            .accounts({
+                multisig: multisigPda.key,
+                transaction: multisigTxPda.key,
+                owner: owner2.publicKey
+            })
+            .signers([owner2])
+            .rpc()
+
+        await program.methods
+            .executeTransaction()
+            .accounts({
+                multisig: multisigPda.key,
+                multisigSigner: multisigPda.key,
+                transaction: multisigTxPda.key,
+                proposer: owner1.publicKey,
+            })
+            .remainingAccounts(
+                accounts.map(
+                    account => account.pubkey.equals(multisigPda.key) ?
+                        { ...account, isSigner: false } : account
+                )
+                    .concat({
+                        pubkey: program.programId,
+                        isWritable: false,
+                        isSigner: false,
+                    }))
+            .rpc()
+
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.ok(poolState.collection.equals(collectionMint));
+        assert.ok(poolState.shareMint.equals(shareMint.key));
+        assert.ok(poolState.vault.equals(vault.key));
+        assert.equal(poolState.ltv, poolConfig.ltv);
+        assert.equal(poolState.availableLiquidity.toNumber(), 0);
+    })
+
+    it("mints shares 1:1 for the first deposit", async () => {
+        lenderShareAccount = await createAssociatedTokenAccount(provider, shareMint.key, lender);
+
+        await program.methods
+            .depositToPool(depositAmount)
+            .accounts({
+                pool: pool,
+                currencyMint: currencyMint,
+                shareMint: shareMint.key,
+                vault: vault.key,
+                lenderTokenAccount: lenderTokenAccount,
+                lenderShareAccount: lenderShareAccount,
+                lender: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.equal(poolState.availableLiquidity.toNumber(), depositAmount.toNumber());
+        assert.equal(await tokenBalance(provider, vault.key), depositAmount.toNumber());
+        assert.equal(await tokenBalance(provider, lenderShareAccount), depositAmount.toNumber());
+    })
+
+    it("burns shares for idle liquidity", async () => {
+        const shares = depositAmount.divn(2);
+        const initialLenderBal = await tokenBalance(provider, lenderTokenAccount);
+
+        await program.methods
+            .withdrawFromPool(shares)
+            .accounts({
+                pool: pool,
+                currencyMint: currencyMint,
+                shareMint: shareMint.key,
+                vault: vault.key,
+                lenderTokenAccount: lenderTokenAccount,
+                lenderShareAccount: lenderShareAccount,
+                lender: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.equal(poolState.availableLiquidity.toNumber(), depositAmount.sub(shares).toNumber());
+        assert.equal(await tokenBalance(provider, lenderShareAccount), depositAmount.sub(shares).toNumber());
+        assert.equal(await tokenBalance(provider, lenderTokenAccount), initialLenderBal + shares.toNumber());
+    })
+
+    // borrows `amount` from the pool against a fresh nft of the collection
+    const borrow = async (amount: anchor.BN, duration: anchor.BN): Promise<PoolLoan> => {
+        const nftMint = await createMint(provider, owner1);
+        const nftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, owner1, nftAccount);
+        const nftMetadata = await createVerifiedCollectionNft(provider, nftMint, owner1, collectionMint);
+
+        const borrowNftMint = await createMint(provider, borrower);
+        const borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+        const loanRequest = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint)).key;
+        const loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest, pool);
+        const nftEscrow = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest)).key;
+
+        await program.methods
+            .borrowFromPool(amount, duration)
+            .accounts({
+                pool: pool,
+                currencyMint: currencyMint,
+                currencyConfig: currencyConfig,
+                vault: vault.key,
+                borrowerTokenAccount: borrowerTokenAccount,
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                nftTokenAccount: nftAccount,
+                borrowNftMint: borrowNftMint,
+                borrowNftTokenAccount: borrowNftAccount,
+                loanRequest: loanRequest,
+                loan: loan,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrow,
+                borrower: borrower.publicKey,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            })
+            .remainingAccounts([{ pubkey: collectionConfig, isWritable: false, isSigner: false }])
+            .signers([borrower])
+            .rpc()
+
+        return { nftMint, nftAccount, borrowNftMint, borrowNftAccount, loanRequest, loan, nftEscrow };
+    }
+
+    const deposit = (amount: anchor.BN) => program.methods
+        .depositToPool(amount)
+        .accounts({
+            pool: pool,
+            currencyMint: currencyMint,
+            shareMint: shareMint.key,
+            vault: vault.key,
+            lenderTokenAccount: lenderTokenAccount,
+            lenderShareAccount: lenderShareAccount,
+            lender: lender.publicKey,
+        })
+        .signers([lender])
+        .rpc()
+
+    const defaultLoan = async (poolLoan: PoolLoan) => {
+        // past maturity, pool loans have no grace period
+        await sleep(3000);
+        await program.methods.refreshLoan().accounts({ loan: poolLoan.loan }).rpc();
+        assert.equal((await program.account.loan.fetch(poolLoan.loan)).status, 3);
+    }
+
+    const sellCollateral = (poolLoan: PoolLoan, lister: anchor.web3.Keypair) => program.methods
+        .sellPoolCollateral()
+        .accounts({
+            pool: pool,
+            loan: poolLoan.loan,
+            multisig: multisigPda.key,
+            lister: lister.publicKey,
+        })
+        .signers([lister])
+        .rpc()
+
+    const settle = (poolLoan: PoolLoan) => program.methods
+        .settlePoolLoan()
+        .accounts({
+            pool: pool,
+            currencyMint: currencyMint,
+            vault: vault.key,
+            loan: poolLoan.loan,
+            platformFees: platformFees.key,
+            loanTokenEscrow: loanTokenEscrow,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc()
+
+    let repaidLoan: PoolLoan;
+    let defaultedLoan: PoolLoan;
+
+    it("draws a loan at the curve's rate for the pool's utilization", async () => {
+        const amount = new anchor.BN(400_000_000_000);
+        const poolBefore = await program.account.lendingPool.fetch(pool);
+        const initialBorrowerBal = await tokenBalance(provider, borrowerTokenAccount);
+
+        repaidLoan = await borrow(amount, new anchor.BN(1_000_000));
+
+        // utilization once the loan is drawn: 4 of the 25 units in the pool are lent out
+        const totalBorrowed = poolBefore.totalBorrowed.add(amount);
+        const utilization = totalBorrowed.muln(1000).div(totalBorrowed.add(poolBefore.availableLiquidity.sub(amount))).toNumber();
+        assert.equal(utilization, 160);
+
+        const loanState = await program.account.loan.fetch(repaidLoan.loan);
+        assert.equal(loanState.interestRate, curveRate(utilization));
+        assert.ok(loanState.lendNftMint.equals(pool));
+        assert.equal(loanState.status, 1);
+        assert.ok(loanState.nftWorth.eq(poolConfig.nftValuation));
+
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.ok(poolState.totalBorrowed.eq(totalBorrowed));
+        assert.ok(poolState.availableLiquidity.eq(poolBefore.availableLiquidity.sub(amount)));
+        assert.equal(await tokenBalance(provider, borrowerTokenAccount), initialBorrowerBal + amount.toNumber());
+        assert.equal(await tokenBalance(provider, repaidLoan.nftEscrow), 1);
+    })
+
+    it("prices new shares above par once interest has accrued", async () => {
+        defaultedLoan = await borrow(new anchor.BN(100_000_000_000), new anchor.BN(5));
+        await sleep(1000);
+
+        const amount = new anchor.BN(1_000_000_000_000);
+        const sharesBefore = await tokenBalance(provider, lenderShareAccount);
+        const liquidityBefore = (await program.account.lendingPool.fetch(pool)).availableLiquidity;
+        await deposit(amount);
+
+        // the interest accrued on the outstanding loans belongs to the existing share holders
+        const minted = await tokenBalance(provider, lenderShareAccount) - sharesBefore;
+        assert.ok(minted > 0 && minted < amount.toNumber());
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.ok(poolState.availableLiquidity.eq(liquidityBefore.add(amount)));
+    })
+
+    it("settles a repaid loan back into the pool", async () => {
+        // enough to cover the accrued interest
+        await mintTokens(provider, 1_000_000_000, currencyMint, lender, borrowerTokenAccount);
+        await program.methods
+            .repayLoan(new anchor.BN(500_000_000_000))
+            .accounts({
+                requestedTokenMint: currencyMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: repaidLoan.loanRequest,
+                platformFees: platformFees.key,
+                loanTokenEscrow: loanTokenEscrow,
+                loan: repaidLoan.loan,
+                borrowNftMint: repaidLoan.borrowNftMint,
+                borrowNftTokenAccount: repaidLoan.borrowNftAccount,
+                nftMint: repaidLoan.nftMint,
+                nftTokenAccount: repaidLoan.nftAccount,
+                nftEscrow: repaidLoan.nftEscrow,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const repaid = await program.account.loan.fetch(repaidLoan.loan);
+        assert.equal(repaid.status, 2);
+        assert.ok(repaid.paidAmount.gt(repaid.requestedAmount));
+        assert.equal(await tokenBalance(provider, repaidLoan.nftAccount), 1);
+
+        const poolBefore = await program.account.lendingPool.fetch(pool);
+        await settle(repaidLoan);
+
+        const settled = await program.account.loan.fetch(repaidLoan.loan);
+        assert.equal(settled.status, 8);
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.ok(poolState.availableLiquidity.eq(poolBefore.availableLiquidity.add(repaid.paidAmount)));
+        assert.ok(poolState.totalBorrowed.eq(poolBefore.totalBorrowed.sub(repaid.requestedAmount)));
+
+        await assert.rejects(settle(repaidLoan));
+    })
+
+    it("lets multisig owners list defaulted collateral, writing the loan down once", async () => {
+        await defaultLoan(defaultedLoan);
+        await assert.rejects(sellCollateral(defaultedLoan, borrower));
+
+        const poolBefore = await program.account.lendingPool.fetch(pool);
+        await sellCollateral(defaultedLoan, owner1);
+
+        assert.equal((await program.account.loan.fetch(defaultedLoan.loan)).status, 6);
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.equal(poolState.pendingSales, 1);
+        assert.ok(poolState.totalBorrowed.eq(poolBefore.totalBorrowed.sub(new anchor.BN(100_000_000_000))));
+
+        // deposits wait for the sale, the loan can't be written down twice
+        await assert.rejects(deposit(new anchor.BN(1_000)));
+        await assert.rejects(sellCollateral(defaultedLoan, owner1));
+    })
+
+    it("settles the sale of defaulted collateral and closes the loan", async () => {
+        const buyer = owner2;
+        const buyerTokenAccount = await createAssociatedTokenAccount(provider, currencyMint, buyer);
+        await mintTokens(provider, poolConfig.nftValuation.toNumber(), currencyMint, lender, buyerTokenAccount);
+        const buyerNftAccount = await createAssociatedTokenAccount(provider, defaultedLoan.nftMint, buyer);
+
+        await program.methods
+            .buyNft()
+            .accounts({
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                nftMint: defaultedLoan.nftMint,
+                buyerNftAccount: buyerNftAccount,
+                loan: defaultedLoan.loan,
+                nftEscrow: defaultedLoan.nftEscrow,
+                loanTokenEscrow: loanTokenEscrow,
+                platformFees: platformFees.key,
+                requestedTokenMint: currencyMint,
+                requestedTokenAccount: buyerTokenAccount,
+                buyer: buyer.publicKey,
+            })
+            .signers([buyer])
+            .rpc()
+        assert.equal(await tokenBalance(provider, buyerNftAccount), 1);
+        assert.equal((await program.account.loan.fetch(defaultedLoan.loan)).status, 7);
+
+        const poolBefore = await program.account.lendingPool.fetch(pool);
+        await settle(defaultedLoan);
+
+        assert.equal((await program.account.loan.fetch(defaultedLoan.loan)).status, 5);
+        const poolState = await program.account.lendingPool.fetch(pool);
+        assert.equal(poolState.pendingSales, 0);
+        assert.ok(poolState.availableLiquidity.eq(poolBefore.availableLiquidity.add(poolConfig.nftValuation)));
+        assert.ok(poolState.totalBorrowed.eq(poolBefore.totalBorrowed));
+
+        // refunding the borrower closes the loan instead of reopening it
+        await program.methods
+            .borrowerWithdrawTokens()
+            .accounts({
+                requestedTokenMint: currencyMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: defaultedLoan.loanRequest,
+                platformFees: platformFees.key,
+                loan: defaultedLoan.loan,
+                borrower: borrower.publicKey,
+                loanTokenEscrow: loanTokenEscrow,
+            })
+            .signers([borrower])
+            .rpc()
+        assert.equal((await program.account.loan.fetch(defaultedLoan.loan)).status, 8);
+        await assert.rejects(program.methods.refreshLoan().accounts({ loan: defaultedLoan.loan }).rpc());
+        await assert.rejects(sellCollateral(defaultedLoan, owner1));
+
+        // deposits reopen once the sale is settled
+        await deposit(new anchor.BN(1_000));
+    })
+
+    it("lets the multisig take unsold collateral out of escrow", async () => {
+        const unsoldLoan = await borrow(new anchor.BN(100_000_000_000), new anchor.BN(5));
+        await defaultLoan(unsoldLoan);
+        await sellCollateral(unsoldLoan, owner1);
+        assert.equal((await program.account.lendingPool.fetch(pool)).pendingSales, 1);
+
+        const destination = await createAssociatedTokenAccount(provider, unsoldLoan.nftMint, owner1);
+        const accounts = [
+            { pubkey: pool, isWritable: true, isSigner: false },
+            { pubkey: unsoldLoan.loan, isWritable: true, isSigner: false },
+            { pubkey: unsoldLoan.loanRequest, isWritable: false, isSigner: false },
+            { pubkey: unsoldLoan.nftMint, isWritable: false, isSigner: false },
+            { pubkey: unsoldLoan.nftEscrow, isWritable: true, isSigner: false },
+            { pubkey: destination, isWritable: true, isSigner: false },
+            { pubkey: platformFees.key, isWritable: false, isSigner: false },
+            { pubkey: multisigPda.key, isWritable: false, isSigner: true },
+            { pubkey: TOKEN_PROGRAM_ID, isWritable: false, isSigner: false },
+        ];
+        const data = program.coder.instruction.encode("seize_pool_collateral", {});
+        await executeMultisigInstruction(program as anchor.Program, accounts, data);
+
+        assert.equal(await tokenBalance(provider, destination), 1);
+        assert.equal(await tokenBalance(provider, unsoldLoan.nftEscrow), 0);
+        assert.equal((await program.account.loan.fetch(unsoldLoan.loan)).status, 4);
+        assert.equal((await program.account.lendingPool.fetch(pool)).pendingSales, 0);
+        await deposit(new anchor.BN(1_000));
+    })
+});
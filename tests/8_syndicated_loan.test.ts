@@ -0,0 +1,292 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import { slotsInAYear } from "../test_utils/calculations";
+import {
+    INTEREST_FEE_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+    SYNDICATE_NOTE_MINT_STR,
+    SYNDICATE_STR,
+    SYNDICATE_VAULT_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let borrower: anchor.web3.Keypair;
+let lenderA: anchor.web3.Keypair;
+let lenderB: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let borrowerTokenAccount: anchor.web3.PublicKey;
+let lenderATokenAccount: anchor.web3.PublicKey;
+let lenderBTokenAccount: anchor.web3.PublicKey;
+let lenderANoteAccount: anchor.web3.PublicKey;
+let lenderBNoteAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let syndicate: PDAParameters;
+let noteMint: PDAParameters;
+let vault: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(slotsInAYear())
+const lenderAShare = new anchor.BN(2500);
+const lenderBShare = requestedAmount.sub(lenderAShare);
+
+const tokenBalance = async (provider: anchor.AnchorProvider, account: anchor.web3.PublicKey) =>
+    parseInt((await provider.connection.getTokenAccountBalance(account)).value.amount);
+
+
+describe("🤝 syndicated loan", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        borrower = getKeypair("owner4");
+        lenderA = getKeypair("owner3");
+        lenderB = getKeypair("owner5");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        requestedTokenMint = await createMint(provider, lenderA);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderATokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lenderA);
+        lenderBTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lenderB);
+        borrowerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, borrower);
+        await mintTokens(provider, 10_000, requestedTokenMint, lenderA, lenderATokenAccount);
+        await mintTokens(provider, 10_000, requestedTokenMint, lenderA, lenderBTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        syndicate = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SYNDICATE_STR, loanRequest.key);
+        noteMint = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SYNDICATE_NOTE_MINT_STR, syndicate.key);
+        vault = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, SYNDICATE_VAULT_STR, syndicate.key);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, syndicate.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+    });
+
+    it("opens a loan request to syndication", async () => {
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const slot = await provider.connection.getSlot();
+        await program.methods
+            .openSyndicate(new anchor.BN(slot + 10_000))
+            .accounts({
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                syndicate: syndicate.key,
+                noteMint: noteMint.key,
+                vault: vault.key,
+                borrower: borrower.publicKey,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const loanRequestState = await program.account.loanRequest.fetch(loanRequest.key);
+        assert.ok(loanRequestState.loan.equals(syndicate.key));
+    })
+
+    it("mints lend notes to each lender for their part", async () => {
+        lenderANoteAccount = await createAssociatedTokenAccount(provider, noteMint.key, lenderA);
+        lenderBNoteAccount = await createAssociatedTokenAccount(provider, noteMint.key, lenderB);
+
+        for (const [lender, tokenAccount, noteAccount, amount] of [
+            [lenderA, lenderATokenAccount, lenderANoteAccount, lenderAShare],
+            [lenderB, lenderBTokenAccount, lenderBNoteAccount, lenderBShare],
+        ] as [anchor.web3.Keypair, anchor.web3.PublicKey, anchor.web3.PublicKey, anchor.BN][]) {
+            await program.methods
+                .fundSyndicate(amount)
+                .accounts({
+                    syndicate: syndicate.key,
+                    loanRequest: loanRequest.key,
+                    requestedTokenMint: requestedTokenMint,
+                    noteMint: noteMint.key,
+                    vault: vault.key,
+                    lenderTokenAccount: tokenAccount,
+                    lenderNoteAccount: noteAccount,
+                    lender: lender.publicKey,
+                })
+                .signers([lender])
+                .rpc()
+            assert.equal(await tokenBalance(provider, noteAccount), amount.toNumber());
+        }
+
+        const syndicateState = await program.account.syndicate.fetch(syndicate.key);
+        assert.equal(syndicateState.fundedAmount.toNumber(), requestedAmount.toNumber());
+        assert.equal(await tokenBalance(provider, vault.key), requestedAmount.toNumber());
+    })
+
+    it("borrower draws the fully funded loan", async () => {
+        await program.methods
+            .drawSyndicatedLoan()
+            .accounts({
+                syndicate: syndicate.key,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                vault: vault.key,
+                borrowerTokenAccount: borrowerTokenAccount,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                loanTokenEscrow: escrowTokenAccount,
+                platformFees: platformFees.key,
+                multisig: multisigPda.key,
+                borrower: borrower.publicKey,
+            })
+            .signers([borrower])
+            .rpc()
+
+        const loanFeeState = await program.account.loanFee.fetch(loanFee);
+        assert.equal(await tokenBalance(provider, vault.key), 0);
+        assert.equal(
+            await tokenBalance(provider, borrowerTokenAccount),
+            requestedAmount.toNumber() - loanFeeState.amount.toNumber()
+        );
+        const loanState = await program.account.loan.fetch(loan);
+        assert.ok(loanState.lendNftMint.equals(syndicate.key));
+    })
+
+    it("note holders claim the repayment pro rata", async () => {
+        await mintTokens(provider, 10_000, requestedTokenMint, lenderA, borrowerTokenAccount);
+
+        await program.methods
+            .repayLoan(requestedAmount.muln(2))
+            .accounts({
+                requestedTokenMint: requestedTokenMint,
+                requestedTokenAccount: borrowerTokenAccount,
+                loanRequest: loanRequest.key,
+                platformFees: platformFees.key,
+                loanTokenEscrow: escrowTokenAccount,
+                loan: loan,
+                borrowNftMint: borrowNftMint,
+                borrowNftTokenAccount: borrowNftAccount,
+                nftMint: nftMint,
+                nftTokenAccount: borrowerMainNftAccount,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc();
+
+        const loanState = await program.account.loan.fetch(loan);
+        const platformFeesAccount = await program.account.platformFees.fetch(platformFees.key);
+        assert.equal(loanState.interestFeePercentage, platformFeesAccount.interestFeePercentage);
+        const paidAmount = loanState.paidAmount.toNumber();
+        const interestFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, INTEREST_FEE_STR, loan)).key;
+
+        let claimed = 0;
+        for (const [lender, tokenAccount, noteAccount, notes] of [
+            [lenderA, lenderATokenAccount, lenderANoteAccount, lenderAShare],
+            [lenderB, lenderBTokenAccount, lenderBNoteAccount, lenderBShare],
+        ] as [anchor.web3.Keypair, anchor.web3.PublicKey, anchor.web3.PublicKey, anchor.BN][]) {
+            const initialBal = await tokenBalance(provider, tokenAccount);
+            await program.methods
+                .claimSyndicate(notes)
+                .accounts({
+                    syndicate: syndicate.key,
+                    loan: loan,
+                    requestedTokenMint: requestedTokenMint,
+                    noteMint: noteMint.key,
+                    platformFees: platformFees.key,
+                    multisig: multisigPda.key,
+                    loanTokenEscrow: escrowTokenAccount,
+                    interestFee: interestFee,
+                    loanFeeEscrow: feeEscrowTokenAccount,
+                    lenderTokenAccount: tokenAccount,
+                    lenderNoteAccount: noteAccount,
+                    lender: lender.publicKey,
+                })
+                .signers([lender])
+                .rpc()
+            const received = await tokenBalance(provider, tokenAccount) - initialBal;
+            assert.ok(Math.abs(received - paidAmount * notes.toNumber() / requestedAmount.toNumber()) <= 1);
+            assert.equal(await tokenBalance(provider, noteAccount), 0);
+            claimed += received;
+        }
+
+        assert.equal(claimed, paidAmount);
+        const completedLoan = await program.account.loan.fetch(loan);
+        assert.deepEqual(completedLoan.status, 5); // Completed
+    })
+});
@@ -0,0 +1,238 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import assert from "assert";
+import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { P2pNftLending } from "../target/types/p2p_nft_lending";
+import { slotsInAYear } from "../test_utils/calculations";
+import {
+    GRANT_LOAN_STR,
+    LOAN_FEE_STR,
+    LOAN_REQUEST_STR,
+    LOAN_STR,
+    MULTISIG_SEED_STR,
+    NFT_ESCROW_STR,
+    PLATFORM_FEES_SEED_STR,
+    POSITION_ESCROW_STR,
+    POSITION_LISTING_STR,
+} from "../test_utils/CONSTANTS";
+import { PDAParameters } from "../test_utils/types";
+import {
+    createAssociatedTokenAccount,
+    createMint,
+    getKeypair,
+    getLoanPDA,
+    getPdaParams,
+    getPdaParamsWithSeedAndPubkey,
+    mintTokens,
+    registerCurrency,
+    createNftMetadata,
+    TOKEN_METADATA_PROGRAM_ID,
+    getGlobalPricingTable,
+} from "../test_utils/walletUtils";
+
+
+let borrower: anchor.web3.Keypair;
+let lender: anchor.web3.Keypair;
+let buyer: anchor.web3.Keypair;
+
+let nftMint: anchor.web3.PublicKey;
+let nftMetadata: anchor.web3.PublicKey;
+let pricingTable: anchor.web3.PublicKey;
+let borrowNftMint: anchor.web3.PublicKey;
+let lendNftMint: anchor.web3.PublicKey;
+let requestedTokenMint: anchor.web3.PublicKey;
+let currencyConfig: anchor.web3.PublicKey;
+
+let borrowerMainNftAccount: anchor.web3.PublicKey;
+let borrowNftAccount: anchor.web3.PublicKey;
+let lendNftAccount: anchor.web3.PublicKey;
+let buyerLendNftAccount: anchor.web3.PublicKey;
+let lenderTokenAccount: anchor.web3.PublicKey;
+let buyerTokenAccount: anchor.web3.PublicKey;
+
+// PDAs
+let platformFees: PDAParameters;
+let multisigPda: PDAParameters;
+let loanRequest: PDAParameters;
+let grantLoan: PDAParameters;
+let listing: PDAParameters;
+let positionEscrow: PDAParameters;
+let loan: anchor.web3.PublicKey;
+let loanFee: anchor.web3.PublicKey;
+
+//escrow
+let nftEscrowTokenAccount: PDAParameters;
+let escrowTokenAccount: anchor.web3.PublicKey;
+let feeEscrowTokenAccount: anchor.web3.PublicKey;
+
+//request loan param
+let nftWorth = new anchor.BN(10000)
+let requestedAmount = new anchor.BN(4000)
+let duration = new anchor.BN(slotsInAYear())
+const price = new anchor.BN(3500);
+
+const tokenBalance = async (provider: anchor.AnchorProvider, account: anchor.web3.PublicKey) =>
+    parseInt((await provider.connection.getTokenAccountBalance(account)).value.amount);
+
+
+describe("🏷️ position marketplace", () => {
+    const provider = anchor.AnchorProvider.env();
+    anchor.setProvider(provider);
+
+    const program = anchor.workspace.P2PNftLending as Program<P2pNftLending>;
+
+    before(async () => {
+        borrower = getKeypair("owner4");
+        lender = getKeypair("owner3");
+        buyer = getKeypair("owner5");
+
+        nftMint = await createMint(provider, borrower);
+        nftMetadata = await createNftMetadata(provider, nftMint, borrower);
+        pricingTable = await getGlobalPricingTable(program as anchor.Program);
+        borrowerMainNftAccount = await createAssociatedTokenAccount(provider, nftMint, borrower);
+        await mintTokens(provider, 1, nftMint, borrower, borrowerMainNftAccount);
+
+        borrowNftMint = await createMint(provider, borrower);
+        borrowNftAccount = await createAssociatedTokenAccount(provider, borrowNftMint, borrower);
+
+        lendNftMint = await createMint(provider, lender);
+        lendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, lender);
+        buyerLendNftAccount = await createAssociatedTokenAccount(provider, lendNftMint, buyer);
+
+        requestedTokenMint = await createMint(provider, lender);
+        currencyConfig = await registerCurrency(program as anchor.Program, requestedTokenMint);
+        lenderTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, lender);
+        buyerTokenAccount = await createAssociatedTokenAccount(provider, requestedTokenMint, buyer);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, lenderTokenAccount);
+        await mintTokens(provider, 10_000, requestedTokenMint, lender, buyerTokenAccount);
+
+        //state
+        loanRequest = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_REQUEST_STR, borrowNftMint);
+        grantLoan = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, GRANT_LOAN_STR, lendNftMint);
+        loan = await getLoanPDA(program as anchor.Program, LOAN_STR, loanRequest.key, grantLoan.key);
+        loanFee = (await getPdaParamsWithSeedAndPubkey(program as anchor.Program, LOAN_FEE_STR, loan)).key;
+        listing = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, POSITION_LISTING_STR, lendNftMint);
+        positionEscrow = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, POSITION_ESCROW_STR, lendNftMint);
+
+        multisigPda = await getPdaParams(program as anchor.Program, MULTISIG_SEED_STR);
+        platformFees = await getPdaParams(program as anchor.Program, PLATFORM_FEES_SEED_STR);
+
+        //Escrow
+        nftEscrowTokenAccount = await getPdaParamsWithSeedAndPubkey(program as anchor.Program, NFT_ESCROW_STR, loanRequest.key);
+        escrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, platformFees.key, true);
+        feeEscrowTokenAccount = await getAssociatedTokenAddress(requestedTokenMint, multisigPda.key, true);
+
+        await program.methods
+            .requestForLoan(nftWorth, requestedAmount, duration)
+            .accounts({
+                nftMint: nftMint,
+                nftMetadata: nftMetadata,
+                tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                pricingTable: pricingTable,
+                nftTokenAccount: borrowerMainNftAccount,
+                borrowNftMint: borrowNftMint,
+                loanRequest: loanRequest.key,
+                borrowNftTokenAccount: borrowNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                platformFees: platformFees.key,
+                nftEscrow: nftEscrowTokenAccount.key,
+                borrower: borrower.publicKey,
+                nftTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([borrower])
+            .rpc()
+
+        await program.methods
+            .grantLoan()
+            .accounts({
+                lendNftMint: lendNftMint,
+                lendNftAccount: lendNftAccount,
+                requestedTokenMint: requestedTokenMint,
+                currencyConfig: currencyConfig,
+                requestedTokenAccount: lenderTokenAccount,
+                loanRequest: loanRequest.key,
+                grantLoanReq: grantLoan.key,
+                platformFees: platformFees.key,
+                loan: loan,
+                loanFee: loanFee,
+                loanFeeEscrow: feeEscrowTokenAccount,
+                multisig: multisigPda.key,
+                loanTokenEscrow: escrowTokenAccount,
+                lender: lender.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([lender])
+            .rpc()
+    });
+
+    const listLendPosition = async () => {
+        await program.methods
+            .listPosition(price)
+            .accounts({
+                loan: loan,
+                positionMint: lendNftMint,
+                sellerPositionAccount: lendNftAccount,
+                listing: listing.key,
+                positionEscrow: positionEscrow.key,
+                seller: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+    }
+
+    it("lists and cancels a lend position", async () => {
+        await listLendPosition();
+
+        const listingState = await program.account.positionListing.fetch(listing.key);
+        assert.ok(listingState.loan.equals(loan));
+        assert.ok(listingState.priceMint.equals(requestedTokenMint));
+        assert.equal(listingState.price.toNumber(), price.toNumber());
+        assert.equal(await tokenBalance(provider, lendNftAccount), 0);
+        assert.equal(await tokenBalance(provider, positionEscrow.key), 1);
+
+        await program.methods
+            .cancelPositionListing()
+            .accounts({
+                listing: listing.key,
+                positionMint: lendNftMint,
+                positionEscrow: positionEscrow.key,
+                sellerPositionAccount: lendNftAccount,
+                seller: lender.publicKey,
+            })
+            .signers([lender])
+            .rpc()
+
+        assert.equal(await tokenBalance(provider, lendNftAccount), 1);
+        assert.equal(await provider.connection.getAccountInfo(listing.key), null);
+    })
+
+    it("buyer takes over the lend position atomically", async () => {
+        await listLendPosition();
+        const initialLenderBal = await tokenBalance(provider, lenderTokenAccount);
+        const initialBuyerBal = await tokenBalance(provider, buyerTokenAccount);
+
+        await program.methods
+            .buyPosition(price)
+            .accounts({
+                listing: listing.key,
+                loan: loan,
+                positionMint: lendNftMint,
+                positionEscrow: positionEscrow.key,
+                buyerPositionAccount: buyerLendNftAccount,
+                priceMint: requestedTokenMint,
+                buyerPaymentAccount: buyerTokenAccount,
+                sellerPaymentAccount: lenderTokenAccount,
+                seller: lender.publicKey,
+                buyer: buyer.publicKey,
+                positionTokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([buyer])
+            .rpc()
+
+        assert.equal(await tokenBalance(provider, buyerLendNftAccount), 1);
+        assert.equal(await tokenBalance(provider, lenderTokenAccount), initialLenderBal + price.toNumber());
+        assert.equal(await tokenBalance(provider, buyerTokenAccount), initialBuyerBal - price.toNumber());
+        assert.equal(await provider.connection.getAccountInfo(positionEscrow.key), null);
+    })
+});